@@ -0,0 +1,175 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::util::read_from_file;
+
+use super::{
+    key_name::KeyName,
+    layout::{ControllerKey, Ps2Key, PS2_KEY_ORDER},
+};
+
+pub const CONTROLLERS_DIR_PATH: &'static str = "controllers";
+pub const PS2_CONTROLLER_ID: &'static str = "ps2";
+
+// The controller types that ship with vert, besides the PS2 controller.
+const BUILTIN_CONTROLLER_DEFS: [&'static str; 6] = [
+    include_str!("../../assets/controllers/gamecube.json"),
+    include_str!("../../assets/controllers/n64.json"),
+    include_str!("../../assets/controllers/xbox.json"),
+    include_str!("../../assets/controllers/snes.json"),
+    include_str!("../../assets/controllers/arcade_stick.json"),
+    include_str!("../../assets/controllers/keyboard.json"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyKind {
+    Button,
+    // A half-axis, such as "Control Stick Left".
+    Axis,
+}
+
+impl Default for KeyKind {
+    fn default() -> Self {
+        KeyKind::Button
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyDef {
+    pub name: KeyName,
+    #[serde(default)]
+    pub kind: KeyKind,
+}
+
+// A named set of related keys, such as a stick or the face buttons.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyGroupDef {
+    pub name: String,
+    pub keys: Vec<KeyDef>,
+}
+
+// A controller type, described as data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControllerDef {
+    // The name identifying this controller type in layout and display files.
+    pub id: KeyName,
+    // The name shown in the UI.
+    pub name: String,
+    pub groups: Vec<KeyGroupDef>,
+}
+
+impl ControllerDef {
+    // The built-in PS2 controller, whose keys are `Ps2Key` values.
+    pub fn ps2() -> ControllerDef {
+        use Ps2Key::*;
+
+        let group = |name: &str, keys: &[Ps2Key], kind: KeyKind| KeyGroupDef {
+            name: name.to_string(),
+            keys: keys
+                .iter()
+                .map(|key| KeyDef {
+                    name: KeyName::new(&format!("{:?}", key)),
+                    kind,
+                })
+                .collect(),
+        };
+
+        ControllerDef {
+            id: KeyName::new(PS2_CONTROLLER_ID),
+            name: "PS2".to_string(),
+            groups: vec![
+                group("D-pad", &[PadU, PadL, PadD, PadR], KeyKind::Button),
+                group("Face", &[Square, Triangle, Circle, Cross], KeyKind::Button),
+                group("Shoulders", &[L1, L2, R1, R2], KeyKind::Button),
+                group("Center", &[Select, Start], KeyKind::Button),
+                group("Stick buttons", &[L3, R3], KeyKind::Button),
+                group(
+                    "Left stick",
+                    &[LeftPosX, LeftNegX, LeftPosY, LeftNegY],
+                    KeyKind::Axis,
+                ),
+                group(
+                    "Right stick",
+                    &[RightPosX, RightNegX, RightPosY, RightNegY],
+                    KeyKind::Axis,
+                ),
+            ],
+        }
+    }
+
+    // The `ControllerKey` identifying the key named `name` of this controller type.
+    pub fn controller_key(&self, name: KeyName) -> ControllerKey {
+        if self.id.as_str() == PS2_CONTROLLER_ID {
+            let ps2_key = PS2_KEY_ORDER
+                .iter()
+                .find(|key| format!("{:?}", key) == name.as_str());
+            if let Some(ps2_key) = ps2_key {
+                return ControllerKey::Ps2(*ps2_key);
+            }
+        }
+
+        ControllerKey::Custom(self.id, name)
+    }
+
+    // Every key of this controller type, in definition order.
+    pub fn keys(&self) -> Vec<ControllerKey> {
+        self.groups
+            .iter()
+            .flat_map(|group| group.keys.iter())
+            .map(|key| self.controller_key(key.name))
+            .collect()
+    }
+}
+
+// The global resource holding every known controller type.
+#[derive(Debug)]
+pub struct ControllerDefsRes {
+    pub defs: Vec<ControllerDef>,
+}
+
+impl ControllerDefsRes {
+    pub fn get(&self, id: KeyName) -> Option<&ControllerDef> {
+        self.defs.iter().find(|def| def.id == id)
+    }
+}
+
+// Load the built-in controller types, followed by those in the controllers directory.
+// A file whose id matches an existing controller type replaces it.
+pub fn read_controller_defs() -> ControllerDefsRes {
+    let mut defs = vec![ControllerDef::ps2()];
+
+    for json in BUILTIN_CONTROLLER_DEFS {
+        match serde_json::from_str::<ControllerDef>(json) {
+            Ok(def) => defs.push(def),
+            Err(e) => println!("Error parsing built-in controller definition: {:?}", e),
+        }
+    }
+
+    let controllers_dir = Path::new(CONTROLLERS_DIR_PATH);
+    if !controllers_dir.is_dir() {
+        fs::create_dir(CONTROLLERS_DIR_PATH);
+    }
+
+    if let Ok(readdir) = fs::read_dir(CONTROLLERS_DIR_PATH) {
+        for entry in readdir {
+            if let Ok(de) = entry {
+                let path = de.path();
+                if path.extension().map_or(true, |ext| ext != "json") {
+                    continue;
+                }
+
+                let path = path.to_str().unwrap();
+                match read_from_file::<ControllerDef>(path) {
+                    Ok(def) => match defs.iter().position(|d| d.id == def.id) {
+                        Some(idx) => defs[idx] = def,
+                        None => defs.push(def),
+                    },
+                    Err(e) => println!("Error reading controller definition '{}': {:?}", path, e),
+                }
+            }
+        }
+    }
+
+    ControllerDefsRes { defs }
+}
@@ -0,0 +1,56 @@
+use std::{fmt, sync::Mutex};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// Every name interned so far. Names are only created from controller definition and layout
+// files, so the set stays small and leaking its strings is fine.
+static INTERNED_NAMES: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+// An interned name of a controller type or key from a controller definition.
+// Interning keeps `ControllerKey` small and `Copy` even though its names come from data files.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct KeyName(&'static str);
+
+impl KeyName {
+    pub fn new(name: &str) -> KeyName {
+        let mut names = INTERNED_NAMES.lock().unwrap();
+
+        match names.iter().find(|interned| **interned == name) {
+            Some(interned) => KeyName(interned),
+            None => {
+                let interned: &'static str = Box::leak(name.to_string().into_boxed_str());
+                names.push(interned);
+                KeyName(interned)
+            }
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl fmt::Debug for KeyName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+impl fmt::Display for KeyName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl Serialize for KeyName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(KeyName::new(&name))
+    }
+}
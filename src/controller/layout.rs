@@ -2,9 +2,16 @@ use bevy::utils::HashMap;
 
 use serde::{Deserialize, Serialize};
 
-use crate::input::input::InputSource;
+use crate::input::{
+    calibration::{AxisCalibration, AxisRange},
+    device::DeviceIdentity,
+    input::{HidId, InputSource},
+    response::InputResponse,
+};
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+use super::{definition::PS2_CONTROLLER_ID, key_name::KeyName};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
 pub enum Ps2Key {
     PadU,
     PadL,
@@ -92,74 +99,839 @@ impl Ps2Key {
 
 const NUM_PS2_KEYS: usize = Ps2Key::RightNegY as usize + 1;
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+// A set of input sources which must all be active at once, such as Shift+Z.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SourceChord {
+    pub sources: Vec<InputSource>,
+}
+
+impl From<InputSource> for SourceChord {
+    fn from(source: InputSource) -> Self {
+        SourceChord {
+            sources: vec![source],
+        }
+    }
+}
+
+impl SourceChord {
+    // Returns `true` if both chords have the same sources, in any order.
+    pub fn same_sources(&self, other: &SourceChord) -> bool {
+        self.sources.len() == other.sources.len()
+            && self.sources.iter().all(|s| other.sources.contains(s))
+    }
+
+    pub fn to_string(&self) -> String {
+        let names: Vec<String> = self.sources.iter().map(|s| s.to_string()).collect();
+        names.join(" + ")
+    }
+}
+
+// The input sources bound to a controller key, along with how their values are processed.
+// The key is active if any of its chords is active.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "BindingDef")]
+pub struct Binding {
+    pub chords: Vec<SourceChord>,
+    pub response: InputResponse,
+    // The observed range of the primary source, if it is an axis that has been calibrated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub calibration: Option<AxisCalibration>,
+}
+
+impl From<SourceChord> for Binding {
+    fn from(chord: SourceChord) -> Self {
+        Binding {
+            chords: vec![chord],
+            response: InputResponse::default(),
+            calibration: None,
+        }
+    }
+}
+
+impl From<InputSource> for Binding {
+    fn from(source: InputSource) -> Self {
+        SourceChord::from(source).into()
+    }
+}
+
+impl Binding {
+    // The first source of the first chord, which is the source that calibration applies to.
+    pub fn primary_source(&self) -> Option<&InputSource> {
+        self.chords.first()?.sources.first()
+    }
+
+    // Every source in every chord of this binding.
+    pub fn sources(&self) -> impl Iterator<Item = &InputSource> {
+        self.chords.iter().flat_map(|chord| chord.sources.iter())
+    }
+
+    pub fn has_source(&self, source: &InputSource) -> bool {
+        self.sources().any(|s| s == source)
+    }
+
+    // Replace the chord at index `slot`, or add a new chord if `slot` is `None`.
+    // The calibration doesn't carry over if the primary source changes.
+    pub fn set_chord(&mut self, slot: Option<usize>, chord: SourceChord) {
+        let old_primary = self.primary_source().copied();
+
+        match slot {
+            Some(idx) if idx < self.chords.len() => self.chords[idx] = chord,
+            _ => self.chords.push(chord),
+        }
+
+        if self.primary_source().copied() != old_primary {
+            self.calibration = None;
+        }
+    }
+
+    pub fn remove_chord(&mut self, idx: usize) {
+        if idx < self.chords.len() {
+            self.chords.remove(idx);
+            self.calibration = None;
+        }
+    }
+
+    pub fn to_string(&self) -> String {
+        let names: Vec<String> = self.chords.iter().map(|c| c.to_string()).collect();
+        names.join(" | ")
+    }
+}
+
+// Older layout files stored a single source for each binding, either bare or alongside its
+// response settings, so accept any of those forms.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BindingDef {
+    Source(InputSource),
+    Full {
+        #[serde(default)]
+        chords: Vec<SourceChord>,
+        #[serde(default)]
+        source: Option<InputSource>,
+        #[serde(default)]
+        response: InputResponse,
+        #[serde(default)]
+        calibration: Option<AxisCalibration>,
+    },
+}
+
+impl From<BindingDef> for Binding {
+    fn from(def: BindingDef) -> Self {
+        match def {
+            BindingDef::Full {
+                mut chords,
+                source,
+                response,
+                calibration,
+            } => {
+                if let Some(source) = source {
+                    chords.insert(0, source.into());
+                }
+
+                Binding {
+                    chords,
+                    response,
+                    calibration,
+                }
+            }
+            BindingDef::Source(source) => source.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Ps2Layout {
-    pub sources: HashMap<Ps2Key, InputSource>,
+    #[serde(alias = "sources")]
+    pub bindings: HashMap<Ps2Key, Binding>,
 }
 
 impl ControllerLayout<Ps2Key> for Ps2Layout {
-    fn get_binding(&self, key: Ps2Key) -> Option<&InputSource> {
-        self.sources.get(&key)
+    fn get_binding(&self, key: Ps2Key) -> Option<&Binding> {
+        self.bindings.get(&key)
     }
 
-    fn set_binding(&mut self, key: Ps2Key, source: &InputSource) {
-        self.sources.insert(key, *source);
+    fn get_binding_mut(&mut self, key: Ps2Key) -> Option<&mut Binding> {
+        self.bindings.get_mut(&key)
     }
 
-    fn is_source_bound(&self, source: &InputSource) -> Option<ControllerKey> {
-        for key in self.sources.keys() {
-            if self.sources.get(key) == Some(source) {
-                return Some(ControllerKey::Ps2(*key));
+    // Bind `chord` to `key`, keeping the response settings of any existing binding.
+    fn set_chord(&mut self, key: Ps2Key, slot: Option<usize>, chord: SourceChord) {
+        match self.bindings.get_mut(&key) {
+            Some(binding) => binding.set_chord(slot, chord),
+            None => {
+                self.bindings.insert(key, chord.into());
             }
         }
+    }
+
+    fn key_bindings(&self) -> Vec<(ControllerKey, &Binding)> {
+        let mut result: Vec<(ControllerKey, &Binding)> = self
+            .bindings
+            .iter()
+            .map(|(key, binding)| (ControllerKey::Ps2(*key), binding))
+            .collect();
+        result.sort_by_key(|(key, _)| *key);
+        result
+    }
+}
+
+// The layout of a controller type loaded from a `ControllerDef`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CustomLayout {
+    // The id of the controller type this layout belongs to.
+    #[serde(skip)]
+    pub controller: Option<KeyName>,
+    pub bindings: HashMap<KeyName, Binding>,
+}
 
-        None
+impl ControllerLayout<KeyName> for CustomLayout {
+    fn get_binding(&self, key: KeyName) -> Option<&Binding> {
+        self.bindings.get(&key)
     }
 
-    fn get_max_key(&self) -> usize {
-        NUM_PS2_KEYS
+    fn get_binding_mut(&mut self, key: KeyName) -> Option<&mut Binding> {
+        self.bindings.get_mut(&key)
+    }
+
+    // Bind `chord` to `key`, keeping the response settings of any existing binding.
+    fn set_chord(&mut self, key: KeyName, slot: Option<usize>, chord: SourceChord) {
+        match self.bindings.get_mut(&key) {
+            Some(binding) => binding.set_chord(slot, chord),
+            None => {
+                self.bindings.insert(key, chord.into());
+            }
+        }
+    }
+
+    fn key_bindings(&self) -> Vec<(ControllerKey, &Binding)> {
+        let controller = match self.controller {
+            Some(controller) => controller,
+            None => return vec![],
+        };
+
+        let mut result: Vec<(ControllerKey, &Binding)> = self
+            .bindings
+            .iter()
+            .map(|(key, binding)| (ControllerKey::Custom(controller, *key), binding))
+            .collect();
+        result.sort_by_key(|(key, _)| *key);
+        result
     }
 }
 
 pub trait ControllerLayout<K> {
-    fn get_binding(&self, key: K) -> Option<&InputSource>;
-    fn set_binding(&mut self, key: K, source: &InputSource);
-    fn is_source_bound(&self, source: &InputSource) -> Option<ControllerKey>;
-    fn get_max_key(&self) -> usize;
+    fn get_binding(&self, key: K) -> Option<&Binding>;
+    fn get_binding_mut(&mut self, key: K) -> Option<&mut Binding>;
+    fn set_chord(&mut self, key: K, slot: Option<usize>, chord: SourceChord);
+
+    // Every bound key with its binding, sorted by key.
+    fn key_bindings(&self) -> Vec<(ControllerKey, &Binding)>;
+
+    // Returns the first key, in sorted order, with a binding that uses `source`.
+    fn is_source_bound(&self, source: &InputSource) -> Option<ControllerKey> {
+        self.key_bindings()
+            .into_iter()
+            .find(|(_, binding)| binding.has_source(source))
+            .map(|(key, _)| key)
+    }
+
+    fn bindings(&self) -> Vec<&Binding> {
+        self.key_bindings()
+            .into_iter()
+            .map(|(_, binding)| binding)
+            .collect()
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
-pub struct ControllerLayoutsRes {
+fn default_controller_type() -> KeyName {
+    KeyName::new(PS2_CONTROLLER_ID)
+}
+
+// The layouts of every controller type for a single player.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "PlayerLayoutDef")]
+pub struct PlayerLayout {
+    // The controller type shown in the controller setup window.
+    pub controller_type: KeyName,
     pub ps2: Ps2Layout,
+    // The layouts of every other controller type, by controller type id.
+    pub custom: HashMap<KeyName, CustomLayout>,
 }
 
-impl ControllerLayoutsRes {
-    pub fn get_binding(&self, key: ControllerKey) -> Option<&InputSource> {
+impl Default for PlayerLayout {
+    fn default() -> Self {
+        PlayerLayout {
+            controller_type: default_controller_type(),
+            ps2: Ps2Layout::default(),
+            custom: HashMap::default(),
+        }
+    }
+}
+
+// Older layout files only contained a PS2 layout.
+#[derive(Deserialize)]
+struct PlayerLayoutDef {
+    #[serde(default = "default_controller_type")]
+    controller_type: KeyName,
+    #[serde(default)]
+    ps2: Ps2Layout,
+    #[serde(default)]
+    custom: HashMap<KeyName, CustomLayout>,
+}
+
+impl From<PlayerLayoutDef> for PlayerLayout {
+    fn from(def: PlayerLayoutDef) -> Self {
+        let mut custom = def.custom;
+
+        // Layouts don't store their own controller type id, so fill it in from the map.
+        for (controller, layout) in custom.iter_mut() {
+            layout.controller = Some(*controller);
+        }
+
+        PlayerLayout {
+            controller_type: def.controller_type,
+            ps2: def.ps2,
+            custom,
+        }
+    }
+}
+
+impl PlayerLayout {
+    pub fn get_binding(&self, key: ControllerKey) -> Option<&Binding> {
         match key {
             ControllerKey::Ps2(ps2_key) => self.ps2.get_binding(ps2_key),
+            ControllerKey::Custom(controller, key) => {
+                self.custom.get(&controller)?.get_binding(key)
+            }
+        }
+    }
+
+    pub fn get_binding_mut(&mut self, key: ControllerKey) -> Option<&mut Binding> {
+        match key {
+            ControllerKey::Ps2(ps2_key) => self.ps2.get_binding_mut(ps2_key),
+            ControllerKey::Custom(controller, key) => {
+                self.custom.get_mut(&controller)?.get_binding_mut(key)
+            }
         }
     }
 
-    pub fn set_binding(&mut self, key: ControllerKey, source: &InputSource) {
+    pub fn set_chord(&mut self, key: ControllerKey, slot: Option<usize>, chord: SourceChord) {
         match key {
-            ControllerKey::Ps2(ps2_key) => self.ps2.set_binding(ps2_key, source),
+            ControllerKey::Ps2(ps2_key) => self.ps2.set_chord(ps2_key, slot, chord),
+            ControllerKey::Custom(controller, key) => {
+                let layout = self.custom.entry(controller).or_default();
+                layout.controller = Some(controller);
+                layout.set_chord(key, slot, chord);
+            }
         }
     }
 
+    // Returns the key bound to `source`, preferring keys of the selected controller type.
     pub fn is_source_bound(&self, source: &InputSource) -> Option<ControllerKey> {
-        self.ps2.is_source_bound(source)
+        let selected = self.custom.get(&self.controller_type);
+        let selected_key = match selected {
+            Some(layout) => layout.is_source_bound(source),
+            None => self.ps2.is_source_bound(source),
+        };
+
+        selected_key.or_else(|| {
+            self.key_bindings()
+                .into_iter()
+                .find(|(_, binding)| binding.has_source(source))
+                .map(|(key, _)| key)
+        })
+    }
+
+    // Every bound key of every controller type with its binding, sorted by key.
+    pub fn key_bindings(&self) -> Vec<(ControllerKey, &Binding)> {
+        let mut result = self.ps2.key_bindings();
+        for layout in self.custom.values() {
+            result.extend(layout.key_bindings());
+        }
+        result.sort_by_key(|(key, _)| *key);
+        result
+    }
+
+    // Every binding of every controller type.
+    pub fn bindings(&self) -> Vec<&Binding> {
+        self.key_bindings()
+            .into_iter()
+            .map(|(_, binding)| binding)
+            .collect()
     }
 }
 
+pub type PlayerId = usize;
+
+// A controller key of a specific player.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "PlayerKeyDef")]
+pub struct PlayerKey {
+    pub player: PlayerId,
+    pub key: ControllerKey,
+}
+
+// Older display and session files stored a bare `ControllerKey`, which belongs to the first player.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PlayerKeyDef {
+    Full {
+        player: PlayerId,
+        key: ControllerKey,
+    },
+    Key(ControllerKey),
+}
+
+impl From<PlayerKeyDef> for PlayerKey {
+    fn from(def: PlayerKeyDef) -> Self {
+        match def {
+            PlayerKeyDef::Full { player, key } => PlayerKey { player, key },
+            PlayerKeyDef::Key(key) => key.into(),
+        }
+    }
+}
+
+impl From<ControllerKey> for PlayerKey {
+    fn from(key: ControllerKey) -> Self {
+        PlayerKey { player: 0, key }
+    }
+}
+
+impl PlayerKey {
+    pub fn new(player: PlayerId, key: ControllerKey) -> PlayerKey {
+        PlayerKey { player, key }
+    }
+
+    pub fn to_string(&self) -> String {
+        format!("P{} {}", self.player + 1, self.key.to_string())
+    }
+}
+
+pub const DEFAULT_PROFILE_NAME: &'static str = "Default";
+
+// A named set of player layouts, such as one per adapter or controller brand.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BindingProfile {
+    pub name: String,
+    // The layouts of each player, indexed by `PlayerId`.
+    pub players: Vec<PlayerLayout>,
+}
+
+impl BindingProfile {
+    pub fn new(name: &str) -> BindingProfile {
+        BindingProfile {
+            name: name.to_string(),
+            players: vec![PlayerLayout::default()],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(try_from = "ControllerLayoutsDef")]
+pub struct ControllerLayoutsRes {
+    pub profiles: Vec<BindingProfile>,
+    // The index of the profile whose bindings are used, in `profiles`.
+    pub active_profile: usize,
+    // The HID devices used by bindings. The `HidId` of a HID source in a binding is an index
+    // into this list, which is resolved to the device's runtime `HidId` by `DeviceMapRes`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub devices: Vec<DeviceIdentity>,
+}
+
+impl Default for ControllerLayoutsRes {
+    fn default() -> Self {
+        ControllerLayoutsRes {
+            profiles: vec![BindingProfile::new(DEFAULT_PROFILE_NAME)],
+            active_profile: 0,
+            devices: vec![],
+        }
+    }
+}
+
+// Older layout files only contained a single profile, or the layout of a single player.
+// Which of these a file holds is decided by its keys, rather than by trying each shape in turn,
+// so that errors in a file are reported instead of it being read as an empty layout.
+#[derive(Deserialize)]
+struct ControllerLayoutsDef {
+    profiles: Option<Vec<BindingProfile>>,
+    #[serde(default)]
+    active_profile: usize,
+    #[serde(default)]
+    devices: Vec<DeviceIdentity>,
+    // A single profile's players.
+    players: Option<Vec<PlayerLayout>>,
+    // A single player's layout.
+    ps2: Option<Ps2Layout>,
+    controller_type: Option<KeyName>,
+    custom: Option<HashMap<KeyName, CustomLayout>>,
+}
+
+impl TryFrom<ControllerLayoutsDef> for ControllerLayoutsRes {
+    type Error = String;
+
+    fn try_from(def: ControllerLayoutsDef) -> Result<Self, Self::Error> {
+        let single_profile = |players: Vec<PlayerLayout>| ControllerLayoutsRes {
+            profiles: vec![BindingProfile {
+                name: DEFAULT_PROFILE_NAME.to_string(),
+                players,
+            }],
+            active_profile: 0,
+            devices: vec![],
+        };
+
+        let mut layouts = match def {
+            ControllerLayoutsDef {
+                profiles: Some(profiles),
+                active_profile,
+                devices,
+                ..
+            } => ControllerLayoutsRes {
+                profiles,
+                active_profile,
+                devices,
+            },
+            ControllerLayoutsDef {
+                players: Some(players),
+                ..
+            } => single_profile(players),
+            ControllerLayoutsDef {
+                ps2: Some(ps2),
+                controller_type,
+                custom,
+                ..
+            } => single_profile(vec![PlayerLayoutDef {
+                controller_type: controller_type.unwrap_or_else(default_controller_type),
+                ps2,
+                custom: custom.unwrap_or_default(),
+            }
+            .into()]),
+            _ => {
+                return Err(
+                    "expected a layouts file with `profiles`, `players` or `ps2`".to_string(),
+                )
+            }
+        };
+
+        // Every profile needs at least one player, and there needs to be an active profile.
+        if layouts.profiles.is_empty() {
+            layouts
+                .profiles
+                .push(BindingProfile::new(DEFAULT_PROFILE_NAME));
+        }
+        for profile in layouts.profiles.iter_mut() {
+            if profile.players.is_empty() {
+                profile.players.push(PlayerLayout::default());
+            }
+        }
+        layouts.active_profile = layouts.active_profile.min(layouts.profiles.len() - 1);
+
+        Ok(layouts)
+    }
+}
+
+impl ControllerLayoutsRes {
+    // Returns the index of `identity` in `devices`, adding it if it isn't there.
+    pub fn add_device(&mut self, identity: DeviceIdentity) -> HidId {
+        match self.devices.iter().position(|d| *d == identity) {
+            Some(idx) => idx,
+            None => {
+                self.devices.push(identity);
+                self.devices.len() - 1
+            }
+        }
+    }
+
+    // The number of `HidId`s used by bindings, in any profile, which aren't in `devices`.
+    // These come from layout files saved before devices were tracked.
+    pub fn num_legacy_devices(&self) -> usize {
+        let max_id = self
+            .profiles
+            .iter()
+            .flat_map(|profile| profile.players.iter())
+            .flat_map(|player| player.bindings())
+            .flat_map(|binding| binding.sources())
+            .filter_map(|source| source.get_hid_id())
+            .max();
+
+        match max_id {
+            Some(id) if id >= self.devices.len() => id + 1 - self.devices.len(),
+            _ => 0,
+        }
+    }
+
+    // Save the identities of the devices used by layouts saved before devices were tracked,
+    // assuming that the `connected` joysticks are still in the order they were then.
+    // Returns `true` if any devices were added.
+    pub fn migrate_legacy_devices(&mut self, connected: &[DeviceIdentity]) -> bool {
+        let num_legacy_devices = self.num_legacy_devices();
+        let start = self.devices.len();
+        for id in start..start + num_legacy_devices {
+            let identity = connected
+                .get(id)
+                .cloned()
+                .unwrap_or_else(|| DeviceIdentity::named(&format!("Joystick {}", id)));
+            self.devices.push(identity);
+        }
+
+        num_legacy_devices > 0
+    }
+
+    // The saved devices used by the active profile's bindings.
+    pub fn bound_devices(&self) -> Vec<HidId> {
+        let mut ids: Vec<HidId> = self
+            .bindings()
+            .into_iter()
+            .flat_map(|binding| binding.sources())
+            .filter_map(|source| source.get_hid_id())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    pub fn profile(&self) -> &BindingProfile {
+        &self.profiles[self.active_profile]
+    }
+
+    fn profile_mut(&mut self) -> &mut BindingProfile {
+        &mut self.profiles[self.active_profile]
+    }
+
+    // Make the profile at `idx` active, returning `true` if the active profile changed.
+    pub fn select_profile(&mut self, idx: usize) -> bool {
+        if idx >= self.profiles.len() || idx == self.active_profile {
+            return false;
+        }
+
+        self.active_profile = idx;
+        true
+    }
+
+    // Add a profile named `name` and make it active.
+    // If `copy_active` is `true`, the new profile starts with the bindings of the active profile.
+    pub fn add_profile(&mut self, name: &str, copy_active: bool) {
+        let profile = if copy_active {
+            BindingProfile {
+                name: name.to_string(),
+                players: self.profile().players.clone(),
+            }
+        } else {
+            BindingProfile::new(name)
+        };
+
+        self.profiles.push(profile);
+        self.active_profile = self.profiles.len() - 1;
+    }
+
+    // Remove the active profile, keeping at least one.
+    pub fn remove_active_profile(&mut self) {
+        if self.profiles.len() > 1 {
+            self.profiles.remove(self.active_profile);
+            self.active_profile = self.active_profile.min(self.profiles.len() - 1);
+        }
+    }
+
+    pub fn rename_active_profile(&mut self, name: &str) {
+        self.profile_mut().name = name.to_string();
+    }
+
+    pub fn player(&self, player: PlayerId) -> Option<&PlayerLayout> {
+        self.profile().players.get(player)
+    }
+
+    pub fn player_mut(&mut self, player: PlayerId) -> Option<&mut PlayerLayout> {
+        self.profile_mut().players.get_mut(player)
+    }
+
+    pub fn num_players(&self) -> usize {
+        self.profile().players.len()
+    }
+
+    pub fn add_player(&mut self) -> PlayerId {
+        let players = &mut self.profile_mut().players;
+        players.push(PlayerLayout::default());
+        players.len() - 1
+    }
+
+    // Remove the last player, keeping at least one.
+    pub fn remove_last_player(&mut self) {
+        let players = &mut self.profile_mut().players;
+        if players.len() > 1 {
+            players.pop();
+        }
+    }
+
+    pub fn get_binding(&self, key: PlayerKey) -> Option<&Binding> {
+        self.player(key.player)?.get_binding(key.key)
+    }
+
+    pub fn get_binding_mut(&mut self, key: PlayerKey) -> Option<&mut Binding> {
+        self.player_mut(key.player)?.get_binding_mut(key.key)
+    }
+
+    // Bind `chord` to `key`, replacing the chord at index `slot` or adding a new one if `None`.
+    pub fn set_chord(&mut self, key: PlayerKey, slot: Option<usize>, chord: SourceChord) {
+        let players = &mut self.profile_mut().players;
+        while players.len() <= key.player {
+            players.push(PlayerLayout::default());
+        }
+        players[key.player].set_chord(key.key, slot, chord);
+    }
+
+    pub fn remove_chord(&mut self, key: PlayerKey, idx: usize) {
+        if let Some(binding) = self.get_binding_mut(key) {
+            binding.remove_chord(idx);
+        }
+    }
+
+    // Calibrate the binding of `key` if its axis was heard moving through its full range,
+    // and `chord` was bound as its primary chord.
+    pub fn set_axis_range(&mut self, key: PlayerKey, chord: &SourceChord, range: AxisRange) {
+        let calibration = match range.calibration() {
+            Some(calibration) => calibration,
+            None => return,
+        };
+
+        if let Some(binding) = self.get_binding_mut(key) {
+            if binding.primary_source() == chord.sources.first() {
+                binding.calibration = Some(calibration);
+            }
+        }
+    }
+
+    // Unbind every chord of `key`, keeping its response settings.
+    pub fn clear_binding(&mut self, key: PlayerKey) {
+        if let Some(binding) = self.get_binding_mut(key) {
+            binding.chords.clear();
+        }
+    }
+
+    // Returns the player key bound to `source`, preferring earlier players.
+    pub fn is_source_bound(&self, source: &InputSource) -> Option<PlayerKey> {
+        self.profile()
+            .players
+            .iter()
+            .enumerate()
+            .find_map(|(player, layout)| {
+                layout
+                    .is_source_bound(source)
+                    .map(|key| PlayerKey::new(player, key))
+            })
+    }
+
+    // Every binding of every player in the active profile.
+    pub fn bindings(&self) -> Vec<&Binding> {
+        self.profile()
+            .players
+            .iter()
+            .flat_map(|layout| layout.bindings())
+            .collect()
+    }
+
+    // Every bound key of every player in the active profile with its binding.
+    pub fn key_bindings(&self) -> Vec<(PlayerKey, &Binding)> {
+        let mut result = vec![];
+        for (player, layout) in self.profile().players.iter().enumerate() {
+            for (key, binding) in layout.key_bindings() {
+                result.push((PlayerKey::new(player, key), binding));
+            }
+        }
+        result
+    }
+
+    // Returns every key in the active profile with a chord of the same sources as `chord`,
+    // along with the index of that chord in the key's binding.
+    pub fn find_chord(&self, chord: &SourceChord) -> Vec<(PlayerKey, usize)> {
+        let mut result = vec![];
+        for (key, binding) in self.key_bindings() {
+            for (idx, bound_chord) in binding.chords.iter().enumerate() {
+                if bound_chord.same_sources(chord) {
+                    result.push((key, idx));
+                }
+            }
+        }
+        result
+    }
+
+    // Returns every chord in the active profile which is bound to more than one key,
+    // along with the keys it's bound to.
+    pub fn duplicated_chords(&self) -> Vec<(SourceChord, Vec<PlayerKey>)> {
+        let mut result: Vec<(SourceChord, Vec<PlayerKey>)> = vec![];
+        for (key, binding) in self.key_bindings() {
+            for chord in binding.chords.iter() {
+                match result.iter_mut().find(|(c, _)| c.same_sources(chord)) {
+                    Some((_, keys)) => {
+                        if !keys.contains(&key) {
+                            keys.push(key);
+                        }
+                    }
+                    None => result.push((chord.clone(), vec![key])),
+                }
+            }
+        }
+
+        result.retain(|(_, keys)| keys.len() > 1);
+        result
+    }
+
+    // Bind `chord` to `key`, resolving conflicts with the keys in `conflicts` (as returned by
+    // `find_chord`) according to `resolution`.
+    pub fn resolve_conflict(
+        &mut self,
+        key: PlayerKey,
+        slot: Option<usize>,
+        chord: SourceChord,
+        conflicts: &[(PlayerKey, usize)],
+        resolution: ConflictResolution,
+    ) {
+        // The chord being replaced, which the conflicting keys get when swapping.
+        let replaced = slot.and_then(|slot| self.get_binding(key)?.chords.get(slot).cloned());
+
+        // Remove chords from the back, so that earlier chord indices stay valid.
+        let mut conflicts = conflicts.to_vec();
+        conflicts.sort_by(|a, b| b.1.cmp(&a.1));
+
+        for &(other_key, idx) in conflicts.iter() {
+            match (resolution, replaced.as_ref()) {
+                (ConflictResolution::AllowDuplicate, _) => {}
+                (ConflictResolution::Swap, Some(replaced)) => {
+                    self.set_chord(other_key, Some(idx), replaced.clone());
+                }
+                (ConflictResolution::Swap, None) | (ConflictResolution::Steal, _) => {
+                    self.remove_chord(other_key, idx);
+                }
+            }
+        }
+
+        self.set_chord(key, slot, chord);
+    }
+}
+
+// How to bind a chord which is already bound to other keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictResolution {
+    // Give the other keys the chord being replaced, or remove the chord from them if
+    // nothing is being replaced.
+    Swap,
+    // Remove the chord from the other keys.
+    Steal,
+    // Leave the chord bound to the other keys as well.
+    AllowDuplicate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum ControllerKey {
     Ps2(Ps2Key),
+    // A key of a data-defined controller type, given by the controller type id and key name.
+    Custom(KeyName, KeyName),
 }
 
 impl ControllerKey {
     pub fn to_string(&self) -> String {
         match self {
             ControllerKey::Ps2(ps2_key) => ps2_key.to_string(),
+            ControllerKey::Custom(_, key) => key.to_string(),
         }
     }
 }
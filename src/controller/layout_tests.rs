@@ -0,0 +1,130 @@
+// Tests of editing controller layouts and the devices their bindings refer to.
+
+use bevy::prelude::KeyCode;
+
+use crate::input::{device::DeviceIdentity, input::InputSource};
+
+use super::layout::{
+    ConflictResolution, ControllerKey, ControllerLayoutsRes, PlayerKey, Ps2Key, SourceChord,
+};
+
+fn ps2_key(key: Ps2Key) -> PlayerKey {
+    PlayerKey::new(0, ControllerKey::Ps2(key))
+}
+
+fn key_chord(key: KeyCode) -> SourceChord {
+    InputSource::Key(key).into()
+}
+
+fn chords(layouts: &ControllerLayoutsRes, key: Ps2Key) -> Vec<SourceChord> {
+    layouts
+        .get_binding(ps2_key(key))
+        .map_or(vec![], |binding| binding.chords.clone())
+}
+
+// Cross is bound to A and Circle to B.
+fn cross_and_circle() -> ControllerLayoutsRes {
+    let mut layouts = ControllerLayoutsRes::default();
+    layouts.set_chord(ps2_key(Ps2Key::Cross), None, key_chord(KeyCode::A));
+    layouts.set_chord(ps2_key(Ps2Key::Circle), None, key_chord(KeyCode::B));
+    layouts
+}
+
+// Bind B to Cross, replacing the chord at `slot`, and resolve its conflict with Circle.
+fn bind_b_to_cross(
+    layouts: &mut ControllerLayoutsRes,
+    slot: Option<usize>,
+    resolution: ConflictResolution,
+) {
+    let chord = key_chord(KeyCode::B);
+    let conflicts = layouts.find_chord(&chord);
+    assert_eq!(conflicts, vec![(ps2_key(Ps2Key::Circle), 0)]);
+
+    layouts.resolve_conflict(ps2_key(Ps2Key::Cross), slot, chord, &conflicts, resolution);
+}
+
+#[test]
+fn swapping_gives_the_other_key_the_replaced_chord() {
+    let mut layouts = cross_and_circle();
+    bind_b_to_cross(&mut layouts, Some(0), ConflictResolution::Swap);
+
+    assert_eq!(chords(&layouts, Ps2Key::Cross), vec![key_chord(KeyCode::B)]);
+    assert_eq!(
+        chords(&layouts, Ps2Key::Circle),
+        vec![key_chord(KeyCode::A)]
+    );
+    assert!(layouts.duplicated_chords().is_empty());
+}
+
+#[test]
+fn swapping_without_a_replaced_chord_steals() {
+    let mut layouts = cross_and_circle();
+    bind_b_to_cross(&mut layouts, None, ConflictResolution::Swap);
+
+    assert_eq!(
+        chords(&layouts, Ps2Key::Cross),
+        vec![key_chord(KeyCode::A), key_chord(KeyCode::B)]
+    );
+    assert!(chords(&layouts, Ps2Key::Circle).is_empty());
+    assert!(layouts.duplicated_chords().is_empty());
+}
+
+#[test]
+fn stealing_removes_the_chord_from_the_other_key() {
+    let mut layouts = cross_and_circle();
+    layouts.set_chord(ps2_key(Ps2Key::Circle), None, key_chord(KeyCode::C));
+    bind_b_to_cross(&mut layouts, Some(0), ConflictResolution::Steal);
+
+    assert_eq!(chords(&layouts, Ps2Key::Cross), vec![key_chord(KeyCode::B)]);
+    assert_eq!(
+        chords(&layouts, Ps2Key::Circle),
+        vec![key_chord(KeyCode::C)]
+    );
+    assert!(layouts.duplicated_chords().is_empty());
+}
+
+#[test]
+fn duplicates_are_kept_and_reported() {
+    let mut layouts = cross_and_circle();
+    bind_b_to_cross(&mut layouts, Some(0), ConflictResolution::AllowDuplicate);
+
+    assert_eq!(chords(&layouts, Ps2Key::Cross), vec![key_chord(KeyCode::B)]);
+    assert_eq!(
+        chords(&layouts, Ps2Key::Circle),
+        vec![key_chord(KeyCode::B)]
+    );
+
+    let duplicated = layouts.duplicated_chords();
+    assert_eq!(duplicated.len(), 1);
+    let (chord, keys) = &duplicated[0];
+    assert_eq!(*chord, key_chord(KeyCode::B));
+    assert_eq!(keys.len(), 2);
+    assert!(keys.contains(&ps2_key(Ps2Key::Cross)));
+    assert!(keys.contains(&ps2_key(Ps2Key::Circle)));
+}
+
+#[test]
+fn legacy_devices_are_migrated_in_connection_order() {
+    let mut layouts = ControllerLayoutsRes::default();
+    layouts.set_chord(
+        ps2_key(Ps2Key::Cross),
+        None,
+        InputSource::HidButton(1, 0).into(),
+    );
+
+    // Joysticks that aren't connected anymore are saved under a placeholder name.
+    let connected = vec![DeviceIdentity::named("Pad A")];
+    assert!(layouts.migrate_legacy_devices(&connected));
+    assert_eq!(
+        layouts.devices,
+        vec![
+            DeviceIdentity::named("Pad A"),
+            DeviceIdentity::named("Joystick 1"),
+        ]
+    );
+
+    // Once every id has a device, there's nothing left to migrate.
+    assert_eq!(layouts.num_legacy_devices(), 0);
+    assert!(!layouts.migrate_legacy_devices(&connected));
+    assert_eq!(layouts.devices.len(), 2);
+}
@@ -1,2 +1,10 @@
+pub mod definition;
+pub mod key_name;
 pub mod layout;
+#[cfg(test)]
+mod layout_tests;
+pub mod sdl_mapping;
 pub mod system;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,305 @@
+use std::fs;
+
+use crate::input::{
+    calibration::AxisRange,
+    input::{AxisSign, HidAxisId, HidHatSwitchId, HidId, InputSource},
+};
+
+use super::{
+    definition::PS2_CONTROLLER_ID,
+    key_name::KeyName,
+    layout::{
+        ControllerKey, ControllerLayoutsRes, PlayerId, PlayerKey, Ps2Key, SourceChord,
+        PS2_KEY_ORDER,
+    },
+};
+
+pub const GAMECONTROLLERDB_FILE_PATH: &'static str = "gamecontrollerdb.txt";
+
+// The name SDL gives the current platform in the `platform` field of a mapping.
+#[cfg(target_os = "linux")]
+pub const SDL_PLATFORM: &'static str = "Linux";
+#[cfg(target_os = "windows")]
+pub const SDL_PLATFORM: &'static str = "Windows";
+#[cfg(target_os = "macos")]
+pub const SDL_PLATFORM: &'static str = "Mac OS X";
+
+// A joystick input named by an SDL mapping, like `b3`, `-a1~` or `h0.4`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SdlInput {
+    Button(usize),
+    // An axis by index, either all of it or only its positive or negative half.
+    Axis {
+        index: usize,
+        half: Option<AxisSign>,
+        inverted: bool,
+    },
+    // A hat by index, with the bitmask of its direction (1 = up, 2 = right, 4 = down, 8 = left).
+    Hat {
+        index: usize,
+        mask: u8,
+    },
+}
+
+impl SdlInput {
+    pub fn parse(s: &str) -> Option<SdlInput> {
+        let (half, s) = match s.chars().next()? {
+            '+' => (Some(AxisSign::Plus), &s[1..]),
+            '-' => (Some(AxisSign::Minus), &s[1..]),
+            _ => (None, s),
+        };
+        let (inverted, s) = match s.strip_suffix('~') {
+            Some(s) => (true, s),
+            None => (false, s),
+        };
+
+        match s.chars().next()? {
+            'b' => Some(SdlInput::Button(s[1..].parse().ok()?)),
+            'a' => Some(SdlInput::Axis {
+                index: s[1..].parse().ok()?,
+                half,
+                inverted,
+            }),
+            'h' => {
+                let (index, mask) = s[1..].split_once('.')?;
+                Some(SdlInput::Hat {
+                    index: index.parse().ok()?,
+                    mask: mask.parse().ok()?,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+// One line of `gamecontrollerdb.txt`: the inputs of the joystick with `guid` that are mapped
+// to each element of SDL's standard controller.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdlMapping {
+    pub guid: String,
+    pub name: String,
+    pub platform: Option<String>,
+    // Each element name (like `a`, `leftx` or `+lefty`) with the input mapped to it.
+    pub elements: Vec<(String, SdlInput)>,
+}
+
+impl SdlMapping {
+    // Parse a mapping line, like `<guid>,<name>,a:b0,leftx:a0,dpup:h0.1,platform:Linux,`.
+    // Returns `None` for comments, blank lines and malformed mappings.
+    pub fn parse(line: &str) -> Option<SdlMapping> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let mut fields = line.split(',');
+        let guid = fields.next()?.to_lowercase();
+        let name = fields.next()?.to_string();
+        // GUIDs are sliced into 4-digit words when matched, which only lands on character
+        // boundaries if every character is a single byte.
+        if guid.len() != 32 || !guid.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let mut platform = None;
+        let mut elements = vec![];
+        for field in fields {
+            let (element, value) = match field.split_once(':') {
+                Some(pair) => pair,
+                None => continue,
+            };
+
+            if element == "platform" {
+                platform = Some(value.to_string());
+            } else if let Some(input) = SdlInput::parse(value) {
+                elements.push((element.to_string(), input));
+            }
+        }
+
+        Some(SdlMapping {
+            guid,
+            name,
+            platform,
+            elements,
+        })
+    }
+
+    // Returns `true` if this mapping is for a joystick with the SDL GUID `guid`.
+    // Newer SDL versions store a CRC of the joystick name in bytes 2-3 of the GUID, and some
+    // mappings leave the version (bytes 12-13) empty to match every version, so those are
+    // ignored when they're unset on either side.
+    pub fn matches_guid(&self, guid: &str) -> bool {
+        let guid = guid.to_lowercase();
+        if guid.len() != self.guid.len() || !guid.chars().all(|c| c.is_ascii_hexdigit()) {
+            return false;
+        }
+
+        (0..self.guid.len() / 4).all(|word| {
+            let (a, b) = (
+                &self.guid[word * 4..word * 4 + 4],
+                &guid[word * 4..word * 4 + 4],
+            );
+            let ignored = (word == 1 || word == 6) && (a == "0000" || b == "0000");
+            ignored || a == b
+        })
+    }
+}
+
+// Read every mapping in the SDL mapping file at `path`.
+pub fn read_sdl_mappings(path: &str) -> Vec<SdlMapping> {
+    match fs::read_to_string(path) {
+        Ok(text) => text.lines().filter_map(SdlMapping::parse).collect(),
+        Err(e) => {
+            println!("Error reading SDL mappings from {}: {:?}", path, e);
+            vec![]
+        }
+    }
+}
+
+// Find the mapping of the joystick with SDL GUID `guid`, preferring mappings for the
+// current platform.
+pub fn find_sdl_mapping<'a>(mappings: &'a [SdlMapping], guid: &str) -> Option<&'a SdlMapping> {
+    let matching: Vec<&SdlMapping> = mappings.iter().filter(|m| m.matches_guid(guid)).collect();
+
+    matching
+        .iter()
+        .find(|m| m.platform.as_deref() == Some(SDL_PLATFORM))
+        .or_else(|| matching.first())
+        .copied()
+}
+
+// The PS2 keys bound by the SDL controller element `element`, with the direction of the
+// element that each key reads, if the element is an axis.
+// SDL's y axes are positive downwards.
+fn element_keys(element: &str) -> Vec<(Ps2Key, Option<AxisSign>)> {
+    use AxisSign::*;
+    use Ps2Key::*;
+
+    match element {
+        "a" => vec![(Cross, None)],
+        "b" => vec![(Circle, None)],
+        "x" => vec![(Square, None)],
+        "y" => vec![(Triangle, None)],
+        "back" => vec![(Select, None)],
+        "start" => vec![(Start, None)],
+        "leftshoulder" => vec![(L1, None)],
+        "lefttrigger" => vec![(L2, None)],
+        "leftstick" => vec![(L3, None)],
+        "rightshoulder" => vec![(R1, None)],
+        "righttrigger" => vec![(R2, None)],
+        "rightstick" => vec![(R3, None)],
+        "dpup" => vec![(PadU, None)],
+        "dpleft" => vec![(PadL, None)],
+        "dpdown" => vec![(PadD, None)],
+        "dpright" => vec![(PadR, None)],
+        "leftx" => vec![(LeftPosX, Some(Plus)), (LeftNegX, Some(Minus))],
+        "+leftx" => vec![(LeftPosX, Some(Plus))],
+        "-leftx" => vec![(LeftNegX, Some(Minus))],
+        "lefty" => vec![(LeftNegY, Some(Plus)), (LeftPosY, Some(Minus))],
+        "+lefty" => vec![(LeftNegY, Some(Plus))],
+        "-lefty" => vec![(LeftPosY, Some(Minus))],
+        "rightx" => vec![(RightPosX, Some(Plus)), (RightNegX, Some(Minus))],
+        "+rightx" => vec![(RightPosX, Some(Plus))],
+        "-rightx" => vec![(RightNegX, Some(Minus))],
+        "righty" => vec![(RightNegY, Some(Plus)), (RightPosY, Some(Minus))],
+        "+righty" => vec![(RightNegY, Some(Plus))],
+        "-righty" => vec![(RightPosY, Some(Minus))],
+        _ => vec![],
+    }
+}
+
+// The input source of joystick `id` for `input`, when it drives a key reading the `direction`
+// of an axis element (or a button element, if `direction` is `None`), along with the range
+// of the source if it's an axis.
+// `axis_order` is the `HidAxisId` of each of the joystick's SDL axis indices.
+pub fn sdl_input_source(
+    input: SdlInput,
+    direction: Option<AxisSign>,
+    id: HidId,
+    axis_order: &[Option<HidAxisId>],
+) -> Option<(InputSource, Option<AxisRange>)> {
+    match input {
+        SdlInput::Button(button) => Some((InputSource::HidButton(id, button), None)),
+        SdlInput::Hat { index: 0, mask } => {
+            let hat = match mask {
+                1 => HidHatSwitchId::Up,
+                2 => HidHatSwitchId::Right,
+                4 => HidHatSwitchId::Down,
+                8 => HidHatSwitchId::Left,
+                _ => return None,
+            };
+            Some((InputSource::HidHatSwitch(id, hat), None))
+        }
+        // Only the first hat of a joystick is read.
+        SdlInput::Hat { .. } => None,
+        SdlInput::Axis {
+            index,
+            half,
+            inverted,
+        } => {
+            let axis = (*axis_order.get(index)?)?;
+            let flip = |sign: AxisSign| match (sign, inverted) {
+                (AxisSign::Plus, true) => AxisSign::Minus,
+                (AxisSign::Minus, true) => AxisSign::Plus,
+                (sign, false) => sign,
+            };
+
+            let (sign, range) = match (half, direction) {
+                (Some(half), _) => (half, AxisRange::Half),
+                (None, Some(direction)) => (flip(direction), AxisRange::Half),
+                // A whole axis driving a button element (like a trigger) rests at one end
+                // and moves through its full range.
+                (None, None) => match flip(AxisSign::Plus) {
+                    AxisSign::Plus => (AxisSign::Plus, AxisRange::Full { rest: -1.0 }),
+                    AxisSign::Minus => (AxisSign::Minus, AxisRange::Full { rest: 1.0 }),
+                },
+            };
+
+            Some((InputSource::HidAxis(id, axis, sign), Some(range)))
+        }
+    }
+}
+
+// Replace the standard (PS2) layout of `player` with the bindings of `mapping` for the
+// joystick with saved id `device`, and show that layout in the controller window.
+// Keys that `mapping` doesn't bind are left unbound. Returns the number of keys bound.
+pub fn import_sdl_mapping(
+    layouts: &mut ControllerLayoutsRes,
+    player: PlayerId,
+    device: HidId,
+    mapping: &SdlMapping,
+    axis_order: &[Option<HidAxisId>],
+) -> usize {
+    let mut bound = vec![];
+
+    for ps2_key in PS2_KEY_ORDER {
+        layouts.clear_binding(PlayerKey::new(player, ControllerKey::Ps2(ps2_key)));
+    }
+
+    for (element, input) in mapping.elements.iter() {
+        for (ps2_key, direction) in element_keys(element) {
+            let source = sdl_input_source(*input, direction, device, axis_order);
+            let (source, range) = match source {
+                Some(source) => source,
+                None => continue,
+            };
+
+            let key = PlayerKey::new(player, ControllerKey::Ps2(ps2_key));
+            if !bound.contains(&ps2_key) {
+                bound.push(ps2_key);
+            }
+
+            let chord = SourceChord::from(source);
+            layouts.set_chord(key, None, chord.clone());
+            if let Some(range) = range {
+                layouts.set_axis_range(key, &chord, range);
+            }
+        }
+    }
+
+    if let Some(layout) = layouts.player_mut(player) {
+        layout.controller_type = KeyName::new(PS2_CONTROLLER_ID);
+    }
+
+    bound.len()
+}
@@ -3,12 +3,29 @@ use bevy_egui::EguiContext;
 use bevy_inspector_egui::egui;
 
 use crate::{
-    input::listener::{InputListener, ListenerResult},
+    input::{
+        calibration::{AxisRange, CalibrationStep, CalibrationWizard},
+        device::DeviceMapRes,
+        global_keyboard::GlobalKeyboardRes,
+        input::{poll_raw_axis, HidId, InputSink},
+        listener::{InputListener, ListenerResult},
+        raw_input_reader::RawInputRes,
+        response::{DeadzoneMode, InputResponse, ResponseCurve},
+        settings::{InputSettings, MAX_LISTEN_TIMEOUT, MAX_POLL_RATE, MIN_POLL_RATE},
+        RawInputReader,
+    },
     state::AppState,
     util::{read_from_file, write_to_file},
 };
 
-use super::layout::{ControllerKey, ControllerLayout, ControllerLayoutsRes, PS2_KEY_ORDER};
+use super::{
+    definition::{read_controller_defs, ControllerDef, ControllerDefsRes, PS2_CONTROLLER_ID},
+    key_name::KeyName,
+    layout::{ConflictResolution, ControllerLayoutsRes, PlayerId, PlayerKey, SourceChord},
+    sdl_mapping::{
+        find_sdl_mapping, import_sdl_mapping, read_sdl_mappings, GAMECONTROLLERDB_FILE_PATH,
+    },
+};
 
 pub const LAYOUTS_FILE_PATH: &'static str = "layouts.json";
 
@@ -24,57 +41,907 @@ pub fn startup(mut commands: Commands) {
         }
     }
 
+    // Read and insert the controller type definitions.
+    commands.insert_resource(read_controller_defs());
+
     // Insert the `InputListener` resources.
     commands.insert_resource(InputListener::default());
+
+    commands.insert_resource(ControllerUiState::default());
+}
+
+// UI state of the controller configuration windows.
+#[derive(Debug, Default)]
+pub struct ControllerUiState {
+    // The player whose layout is shown in the controller window.
+    pub player: PlayerId,
+    // The player key whose binding response is being edited, if any.
+    pub editing_response: Option<PlayerKey>,
+    // The axis calibration in progress, if any.
+    pub calibration: Option<CalibrationWizard>,
+    // A heard chord which is already bound to other keys, waiting for the user to decide
+    // how to resolve the conflict.
+    pub conflict: Option<PendingConflict>,
+    // The connected joystick whose SDL mapping is imported, by runtime id.
+    pub import_device: HidId,
+}
+
+#[derive(Debug, Clone)]
+pub struct PendingConflict {
+    pub key: PlayerKey,
+    pub slot: Option<usize>,
+    pub chord: SourceChord,
+    pub range: Option<AxisRange>,
+    // The other keys bound to `chord`, with the index of the chord in their bindings.
+    pub conflicts: Vec<(PlayerKey, usize)>,
 }
 
 const NO_BINDING: &'static str = "-";
-const LISTEN_FOR_BINDING: &'static str = "Listening...";
+const LISTEN_FOR_BINDING: &'static str = "Listening (Esc cancels)...";
+const ADD_CHORD: &'static str = "+";
+const MISSING_DEVICE: &'static str = "Disconnected:";
+const CONFLICT_WINDOW_TITLE: &'static str = "Binding conflict";
+const LAYOUT_HEALTH: &'static str = "Layout health";
+const REMOVE_CHORD: &'static str = "x";
+const CLEAR_BINDING: &'static str = "Clear";
+const IMPORT_SDL_MAPPING: &'static str = "Import SDL mapping";
 const CONTROLLER_WINDOW_TITLE: &'static str = "Controller";
+const KEYBOARD_WINDOW_TITLE: &'static str = "Background keyboard";
+const INPUT_SETTINGS_WINDOW_TITLE: &'static str = "Input polling";
+const RESPONSE_WINDOW_TITLE: &'static str = "Binding response";
+const EDIT_RESPONSE: &'static str = "Response";
+const CALIBRATION_WINDOW_TITLE: &'static str = "Axis calibration";
+const NEXT_PROFILE_HOTKEY: KeyCode = KeyCode::F10;
+const DEFAULT_EXPONENTIAL_CURVE: f32 = 2.0;
+const MAX_EXPONENTIAL_CURVE: f32 = 5.0;
+
+// Returns the definition of the controller type selected for `player` in the setup window.
+fn selected_controller_def<'a>(
+    defs: &'a ControllerDefsRes,
+    layouts: &ControllerLayoutsRes,
+    player: PlayerId,
+) -> &'a ControllerDef {
+    layouts
+        .player(player)
+        .and_then(|layout| defs.get(layout.controller_type))
+        .or_else(|| defs.get(KeyName::new(PS2_CONTROLLER_ID)))
+        .unwrap()
+}
 
 pub fn ui_system(
     mut egui_ctx: ResMut<EguiContext>,
+    defs: Res<ControllerDefsRes>,
     mut layouts: ResMut<ControllerLayoutsRes>,
     mut input_listener: ResMut<InputListener>,
+    mut ui_state: ResMut<ControllerUiState>,
     mut event_reader: EventReader<ListenerResult>,
+    mut ew_profile: EventWriter<RequestSelectProfile>,
+    mut ew_import: EventWriter<RequestImportSdlMapping>,
+    device_map: Res<DeviceMapRes>,
+    mut sinks: Query<&mut InputSink>,
 ) {
+    // Keep the selected player valid if players were removed.
+    if ui_state.player >= layouts.num_players() {
+        ui_state.player = layouts.num_players() - 1;
+    }
+
+    // Store chords heard by the input listener
+    for ev in event_reader.iter() {
+        if let ListenerResult::SourceToKey(chord, key, slot, range) = ev {
+            let chord = device_map.save_chord(&mut layouts, chord);
+            input_listener.stop_listening();
+
+            // Ask how to resolve the conflict if the chord is bound to other keys.
+            let conflicts: Vec<(PlayerKey, usize)> = layouts
+                .find_chord(&chord)
+                .into_iter()
+                .filter(|(other_key, _)| other_key != key)
+                .collect();
+            if !conflicts.is_empty() {
+                ui_state.conflict = Some(PendingConflict {
+                    key: *key,
+                    slot: *slot,
+                    chord,
+                    range: *range,
+                    conflicts,
+                });
+                continue;
+            }
+
+            layouts.set_chord(*key, *slot, chord.clone());
+            if let Some(range) = range {
+                layouts.set_axis_range(*key, &chord, *range);
+            }
+            write_layouts_to_file(&layouts);
+
+            for mut sink in sinks.iter_mut() {
+                sink.sources_dirty = true;
+            }
+        }
+    }
+
     egui::Window::new(CONTROLLER_WINDOW_TITLE).show(egui_ctx.ctx_mut(), |ui| {
+        // Profile selection
+        ui.horizontal(|ui| {
+            egui::ComboBox::new("binding_profile", "Profile")
+                .selected_text(layouts.profile().name.as_str())
+                .show_ui(ui, |ui| {
+                    for (idx, profile) in layouts.profiles.iter().enumerate() {
+                        let selected = idx == layouts.active_profile;
+                        if ui
+                            .selectable_label(selected, profile.name.as_str())
+                            .clicked()
+                        {
+                            ew_profile.send(RequestSelectProfile(idx));
+                        }
+                    }
+                });
+
+            if ui.button("New").clicked() {
+                let name = format!("Profile {}", layouts.profiles.len() + 1);
+                layouts.add_profile(&name, false);
+                ew_profile.send(RequestSelectProfile(layouts.active_profile));
+            }
+
+            if ui.button("Duplicate").clicked() {
+                let name = format!("{} (copy)", layouts.profile().name);
+                layouts.add_profile(&name, true);
+                ew_profile.send(RequestSelectProfile(layouts.active_profile));
+            }
+
+            if layouts.profiles.len() > 1 && ui.button("Delete").clicked() {
+                layouts.remove_active_profile();
+                ew_profile.send(RequestSelectProfile(layouts.active_profile));
+            }
+        });
+
+        let mut profile_name = layouts.profile().name.clone();
+        if ui.text_edit_singleline(&mut profile_name).changed() {
+            layouts.rename_active_profile(&profile_name);
+            write_layouts_to_file(&layouts);
+        }
+
+        // Player selection
+        ui.horizontal(|ui| {
+            egui::ComboBox::new("controller_player", "Player")
+                .selected_text(format!("P{}", ui_state.player + 1))
+                .show_ui(ui, |ui| {
+                    for player in 0..layouts.num_players() {
+                        ui.selectable_value(
+                            &mut ui_state.player,
+                            player,
+                            format!("P{}", player + 1),
+                        );
+                    }
+                });
+
+            if ui.button("Add player").clicked() {
+                ui_state.player = layouts.add_player();
+                write_layouts_to_file(&layouts);
+            }
+
+            if layouts.num_players() > 1 && ui.button("Remove last player").clicked() {
+                layouts.remove_last_player();
+                ui_state.player = ui_state.player.min(layouts.num_players() - 1);
+                write_layouts_to_file(&layouts);
+
+                // Unbind the sinks of the removed player's keys.
+                for mut sink in sinks.iter_mut() {
+                    sink.sources_dirty = true;
+                }
+            }
+        });
+
+        let player = ui_state.player;
+        let def = selected_controller_def(&defs, &layouts, player);
+
+        // Controller type selection
+        egui::ComboBox::new("controller_type", "Controller")
+            .selected_text(def.name.as_str())
+            .show_ui(ui, |ui| {
+                for option in defs.defs.iter() {
+                    let selected = option.id == def.id;
+                    if ui
+                        .selectable_label(selected, option.name.as_str())
+                        .clicked()
+                        && !selected
+                    {
+                        if let Some(layout) = layouts.player_mut(player) {
+                            layout.controller_type = option.id;
+                        }
+                        write_layouts_to_file(&layouts);
+                    }
+                }
+            });
+
+        // Warn about bound joysticks which aren't connected.
+        // Their bindings are restored automatically once they're plugged back in.
+        for id in layouts.bound_devices() {
+            if !device_map.is_connected(id) {
+                if let Some(device) = layouts.devices.get(id) {
+                    ui.colored_label(
+                        egui::Color32::YELLOW,
+                        format!("{} {}", MISSING_DEVICE, device.name),
+                    );
+                }
+            }
+        }
+
+        // Bind the standard layout from the SDL mapping of a connected joystick.
+        if !device_map.connected.is_empty() {
+            if ui_state.import_device >= device_map.connected.len() {
+                ui_state.import_device = 0;
+            }
+
+            ui.horizontal(|ui| {
+                let selected = &device_map.connected[ui_state.import_device];
+                egui::ComboBox::new("sdl_import_device", "")
+                    .selected_text(selected.name.as_str())
+                    .show_ui(ui, |ui| {
+                        for (id, device) in device_map.connected.iter().enumerate() {
+                            ui.selectable_value(
+                                &mut ui_state.import_device,
+                                id,
+                                device.name.as_str(),
+                            );
+                        }
+                    });
+
+                if ui.button(IMPORT_SDL_MAPPING).clicked() {
+                    ew_import.send(RequestImportSdlMapping {
+                        player,
+                        device: ui_state.import_device,
+                    });
+                }
+            });
+        }
+
+        if ui_state.calibration.is_none() && ui.button("Calibrate axes").clicked() {
+            ui_state.calibration = Some(CalibrationWizard::new());
+        }
+
         egui::Grid::new(69).show(ui, |ui| {
-            for ps2_key in PS2_KEY_ORDER {
-                let key = ControllerKey::Ps2(ps2_key);
-                // Label with the controller key name
-                ui.label(key.to_string());
-
-                // Button with the current binding name/listening prompt
-                let listening = input_listener.listening_for_input_source()
-                    && input_listener.has_key_consumer(ControllerKey::Ps2(ps2_key));
-
-                if listening {
-                    ui.button(LISTEN_FOR_BINDING.to_string());
-                    for ev in event_reader.iter() {
-                        if let ListenerResult::SourceToKey(source, key) = ev {
-                            layouts.set_binding(*key, source);
+            for group in def.groups.iter() {
+                ui.strong(group.name.as_str());
+                ui.end_row();
+
+                for key_def in group.keys.iter() {
+                    let key = PlayerKey::new(player, def.controller_key(key_def.name));
+                    // Label with the controller key name
+                    ui.label(key.key.to_string());
+
+                    // Buttons for each chord of the binding, which are replaced when clicked,
+                    // and a button to listen for an additional chord.
+                    ui.horizontal(|ui| {
+                        let chords = layouts
+                            .get_binding(key)
+                            .map_or(vec![], |binding| binding.chords.clone());
+                        if chords.is_empty() && !input_listener.has_key_consumer(key) {
+                            ui.label(NO_BINDING);
+                        }
+
+                        let mut removed_chord = None;
+                        let slots = (0..chords.len()).map(Some).chain(std::iter::once(None));
+                        for slot in slots {
+                            let listening = input_listener.listening_for_input_source()
+                                && input_listener.has_slot_consumer(key, slot);
+
+                            if listening {
+                                if ui.button(listening_prompt(&input_listener)).clicked() {
+                                    input_listener.stop_listening();
+                                }
+                            } else if let Some(idx) = slot {
+                                if ui.button(chords[idx].to_string()).clicked() {
+                                    input_listener.listen_input_source(key, slot);
+                                }
+                                if ui.small_button(REMOVE_CHORD).clicked() {
+                                    removed_chord = Some(idx);
+                                }
+                            } else if ui.small_button(ADD_CHORD).clicked() {
+                                input_listener.listen_input_source(key, slot);
+                            }
+                        }
+
+                        // A single chord is already cleared by its remove button.
+                        let cleared = chords.len() > 1 && ui.small_button(CLEAR_BINDING).clicked();
+
+                        if let Some(idx) = removed_chord {
+                            layouts.remove_chord(key, idx);
+                        } else if cleared {
+                            layouts.clear_binding(key);
+                        }
+                        if removed_chord.is_some() || cleared {
                             write_layouts_to_file(&layouts);
-                            input_listener.stop_listening();
+                            for mut sink in sinks.iter_mut() {
+                                sink.sources_dirty = true;
+                            }
+                        }
+                    });
+
+                    // Button to edit the response settings of the binding
+                    if layouts.get_binding(key).is_some() {
+                        let editing = ui_state.editing_response == Some(key);
+                        if ui.selectable_label(editing, EDIT_RESPONSE).clicked() {
+                            ui_state.editing_response = if editing { None } else { Some(key) };
                         }
                     }
-                } else {
-                    let binding_str = &layouts
-                        .get_binding(key)
-                        .map_or(NO_BINDING.to_string(), |key| key.to_string());
 
-                    if ui.button(binding_str).clicked() {
-                        input_listener.listen_input_source(key);
-                    };
-                };
+                    ui.end_row();
+                }
+            }
+        });
+
+        egui::CollapsingHeader::new(LAYOUT_HEALTH).show(ui, |ui| {
+            layout_health_report(ui, &defs, &layouts);
+        });
+    });
+}
+
+// List the keys of each player's controller type without a binding, and the chords bound
+// to more than one key in the active profile.
+fn layout_health_report(
+    ui: &mut egui::Ui,
+    defs: &ControllerDefsRes,
+    layouts: &ControllerLayoutsRes,
+) {
+    let mut healthy = true;
+
+    for player in 0..layouts.num_players() {
+        let def = selected_controller_def(defs, layouts, player);
+        let unbound: Vec<String> = def
+            .keys()
+            .into_iter()
+            .filter(|&key| {
+                layouts
+                    .get_binding(PlayerKey::new(player, key))
+                    .map_or(true, |binding| binding.chords.is_empty())
+            })
+            .map(|key| key.to_string())
+            .collect();
+
+        if !unbound.is_empty() {
+            healthy = false;
+            ui.label(format!("P{} unbound: {}", player + 1, unbound.join(", ")));
+        }
+    }
+
+    for (chord, keys) in layouts.duplicated_chords() {
+        healthy = false;
+        let key_names: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+        ui.colored_label(
+            egui::Color32::YELLOW,
+            format!("{} is bound to {}", chord.to_string(), key_names.join(", ")),
+        );
+    }
+
+    if healthy {
+        ui.label("Every key is bound, and no chord is bound twice.");
+    }
+}
+
+// Ask how to bind a chord which is already bound to other keys.
+pub fn binding_conflict_ui_system(
+    mut egui_ctx: ResMut<EguiContext>,
+    mut layouts: ResMut<ControllerLayoutsRes>,
+    mut ui_state: ResMut<ControllerUiState>,
+    mut sinks: Query<&mut InputSink>,
+) {
+    let conflict = match ui_state.conflict.as_ref() {
+        Some(conflict) => conflict.clone(),
+        None => return,
+    };
+
+    let mut resolution = None;
+    let mut open = true;
+
+    egui::Window::new(CONFLICT_WINDOW_TITLE)
+        .open(&mut open)
+        .show(egui_ctx.ctx_mut(), |ui| {
+            let key_names: Vec<String> = conflict
+                .conflicts
+                .iter()
+                .map(|(key, _)| key.to_string())
+                .collect();
+            ui.label(format!(
+                "{} is already bound to {}.",
+                conflict.chord.to_string(),
+                key_names.join(", ")
+            ));
+
+            ui.horizontal(|ui| {
+                if ui.button("Swap").clicked() {
+                    resolution = Some(ConflictResolution::Swap);
+                }
+                if ui.button("Steal").clicked() {
+                    resolution = Some(ConflictResolution::Steal);
+                }
+                if ui.button("Allow duplicate").clicked() {
+                    resolution = Some(ConflictResolution::AllowDuplicate);
+                }
+                if ui.button("Cancel").clicked() {
+                    ui_state.conflict = None;
+                }
+            });
+        });
+
+    if !open {
+        ui_state.conflict = None;
+    }
+
+    if let Some(resolution) = resolution {
+        layouts.resolve_conflict(
+            conflict.key,
+            conflict.slot,
+            conflict.chord.clone(),
+            &conflict.conflicts,
+            resolution,
+        );
+        if let Some(range) = conflict.range {
+            layouts.set_axis_range(conflict.key, &conflict.chord, range);
+        }
+        write_layouts_to_file(&layouts);
+        ui_state.conflict = None;
+
+        for mut sink in sinks.iter_mut() {
+            sink.sources_dirty = true;
+        }
+    }
+}
+
+// The text of the button for a binding being listened for, showing the chord heard so far
+// and the seconds left before the listener gives up.
+fn listening_prompt(input_listener: &InputListener) -> String {
+    let heard = input_listener.partial_chord();
+    let prompt = if heard.is_empty() {
+        LISTEN_FOR_BINDING.to_string()
+    } else {
+        let names: Vec<String> = heard.iter().map(|s| s.to_string()).collect();
+        format!("{} + ...", names.join(" + "))
+    };
+
+    match input_listener.time_left() {
+        Some(time_left) => format!("{} ({}s)", prompt, time_left.ceil()),
+        None => prompt,
+    }
+}
+
+// Activate the binding profile at the given index in `ControllerLayoutsRes::profiles`.
+pub struct RequestSelectProfile(pub usize);
 
+// Switch the active binding profile, and rebind every input sink to the new profile's bindings.
+fn handle_request_select_profile(
+    mut layouts: ResMut<ControllerLayoutsRes>,
+    mut event_reader: EventReader<RequestSelectProfile>,
+    mut sinks: Query<&mut InputSink>,
+) {
+    let mut changed = false;
+    for RequestSelectProfile(idx) in event_reader.iter() {
+        layouts.select_profile(*idx);
+        changed = true;
+    }
+
+    if changed {
+        write_layouts_to_file(&layouts);
+        for mut sink in sinks.iter_mut() {
+            sink.sources_dirty = true;
+        }
+    }
+}
+
+// Bind the standard layout of `player` from the SDL mapping of the connected joystick
+// with runtime id `device`, read from `GAMECONTROLLERDB_FILE_PATH`.
+pub struct RequestImportSdlMapping {
+    pub player: PlayerId,
+    pub device: HidId,
+}
+
+fn handle_request_import_sdl_mapping(
+    mut layouts: ResMut<ControllerLayoutsRes>,
+    device_map: Res<DeviceMapRes>,
+    raw_input: NonSend<RawInputRes>,
+    mut event_reader: EventReader<RequestImportSdlMapping>,
+    mut sinks: Query<&mut InputSink>,
+) {
+    for RequestImportSdlMapping { player, device } in event_reader.iter() {
+        let identity = match device_map.connected.get(*device) {
+            Some(identity) => identity.clone(),
+            None => continue,
+        };
+        let guid = match identity.guid.as_ref() {
+            Some(guid) => guid,
+            None => {
+                println!("No SDL GUID is known for {}", identity.name);
+                continue;
+            }
+        };
+
+        let mappings = read_sdl_mappings(GAMECONTROLLERDB_FILE_PATH);
+        let mapping = match find_sdl_mapping(&mappings, guid) {
+            Some(mapping) => mapping,
+            None => {
+                println!(
+                    "No SDL mapping for {} ({}) in {}",
+                    identity.name, guid, GAMECONTROLLERDB_FILE_PATH
+                );
+                continue;
+            }
+        };
+
+        // Bindings refer to joysticks by their saved id.
+        let saved_id = match device_map.saved_id(*device) {
+            Some(id) => id,
+            None => layouts.add_device(identity.clone()),
+        };
+        let axis_order = raw_input.0.sdl_axis_order(*device);
+        let bound = import_sdl_mapping(&mut layouts, *player, saved_id, mapping, &axis_order);
+        println!(
+            "Imported {} keys from the SDL mapping '{}'",
+            bound, mapping.name
+        );
+
+        write_layouts_to_file(&layouts);
+        for mut sink in sinks.iter_mut() {
+            sink.sources_dirty = true;
+        }
+    }
+}
+
+// Cycle through the binding profiles.
+fn profile_hotkey_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    layouts: Res<ControllerLayoutsRes>,
+    mut event_writer: EventWriter<RequestSelectProfile>,
+) {
+    if keyboard_input.just_pressed(NEXT_PROFILE_HOTKEY) {
+        let next = (layouts.active_profile + 1) % layouts.profiles.len();
+        event_writer.send(RequestSelectProfile(next));
+    }
+}
+
+// Configure which keyboard devices are read while the vert window is unfocused.
+pub fn keyboard_capture_ui_system(
+    mut egui_ctx: ResMut<EguiContext>,
+    mut global_keyboard: NonSendMut<GlobalKeyboardRes>,
+) {
+    egui::Window::new(KEYBOARD_WINDOW_TITLE).show(egui_ctx.ctx_mut(), |ui| {
+        let mut changed = ui
+            .checkbox(
+                &mut global_keyboard.settings.enabled,
+                "Capture keyboard while unfocused",
+            )
+            .changed();
+
+        let device_names = global_keyboard.device_names();
+        if device_names.is_empty() {
+            ui.label("No readable keyboard devices.");
+        } else {
+            ui.label("Devices (read all if none are checked):");
+            for name in device_names {
+                let devices = &mut global_keyboard.settings.devices;
+                let mut checked = devices.contains(&name);
+                if ui.checkbox(&mut checked, name.as_str()).changed() {
+                    if checked {
+                        devices.push(name);
+                    } else {
+                        devices.retain(|d| *d != name);
+                    }
+                    changed = true;
+                }
+            }
+        }
+
+        if changed {
+            global_keyboard.save_settings();
+        }
+    });
+}
+
+pub fn input_settings_ui_system(
+    mut egui_ctx: ResMut<EguiContext>,
+    mut settings: ResMut<InputSettings>,
+) {
+    egui::Window::new(INPUT_SETTINGS_WINDOW_TITLE).show(egui_ctx.ctx_mut(), |ui| {
+        let mut changed = ui
+            .add(
+                egui::Slider::new(&mut settings.poll_rate, MIN_POLL_RATE..=MAX_POLL_RATE)
+                    .logarithmic(true)
+                    .suffix(" Hz")
+                    .text("Poll rate"),
+            )
+            .changed();
+
+        changed |= ui
+            .checkbox(
+                &mut settings.timestamped_presses,
+                "Keep button taps shorter than a poll",
+            )
+            .changed();
+
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut settings.listen_timeout, 0.0..=MAX_LISTEN_TIMEOUT)
+                    .suffix(" s")
+                    .text("Binding timeout (0 waits forever)"),
+            )
+            .changed();
+
+        if changed {
+            settings.save();
+        }
+    });
+}
+
+// Walk the user through calibrating every axis-bound controller key, first at rest and then
+// across its full range, and store the observed ranges in the layout.
+pub fn calibration_ui_system(
+    mut egui_ctx: ResMut<EguiContext>,
+    defs: Res<ControllerDefsRes>,
+    mut layouts: ResMut<ControllerLayoutsRes>,
+    mut ui_state: ResMut<ControllerUiState>,
+    axis_input: Res<Axis<GamepadAxis>>,
+    mut raw_input: NonSendMut<RawInputRes>,
+    device_map: Res<DeviceMapRes>,
+    mut sinks: Query<&mut InputSink>,
+) {
+    let player = ui_state.player;
+    let wizard = match ui_state.calibration.as_mut() {
+        Some(wizard) => wizard,
+        None => return,
+    };
+
+    // Observe the current raw value of every axis binding of the selected player's controller.
+    let mut axis_keys = vec![];
+    for key in selected_controller_def(&defs, &layouts, player).keys() {
+        let key = PlayerKey::new(player, key);
+        if let Some(binding) = layouts.get_binding(key) {
+            let value = binding
+                .primary_source()
+                .map(|source| device_map.resolve_source(source))
+                .and_then(|source| poll_raw_axis(&source, &axis_input, &mut raw_input));
+            if let Some(value) = value {
+                wizard.observe(key, value);
+                axis_keys.push(key);
+            }
+        }
+    }
+
+    let mut open = true;
+    let mut finished = false;
+    let mut closed = false;
+
+    egui::Window::new(CALIBRATION_WINDOW_TITLE)
+        .open(&mut open)
+        .show(egui_ctx.ctx_mut(), |ui| {
+            if axis_keys.is_empty() {
+                ui.label("No controller keys are bound to an axis.");
+                return;
+            }
+
+            match wizard.step {
+                CalibrationStep::Center => {
+                    ui.label("Let go of every stick and trigger, then press Next.");
+                    if ui.button("Next").clicked() {
+                        wizard.step = CalibrationStep::Range;
+                    }
+                }
+                CalibrationStep::Range => {
+                    ui.label("Rotate each stick and press each trigger fully, then press Finish.");
+                    if ui.button("Finish").clicked() {
+                        finished = true;
+                    }
+                }
+                CalibrationStep::Skipped => {
+                    ui.label(
+                        "These keys weren't moved far enough to be calibrated, so they keep \
+                         their previous calibration:",
+                    );
+                    for key in wizard.skipped.iter() {
+                        ui.label(key.to_string());
+                    }
+                    if ui.button("Close").clicked() {
+                        closed = true;
+                    }
+                    return;
+                }
+            }
+
+            egui::Grid::new("calibration_ranges").show(ui, |ui| {
+                ui.label("Key");
+                ui.label("Min");
+                ui.label("Center");
+                ui.label("Max");
                 ui.end_row();
+
+                for key in axis_keys.iter() {
+                    if let Some(c) = wizard.observed.get(key) {
+                        ui.label(key.to_string());
+                        ui.label(format!("{:.3}", c.min));
+                        ui.label(format!("{:.3}", c.center));
+                        ui.label(format!("{:.3}", c.max));
+                        ui.end_row();
+                    }
+                }
+            });
+        });
+
+    if finished {
+        for (key, calibration) in wizard.finish() {
+            if let Some(binding) = layouts.get_binding_mut(key) {
+                binding.calibration = Some(calibration);
             }
+        }
+        write_layouts_to_file(&layouts);
+
+        // Re-resolve the bindings of every sink so that they pick up the new calibration.
+        for mut sink in sinks.iter_mut() {
+            sink.sources_dirty = true;
+        }
+    }
+
+    if finished && !wizard.skipped.is_empty() {
+        wizard.step = CalibrationStep::Skipped;
+    } else if finished || closed || !open {
+        ui_state.calibration = None;
+    }
+}
+
+// Edit the deadzones, inversion and response curve of the binding selected in the controller window.
+pub fn response_ui_system(
+    mut egui_ctx: ResMut<EguiContext>,
+    mut layouts: ResMut<ControllerLayoutsRes>,
+    mut ui_state: ResMut<ControllerUiState>,
+    mut sinks: Query<&mut InputSink>,
+) {
+    let key = match ui_state.editing_response {
+        Some(key) => key,
+        None => return,
+    };
+
+    let response = match layouts.get_binding_mut(key) {
+        Some(binding) => &mut binding.response,
+        None => {
+            ui_state.editing_response = None;
+            return;
+        }
+    };
+
+    let mut open = true;
+    let mut changed = false;
+
+    egui::Window::new(RESPONSE_WINDOW_TITLE)
+        .open(&mut open)
+        .show(egui_ctx.ctx_mut(), |ui| {
+            ui.label(key.to_string());
+            changed |= response_editor(ui, response);
         });
-    });
+
+    if !open {
+        ui_state.editing_response = None;
+    }
+
+    if changed {
+        write_layouts_to_file(&layouts);
+
+        // Re-resolve the bindings of every sink so that they pick up the new response.
+        for mut sink in sinks.iter_mut() {
+            sink.sources_dirty = true;
+        }
+    }
 }
 
-fn write_layouts_to_file(layouts: &ControllerLayoutsRes) {
+// Returns `true` if `response` was changed.
+fn response_editor(ui: &mut egui::Ui, response: &mut InputResponse) -> bool {
+    let mut changed = false;
+
+    changed |= ui
+        .add(egui::Slider::new(&mut response.inner_deadzone, 0.0..=1.0).text("Inner deadzone"))
+        .changed();
+    changed |= ui
+        .add(egui::Slider::new(&mut response.outer_deadzone, 0.0..=1.0).text("Outer deadzone"))
+        .changed();
+
+    egui::ComboBox::new("response_deadzone_mode", "Deadzone mode")
+        .selected_text(response.deadzone_mode.to_string())
+        .show_ui(ui, |ui| {
+            for mode in DeadzoneMode::ALL {
+                changed |= ui
+                    .selectable_value(&mut response.deadzone_mode, mode, mode.to_string())
+                    .changed();
+            }
+        });
+
+    changed |= ui.checkbox(&mut response.invert, "Invert").changed();
+
+    // Thresholds used when an axis drives a button
+    changed |= ui
+        .add(egui::Slider::new(&mut response.press_threshold, 0.0..=1.0).text("Press threshold"))
+        .changed();
+    changed |= ui
+        .add(
+            egui::Slider::new(
+                &mut response.release_threshold,
+                0.0..=response.press_threshold,
+            )
+            .text("Release threshold"),
+        )
+        .changed();
+
+    // Curve type selection
+    let curve_options = [
+        ResponseCurve::Linear,
+        ResponseCurve::Exponential(DEFAULT_EXPONENTIAL_CURVE),
+        ResponseCurve::Custom(vec![(0.5, 0.5)]),
+    ];
+    egui::ComboBox::new("response_curve", "Curve")
+        .selected_text(response.curve.name())
+        .show_ui(ui, |ui| {
+            for option in curve_options {
+                let selected = response.curve.name() == option.name();
+                if ui.selectable_label(selected, option.name()).clicked() && !selected {
+                    response.curve = option;
+                    changed = true;
+                }
+            }
+        });
+
+    // Curve parameters
+    match &mut response.curve {
+        ResponseCurve::Linear => {}
+        ResponseCurve::Exponential(exp) => {
+            changed |= ui
+                .add(egui::Slider::new(exp, 0.1..=MAX_EXPONENTIAL_CURVE).text("Exponent"))
+                .changed();
+        }
+        ResponseCurve::Custom(points) => {
+            let mut removed = None;
+
+            egui::Grid::new("response_curve_points").show(ui, |ui| {
+                ui.label("Input");
+                ui.label("Output");
+                ui.end_row();
+
+                for (i, (x, y)) in points.iter_mut().enumerate() {
+                    changed |= ui
+                        .add(egui::DragValue::new(x).speed(0.01).clamp_range(0.0..=1.0))
+                        .changed();
+                    changed |= ui
+                        .add(egui::DragValue::new(y).speed(0.01).clamp_range(0.0..=1.0))
+                        .changed();
+                    if ui.button("Remove").clicked() {
+                        removed = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+
+            if let Some(i) = removed {
+                points.remove(i);
+                changed = true;
+            }
+
+            if ui.button("Add point").clicked() {
+                let last = points.last().map_or(0.0, |p| p.0);
+                let x = (last + 1.0) / 2.0;
+                points.push((x, x));
+                changed = true;
+            }
+
+            // Interpolation expects the points to be ordered by input.
+            if changed {
+                points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+            }
+        }
+    }
+
+    if !response.is_default() && ui.button("Reset").clicked() {
+        *response = InputResponse::default();
+        changed = true;
+    }
+
+    changed
+}
+
+pub fn write_layouts_to_file(layouts: &ControllerLayoutsRes) {
     write_to_file(layouts, LAYOUTS_FILE_PATH);
 }
 
@@ -82,6 +949,23 @@ pub fn add_controller_systems(app: &mut App, controller_state: AppState) {
     // Startup
     app.add_startup_system(startup);
 
+    // Binding profiles
+    app.add_event::<RequestSelectProfile>();
+    app.add_system(handle_request_select_profile);
+    app.add_system(profile_hotkey_system);
+
+    // SDL mappings
+    app.add_event::<RequestImportSdlMapping>();
+    app.add_system(handle_request_import_sdl_mapping);
+
     // Update
-    app.add_system_set(SystemSet::on_update(controller_state).with_system(ui_system));
+    app.add_system_set(
+        SystemSet::on_update(controller_state)
+            .with_system(ui_system)
+            .with_system(response_ui_system)
+            .with_system(calibration_ui_system)
+            .with_system(binding_conflict_ui_system)
+            .with_system(keyboard_capture_ui_system)
+            .with_system(input_settings_ui_system),
+    );
 }
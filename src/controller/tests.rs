@@ -0,0 +1,234 @@
+// Tests of reading SDL controller mappings and binding controller layouts from them.
+
+use bevy::prelude::KeyCode;
+
+use crate::input::{
+    calibration::AxisRange,
+    input::{AxisSign, HidAxisId, HidHatSwitchId, InputSource},
+};
+
+use super::{
+    layout::{ControllerKey, ControllerLayoutsRes, PlayerKey, Ps2Key},
+    sdl_mapping::{
+        find_sdl_mapping, import_sdl_mapping, sdl_input_source, SdlInput, SdlMapping, SDL_PLATFORM,
+    },
+};
+
+const PAD_GUID: &'static str = "030000005e0400008e02000014010000";
+
+const AXIS_ORDER: [Option<HidAxisId>; 3] = [Some(HidAxisId::X), Some(HidAxisId::Y), None];
+
+fn mapping_line(guid: &str, fields: &str) -> String {
+    format!("{},Test Pad,{}", guid, fields)
+}
+
+fn ps2_key(key: Ps2Key) -> PlayerKey {
+    PlayerKey::new(0, ControllerKey::Ps2(key))
+}
+
+#[test]
+fn sdl_inputs_are_parsed() {
+    assert_eq!(SdlInput::parse("b3"), Some(SdlInput::Button(3)));
+    assert_eq!(
+        SdlInput::parse("-a1~"),
+        Some(SdlInput::Axis {
+            index: 1,
+            half: Some(AxisSign::Minus),
+            inverted: true,
+        })
+    );
+    assert_eq!(
+        SdlInput::parse("+a2"),
+        Some(SdlInput::Axis {
+            index: 2,
+            half: Some(AxisSign::Plus),
+            inverted: false,
+        })
+    );
+    assert_eq!(
+        SdlInput::parse("h0.4"),
+        Some(SdlInput::Hat { index: 0, mask: 4 })
+    );
+
+    assert_eq!(SdlInput::parse(""), None);
+    assert_eq!(SdlInput::parse("x1"), None);
+    assert_eq!(SdlInput::parse("h0"), None);
+}
+
+#[test]
+fn mapping_lines_are_parsed() {
+    let line = mapping_line(
+        &PAD_GUID.to_uppercase(),
+        "a:b0,lefty:-a1~,bad,platform:Linux,",
+    );
+    let mapping = SdlMapping::parse(&line).unwrap();
+
+    assert_eq!(mapping.guid, PAD_GUID);
+    assert_eq!(mapping.name, "Test Pad");
+    assert_eq!(mapping.platform.as_deref(), Some("Linux"));
+    assert_eq!(
+        mapping.elements,
+        vec![
+            ("a".to_string(), SdlInput::Button(0)),
+            (
+                "lefty".to_string(),
+                SdlInput::Axis {
+                    index: 1,
+                    half: Some(AxisSign::Minus),
+                    inverted: true,
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn comments_and_malformed_lines_are_skipped() {
+    assert_eq!(SdlMapping::parse("# Game Controller DB"), None);
+    assert_eq!(SdlMapping::parse("   "), None);
+    assert_eq!(SdlMapping::parse(&mapping_line("0300005e04", "a:b0")), None);
+    assert_eq!(
+        SdlMapping::parse(&mapping_line(&format!("{}00", PAD_GUID), "a:b0")),
+        None
+    );
+
+    // 32 bytes, but not 32 hex digits. The second would be sliced through the middle of `é`.
+    assert_eq!(
+        SdlMapping::parse(&mapping_line("030000005e0400008e0200001401000z", "a:b0")),
+        None
+    );
+    assert_eq!(
+        SdlMapping::parse(&mapping_line("030000005e0400008e020000140é000", "a:b0")),
+        None
+    );
+}
+
+#[test]
+fn guids_match_ignoring_unset_crc_and_version() {
+    let mapping = SdlMapping::parse(&mapping_line("030000005e0400008e02000000000000", "")).unwrap();
+
+    assert!(mapping.matches_guid(PAD_GUID));
+    assert!(mapping.matches_guid("0300a1b25e0400008e02000014010000"));
+    assert!(!mapping.matches_guid("030000005e0400008f02000014010000"));
+    assert!(!mapping.matches_guid(&PAD_GUID[..30]));
+    assert!(!mapping.matches_guid("030000005e0400008e020000140é000"));
+}
+
+#[test]
+fn mappings_for_the_current_platform_are_preferred() {
+    let other_platform = if SDL_PLATFORM == "Linux" {
+        "Windows"
+    } else {
+        "Linux"
+    };
+    let mappings: Vec<SdlMapping> = [
+        mapping_line("03000000000000000000000000000000", "a:b9,"),
+        mapping_line(PAD_GUID, &format!("a:b1,platform:{},", other_platform)),
+        mapping_line(PAD_GUID, &format!("a:b2,platform:{},", SDL_PLATFORM)),
+    ]
+    .iter()
+    .filter_map(|line| SdlMapping::parse(line))
+    .collect();
+
+    let found = find_sdl_mapping(&mappings, PAD_GUID).unwrap();
+    assert_eq!(found.elements, vec![("a".to_string(), SdlInput::Button(2))]);
+
+    // Without a mapping for the current platform, the first matching mapping is used.
+    let found = find_sdl_mapping(&mappings[..2], PAD_GUID).unwrap();
+    assert_eq!(found.elements, vec![("a".to_string(), SdlInput::Button(1))]);
+
+    assert_eq!(
+        find_sdl_mapping(&mappings, "050000005e0400008e02000014010000"),
+        None
+    );
+}
+
+#[test]
+fn sdl_inputs_become_sources_of_the_device() {
+    let axis = |index, half, inverted| SdlInput::Axis {
+        index,
+        half,
+        inverted,
+    };
+
+    assert_eq!(
+        sdl_input_source(SdlInput::Button(4), None, 2, &AXIS_ORDER),
+        Some((InputSource::HidButton(2, 4), None))
+    );
+    assert_eq!(
+        sdl_input_source(SdlInput::Hat { index: 0, mask: 4 }, None, 2, &AXIS_ORDER),
+        Some((InputSource::HidHatSwitch(2, HidHatSwitchId::Down), None))
+    );
+    assert_eq!(
+        sdl_input_source(SdlInput::Hat { index: 1, mask: 4 }, None, 2, &AXIS_ORDER),
+        None
+    );
+
+    // Half axes keep their half, and whole axes read the direction of their element.
+    assert_eq!(
+        sdl_input_source(axis(1, Some(AxisSign::Plus), false), None, 2, &AXIS_ORDER),
+        Some((
+            InputSource::HidAxis(2, HidAxisId::Y, AxisSign::Plus),
+            Some(AxisRange::Half)
+        ))
+    );
+    assert_eq!(
+        sdl_input_source(axis(0, None, true), Some(AxisSign::Plus), 2, &AXIS_ORDER),
+        Some((
+            InputSource::HidAxis(2, HidAxisId::X, AxisSign::Minus),
+            Some(AxisRange::Half)
+        ))
+    );
+
+    // A whole axis bound to a button rests at the end of its range opposite its sign.
+    assert_eq!(
+        sdl_input_source(axis(0, None, true), None, 2, &AXIS_ORDER),
+        Some((
+            InputSource::HidAxis(2, HidAxisId::X, AxisSign::Minus),
+            Some(AxisRange::Full { rest: 1.0 })
+        ))
+    );
+
+    // Axes that can't be read, or that the device doesn't have, aren't bound.
+    assert_eq!(
+        sdl_input_source(axis(2, None, false), None, 2, &AXIS_ORDER),
+        None
+    );
+    assert_eq!(
+        sdl_input_source(axis(5, None, false), None, 2, &AXIS_ORDER),
+        None
+    );
+}
+
+#[test]
+fn importing_a_mapping_replaces_the_whole_layout() {
+    let mut layouts = ControllerLayoutsRes::default();
+    layouts.set_chord(
+        ps2_key(Ps2Key::Cross),
+        None,
+        InputSource::Key(KeyCode::A).into(),
+    );
+    layouts.set_chord(
+        ps2_key(Ps2Key::Start),
+        None,
+        InputSource::Key(KeyCode::Return).into(),
+    );
+
+    let mapping = SdlMapping::parse(&mapping_line(PAD_GUID, "a:b0,leftx:a0,")).unwrap();
+    let bound = import_sdl_mapping(&mut layouts, 0, 1, &mapping, &AXIS_ORDER);
+    assert_eq!(bound, 3);
+
+    let cross = layouts.get_binding(ps2_key(Ps2Key::Cross)).unwrap();
+    assert_eq!(cross.chords.len(), 1);
+    assert_eq!(cross.primary_source(), Some(&InputSource::HidButton(1, 0)));
+
+    let left_neg_x = layouts.get_binding(ps2_key(Ps2Key::LeftNegX)).unwrap();
+    assert_eq!(
+        left_neg_x.primary_source(),
+        Some(&InputSource::HidAxis(1, HidAxisId::X, AxisSign::Minus))
+    );
+
+    // Keys the mapping doesn't mention are unbound.
+    let start = layouts.get_binding(ps2_key(Ps2Key::Start));
+    assert!(start.map_or(true, |binding| binding.chords.is_empty()));
+}
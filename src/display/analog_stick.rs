@@ -3,15 +3,13 @@ use bevy_inspector_egui::{Inspectable, RegisterInspectable};
 use bevy_prototype_lyon::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::{
-    editor::inspector::BoundControllerKey,
-    input::input::{InputSink, InputValue},
-};
+use crate::{editor::inspector::BoundControllerKey, input::input::InputSink};
 
 use super::{
     display::{AtomicInputDisplay, RootAtomicDisplayMarker, TaggedAtomicParams},
     renderable::Renderable,
     serialization::{CircleDef, DrawModeDef, TransformDef},
+    socd::{SocdMode, SocdState},
 };
 
 // The data parameterizing an analog stick input display.
@@ -27,6 +25,12 @@ pub struct AnalogStickParams {
     pub neg_y: BoundControllerKey,
     #[inspectable(label = "Trigger")]
     pub trigger: BoundControllerKey,
+    #[inspectable(label = "SOCD mode")]
+    #[serde(default)]
+    pub socd_mode: SocdMode,
+    #[inspectable(label = "Normalize diagonals")]
+    #[serde(default)]
+    pub normalize_diagonals: bool,
     #[inspectable(label = "Transform")]
     pub transform: TransformDef,
     #[inspectable(min = 0.0, suffix = "px", label = "Stick radius")]
@@ -84,6 +88,8 @@ impl Default for AnalogStickParams {
             pos_y: Default::default(),
             neg_y: Default::default(),
             trigger: Default::default(),
+            socd_mode: Default::default(),
+            normalize_diagonals: false,
             transform: Default::default(),
             stick_radius: 30.0,
             stick_display: Renderable::Circle(stick_display),
@@ -107,7 +113,13 @@ impl AnalogStickParams {
         } = self;
 
         // Collect the input sources needed by this display
-        let sources = vec![pos_x.key, neg_x.key, pos_y.key, neg_y.key, trigger.key];
+        let sources = vec![
+            pos_x.player_key(),
+            neg_x.player_key(),
+            pos_y.player_key(),
+            neg_y.player_key(),
+            trigger.player_key(),
+        ];
         let input_sink = InputSink::new(sources);
 
         (
@@ -115,6 +127,7 @@ impl AnalogStickParams {
             Into::<Transform>::into(self.transform),
             RootAnalogStickMarker,
             RootAtomicDisplayMarker,
+            SocdState::default(),
             Name::new("** Analog Stick"),
             input_sink,
         )
@@ -159,60 +172,44 @@ pub struct ChildBgMarker;
 pub struct AnalogStickAtomicDisplay;
 
 impl AnalogStickAtomicDisplay {
-    fn unwrap_axis(axis: Option<InputValue>) -> f32 {
-        match axis {
-            Some(InputValue::Axis(v)) => v,
-            _ => 0.0,
-        }
-    }
-
-    // Parses an axis magnitude from two (potentially `None`-valued) analog inputs
-    // corresponding to the positive and negative directions of that axis.
-    fn axis_to_position(pos: Option<InputValue>, neg: Option<InputValue>) -> f32 {
-        let pos_value = Self::unwrap_axis(pos);
-        let neg_value = Self::unwrap_axis(neg);
-        pos_value - neg_value
-    }
-
     // Parses the relative position of the analog stick as a `Vec2` from the analog
-    // stick's `InputValue` vector.
-    fn axes_to_positions(values: &Vec<Option<InputValue>>) -> Vec2 {
-        if let [pos_x, neg_x, pos_y, neg_y] = values[0..4] {
-            Vec2::new(
-                Self::axis_to_position(pos_x, neg_x),
-                Self::axis_to_position(pos_y, neg_y),
-            )
-        } else {
-            Vec2::new(0.0, 0.0)
-        }
-    }
-
-    // Returns `true` if the analog stick's `InputValue` vector indicates that the
-    // trigger button is being pressed.
-    fn is_trigger_pressed(values: &Vec<Option<InputValue>>) -> bool {
-        match values.get(4) {
-            Some(&Some(InputValue::Button(v))) => v,
-            _ => false,
-        }
+    // stick's `InputSink`, resolving opposing directions according to the stick's SOCD mode.
+    // Buttons bound to the directions move the stick fully.
+    fn axes_to_positions(
+        sink: &InputSink,
+        socd: &mut SocdState,
+        params: &AnalogStickParams,
+    ) -> Vec2 {
+        let values = [
+            sink.axis_value(0),
+            sink.axis_value(1),
+            sink.axis_value(2),
+            sink.axis_value(3),
+        ];
+        socd.resolve(values, params.socd_mode, params.normalize_diagonals)
     }
 
     fn analog_stick_display_system(
-        q_parent: Query<(&InputSink, &Children, &TaggedAtomicParams), With<RootAnalogStickMarker>>,
+        mut q_parent: Query<
+            (&InputSink, &Children, &TaggedAtomicParams, &mut SocdState),
+            With<RootAnalogStickMarker>,
+        >,
         mut q_child_stick: Query<(&mut Transform, &mut DrawMode), With<ChildStickMarker>>,
     ) {
-        for (sink, children, tagged_params) in q_parent.iter() {
+        for (sink, children, tagged_params, mut socd) in q_parent.iter_mut() {
             if let TaggedAtomicParams::AnalogStick(params) = tagged_params {
+                let pos = Self::axes_to_positions(sink, &mut socd, params);
+
                 for child in children.iter() {
                     if let Ok((mut stick_transform, mut draw_mode)) = q_child_stick.get_mut(*child)
                     {
                         // Move the stick child according to the axis input
-                        let pos = Self::axes_to_positions(&sink.values);
                         stick_transform.translation.x = pos.x * params.stick_radius;
                         stick_transform.translation.y = pos.y * params.stick_radius;
 
                         // Handle trigger presses
                         if params.trigger.key.is_some() {
-                            if Self::is_trigger_pressed(&sink.values) {
+                            if sink.is_pressed(4) {
                                 *draw_mode = params.trigger_mode.into();
                             } else {
                                 *draw_mode = params.stick_mode.into();
@@ -3,10 +3,7 @@ use bevy_inspector_egui::{Inspectable, RegisterInspectable};
 
 use serde::{Deserialize, Serialize};
 
-use crate::{
-    editor::inspector::BoundControllerKey,
-    input::input::{InputSink, InputValue},
-};
+use crate::{editor::inspector::BoundControllerKey, input::input::InputSink};
 
 use super::{
     display::{AtomicInputDisplay, RootAtomicDisplayMarker, TaggedAtomicParams},
@@ -14,6 +11,27 @@ use super::{
     serialization::{CircleDef, DrawModeDef, FillModeDef, TransformDef},
 };
 
+// The z offset of the scaled "on" model, so that it draws over the "off" model.
+const PRESSURE_Z_OFFSET: f32 = 0.1;
+
+// How a button display shows how hard its key is pressed, for keys bound to an axis
+// (like the pressure-sensitive face buttons of a PS2 controller).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Inspectable)]
+pub enum ButtonPressure {
+    // Toggle between the on and off textures at the binding's press threshold.
+    None,
+    // Blend from the off texture to the on texture as the key is pressed.
+    Color,
+    // Grow the on model over the off model from its center as the key is pressed.
+    Scale,
+}
+
+impl Default for ButtonPressure {
+    fn default() -> Self {
+        ButtonPressure::None
+    }
+}
+
 // The data parameterizing a button input display.
 #[derive(Debug, Clone, Serialize, Deserialize, Component, Inspectable)]
 pub struct ButtonParams {
@@ -27,6 +45,9 @@ pub struct ButtonParams {
     pub on_mode: DrawModeDef,
     #[inspectable(label = "Off texture")]
     pub off_mode: DrawModeDef,
+    #[inspectable(label = "Pressure")]
+    #[serde(default)]
+    pub pressure: ButtonPressure,
 }
 
 impl Default for ButtonParams {
@@ -43,6 +64,7 @@ impl Default for ButtonParams {
             button_key: Default::default(),
             transform: Default::default(),
             displayable: Renderable::Circle(CircleDef { radius: 10.0 }),
+            pressure: Default::default(),
         }
     }
 }
@@ -67,7 +89,7 @@ impl ButtonParams {
         commands
             .insert(Visibility { is_visible: false })
             .insert(ChildButtonMarker { pressed: true })
-            .insert(InputSink::new(vec![self.button_key.key]));
+            .insert(InputSink::new(vec![self.button_key.player_key()]));
     }
 
     fn insert_off_bundle(&self, commands: &mut EntityCommands) {
@@ -76,7 +98,21 @@ impl ButtonParams {
 
         commands
             .insert(ChildButtonMarker { pressed: false })
-            .insert(InputSink::new(vec![self.button_key.key]));
+            .insert(InputSink::new(vec![self.button_key.player_key()]));
+    }
+
+    // The transform of the "on" model when the key is pressed to `value` in `Scale` mode.
+    fn pressure_transform(&self, value: f32) -> Transform {
+        let value = value.clamp(0.0, 1.0);
+        let (min, max) = self.displayable.bounds();
+        let center = (min + max) / 2.0;
+        let translation = center * (1.0 - value);
+
+        Transform {
+            translation: translation.extend(PRESSURE_Z_OFFSET),
+            scale: Vec3::new(value, value, 1.0),
+            ..Transform::identity()
+        }
     }
 }
 
@@ -97,18 +133,50 @@ pub struct ButtonAtomicDisplay;
 
 impl ButtonAtomicDisplay {
     // Update all atomic button displays.
-    fn button_update_system(mut query: Query<(&InputSink, &ChildButtonMarker, &mut Visibility)>) {
-        for (sink, marker, mut vis) in query.iter_mut() {
-            match sink.values[0] {
-                Some(InputValue::Button(true)) => {
-                    vis.is_visible = marker.pressed;
+    fn button_update_system(
+        q_parent: Query<&TaggedAtomicParams, With<RootButtonMarker>>,
+        mut query: Query<(
+            &Parent,
+            &InputSink,
+            &ChildButtonMarker,
+            &mut Visibility,
+            &mut DrawMode,
+            &mut Transform,
+        )>,
+    ) {
+        for (parent, sink, marker, mut vis, mut draw_mode, mut transform) in query.iter_mut() {
+            let params = match q_parent.get(parent.0) {
+                Ok(TaggedAtomicParams::Button(params)) => params,
+                _ => continue,
+            };
+
+            // Buttons bound to a button-type key are pressed either fully or not at all.
+            let value = sink.axis_value(0).clamp(0.0, 1.0);
+
+            match (params.pressure, marker.pressed) {
+                // Axis values press the button according to their binding's thresholds.
+                // If no input source has been bound to this button, it's always displayed as unpressed.
+                (ButtonPressure::None, pressed) => {
+                    vis.is_visible = sink.is_pressed(0) == pressed;
+
+                    // The "off" model keeps the color blended by `Color` pressure otherwise.
+                    if !pressed {
+                        *draw_mode = params.off_mode.into();
+                    }
                 }
-                Some(InputValue::Button(false)) => {
-                    vis.is_visible = !marker.pressed;
+                (ButtonPressure::Color, true) => {
+                    vis.is_visible = false;
                 }
-                _ => {
-                    // If no input source has been bound to this button, always display it as unpressed.
-                    vis.is_visible = !marker.pressed;
+                (ButtonPressure::Color, false) => {
+                    vis.is_visible = true;
+                    *draw_mode = params.off_mode.lerp(params.on_mode, value).into();
+                }
+                (ButtonPressure::Scale, true) => {
+                    vis.is_visible = value > 0.0;
+                    *transform = params.pressure_transform(value);
+                }
+                (ButtonPressure::Scale, false) => {
+                    vis.is_visible = true;
                 }
             }
         }
@@ -170,3 +238,6 @@ impl AtomicInputDisplay<ButtonParams> for ButtonAtomicDisplay {
         app.register_inspectable::<ButtonParams>();
     }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,93 @@
+// Tests of how button displays show the pressure of keys bound to an axis.
+
+use bevy::prelude::*;
+
+use super::{ButtonParams, PRESSURE_Z_OFFSET};
+use crate::display::{
+    renderable::Renderable,
+    serialization::{DrawModeDef, FillModeDef, RectangleDef, StrokeModeDef},
+};
+
+fn fill(color: Color) -> FillModeDef {
+    FillModeDef {
+        options: Default::default(),
+        color,
+    }
+}
+
+fn stroke(thickness: f32, color: Color) -> StrokeModeDef {
+    StrokeModeDef { thickness, color }
+}
+
+#[test]
+fn pressure_grows_the_on_model_from_its_center() {
+    // Rectangles are drawn from their bottom left corner, so their center is (4, 2).
+    let params = ButtonParams {
+        displayable: Renderable::Rectangle(RectangleDef {
+            extents: Vec2::new(8.0, 4.0),
+        }),
+        ..Default::default()
+    };
+
+    for (value, translation, scale) in [
+        (0.0, Vec2::new(4.0, 2.0), 0.0),
+        (0.5, Vec2::new(2.0, 1.0), 0.5),
+        (1.0, Vec2::ZERO, 1.0),
+        // Values outside [0, 1] are clamped.
+        (1.5, Vec2::ZERO, 1.0),
+        (-0.5, Vec2::new(4.0, 2.0), 0.0),
+    ] {
+        let transform = params.pressure_transform(value);
+        assert_eq!(transform.translation, translation.extend(PRESSURE_Z_OFFSET));
+        assert_eq!(transform.scale, Vec3::new(scale, scale, 1.0));
+        assert_eq!(transform.rotation, Quat::IDENTITY);
+    }
+}
+
+#[test]
+fn draw_modes_blend_their_colors_and_thicknesses() {
+    let black = Color::rgba(0.0, 0.0, 0.0, 1.0);
+    let orange = Color::rgba(1.0, 0.5, 0.0, 1.0);
+
+    match DrawModeDef::Fill(fill(black)).lerp(DrawModeDef::Fill(fill(orange)), 0.5) {
+        DrawModeDef::Fill(f) => assert_eq!(f.color.as_rgba_f32(), [0.5, 0.25, 0.0, 1.0]),
+        mode => panic!("expected a fill, got {:?}", mode),
+    }
+
+    let thin = DrawModeDef::Stroke(stroke(1.0, black));
+    let thick = DrawModeDef::Stroke(stroke(3.0, orange));
+    match thin.lerp(thick, 0.25) {
+        DrawModeDef::Stroke(s) => {
+            assert_eq!(s.thickness, 1.5);
+            assert_eq!(s.color.as_rgba_f32(), [0.25, 0.125, 0.0, 1.0]);
+        }
+        mode => panic!("expected a stroke, got {:?}", mode),
+    }
+
+    let outlined = |fill_color: Color, thickness: f32| DrawModeDef::Outlined {
+        Fill: fill(fill_color),
+        Border: stroke(thickness, fill_color),
+    };
+    match outlined(black, 2.0).lerp(outlined(orange, 4.0), 1.0) {
+        DrawModeDef::Outlined { Fill: f, Border: b } => {
+            assert_eq!(f.color.as_rgba_f32(), [1.0, 0.5, 0.0, 1.0]);
+            assert_eq!(b.thickness, 4.0);
+        }
+        mode => panic!("expected an outlined mode, got {:?}", mode),
+    }
+}
+
+#[test]
+fn draw_modes_of_different_kinds_switch_halfway() {
+    let fill_mode = DrawModeDef::Fill(fill(Color::BLACK));
+    let stroke_mode = DrawModeDef::Stroke(stroke(2.0, Color::RED));
+
+    assert!(matches!(
+        fill_mode.lerp(stroke_mode, 0.25),
+        DrawModeDef::Fill(_)
+    ));
+    assert!(matches!(
+        fill_mode.lerp(stroke_mode, 0.75),
+        DrawModeDef::Stroke(_)
+    ));
+}
@@ -3,7 +3,11 @@ use bevy_inspector_egui::Inspectable;
 
 use serde::{Deserialize, Serialize};
 
-use super::{analog_stick::AnalogStickParams, button::ButtonParams, frame::FrameParams};
+use super::{
+    analog_stick::AnalogStickParams, button::ButtonParams, frame::FrameParams,
+    input_history::InputHistoryParams, label::LabelParams, mouse::MouseParams,
+    trigger::TriggerParams,
+};
 
 #[derive(Component)]
 pub struct RootAtomicDisplayMarker;
@@ -25,17 +29,31 @@ pub enum TaggedAtomicParams {
     Button(ButtonParams),
     AnalogStick(AnalogStickParams),
     Frame(FrameParams),
+    Mouse(MouseParams),
+    InputHistory(InputHistoryParams),
+    Trigger(TriggerParams),
+    Label(LabelParams),
 }
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub enum AtomicParamsTag {
     Button,
     AnalogStick,
+    Mouse,
+    InputHistory,
+    Trigger,
+    Label,
 }
 
 impl AtomicParamsTag {
-    pub const CAN_CREATE: [AtomicParamsTag; 2] =
-        [AtomicParamsTag::Button, AtomicParamsTag::AnalogStick];
+    pub const CAN_CREATE: [AtomicParamsTag; 6] = [
+        AtomicParamsTag::Button,
+        AtomicParamsTag::AnalogStick,
+        AtomicParamsTag::Trigger,
+        AtomicParamsTag::Mouse,
+        AtomicParamsTag::InputHistory,
+        AtomicParamsTag::Label,
+    ];
 }
 
 impl ToString for AtomicParamsTag {
@@ -43,6 +61,10 @@ impl ToString for AtomicParamsTag {
         match self {
             AtomicParamsTag::Button => "Button".to_string(),
             AtomicParamsTag::AnalogStick => "Analog stick".to_string(),
+            AtomicParamsTag::Mouse => "Mouse".to_string(),
+            AtomicParamsTag::InputHistory => "Input history".to_string(),
+            AtomicParamsTag::Trigger => "Trigger".to_string(),
+            AtomicParamsTag::Label => "Label".to_string(),
         }
     }
 }
@@ -0,0 +1,538 @@
+use std::collections::VecDeque;
+
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    editor::inspector::{BoundControllerKey, InputSinkId},
+    input::{events::TimedPress, input::InputSink},
+};
+
+use super::{
+    display::{AtomicInputDisplay, RootAtomicDisplayMarker, TaggedAtomicParams},
+    serialization::TransformDef,
+};
+
+const HISTORY_FONT_PATH: &'static str = "fonts/FiraMono-Medium.ttf";
+
+// The z offset of the rows, so that they draw over a frame.
+const ROW_Z_OFFSET: f32 = 0.1;
+
+// The number of direction keys at the start of the display's `InputSink`.
+const NUM_DIRECTIONS: usize = 4;
+
+// The longest hold shown, in frames, so that rows stay the same width.
+const MAX_HOLD_FRAMES: u64 = 999;
+
+// How directions are written in the history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Inspectable)]
+pub enum DirectionNotation {
+    // Numbers laid out like a numpad, where 5 is neutral and 6 is forward.
+    Numpad,
+    Arrows,
+}
+
+impl DirectionNotation {
+    // Returns how to write the numpad `direction`.
+    fn write(self, direction: u8) -> &'static str {
+        match self {
+            DirectionNotation::Numpad => {
+                ["1", "2", "3", "4", "5", "6", "7", "8", "9"][direction as usize - 1]
+            }
+            DirectionNotation::Arrows => {
+                ["↙", "↓", "↘", "←", "·", "→", "↖", "↑", "↗"][direction as usize - 1]
+            }
+        }
+    }
+}
+
+impl Default for DirectionNotation {
+    fn default() -> Self {
+        DirectionNotation::Numpad
+    }
+}
+
+// The direction in which older entries of the history are laid out from the newest one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Inspectable)]
+pub enum HistoryOrientation {
+    Down,
+    Up,
+    Left,
+    Right,
+}
+
+impl HistoryOrientation {
+    fn offset(self) -> Vec2 {
+        match self {
+            HistoryOrientation::Down => Vec2::new(0.0, -1.0),
+            HistoryOrientation::Up => Vec2::new(0.0, 1.0),
+            HistoryOrientation::Left => Vec2::new(-1.0, 0.0),
+            HistoryOrientation::Right => Vec2::new(1.0, 0.0),
+        }
+    }
+
+    fn alignment(self) -> TextAlignment {
+        let horizontal = match self {
+            HistoryOrientation::Down | HistoryOrientation::Up => HorizontalAlign::Left,
+            HistoryOrientation::Left | HistoryOrientation::Right => HorizontalAlign::Center,
+        };
+
+        TextAlignment {
+            vertical: VerticalAlign::Center,
+            horizontal,
+        }
+    }
+}
+
+impl Default for HistoryOrientation {
+    fn default() -> Self {
+        HistoryOrientation::Down
+    }
+}
+
+// A button shown in the history, as its icon drawn in its color while it's held.
+#[derive(Debug, Clone, Serialize, Deserialize, Inspectable)]
+pub struct HistoryButton {
+    #[inspectable(label = "Button")]
+    pub key: BoundControllerKey,
+    #[inspectable(label = "Icon")]
+    pub icon: String,
+    #[inspectable(label = "Color", alpha = true)]
+    pub color: Color,
+}
+
+impl Default for HistoryButton {
+    fn default() -> Self {
+        Self {
+            key: Default::default(),
+            icon: "B".to_string(),
+            color: Color::WHITE,
+        }
+    }
+}
+
+// The data parameterizing an input history display.
+#[derive(Debug, Clone, Serialize, Deserialize, Component, Inspectable)]
+pub struct InputHistoryParams {
+    #[inspectable(label = "Up")]
+    pub up: BoundControllerKey,
+    #[inspectable(label = "Down")]
+    pub down: BoundControllerKey,
+    #[inspectable(label = "Left")]
+    pub left: BoundControllerKey,
+    #[inspectable(label = "Right")]
+    pub right: BoundControllerKey,
+    #[inspectable(label = "Buttons")]
+    pub buttons: Vec<HistoryButton>,
+    #[inspectable(label = "Transform")]
+    pub transform: TransformDef,
+    #[inspectable(label = "Length", min = 1, max = 40)]
+    pub length: usize,
+    #[inspectable(label = "Orientation")]
+    pub orientation: HistoryOrientation,
+    #[inspectable(label = "Directions")]
+    pub notation: DirectionNotation,
+    #[inspectable(label = "Row spacing", min = 0.0, suffix = "px")]
+    pub spacing: f32,
+    #[inspectable(label = "Font size", min = 1.0, suffix = "px")]
+    pub font_size: f32,
+    #[inspectable(label = "Text color", alpha = true)]
+    pub text_color: Color,
+    // The opacity of the oldest entry, fading linearly from the newest entry's full opacity.
+    #[inspectable(label = "Oldest opacity", min = 0.0, max = 1.0)]
+    pub fade: f32,
+    // The frame rate used to count hold durations.
+    #[inspectable(label = "Frame rate", min = 1.0, suffix = "fps")]
+    pub frame_rate: f32,
+}
+
+impl Default for InputHistoryParams {
+    fn default() -> Self {
+        let button = |icon: &str, color: Color| HistoryButton {
+            key: Default::default(),
+            icon: icon.to_string(),
+            color,
+        };
+
+        Self {
+            up: Default::default(),
+            down: Default::default(),
+            left: Default::default(),
+            right: Default::default(),
+            buttons: vec![
+                button("A", Color::RED),
+                button("B", Color::YELLOW),
+                button("C", Color::GREEN),
+                button("D", Color::BLUE),
+            ],
+            transform: Default::default(),
+            length: 12,
+            orientation: Default::default(),
+            notation: Default::default(),
+            spacing: 18.0,
+            font_size: 16.0,
+            text_color: Color::BLACK,
+            fade: 0.3,
+            frame_rate: 60.0,
+        }
+    }
+}
+
+impl InputHistoryParams {
+    fn root_bundle(&self) -> impl Bundle {
+        // Collect the input sources needed by this display
+        let mut sources = vec![
+            self.up.player_key(),
+            self.down.player_key(),
+            self.left.player_key(),
+            self.right.player_key(),
+        ];
+        for button in self.buttons.iter() {
+            sources.push(button.key.player_key());
+        }
+        let input_sink = InputSink::new(sources);
+
+        (
+            GlobalTransform::identity(),
+            Into::<Transform>::into(self.transform),
+            RootInputHistoryMarker,
+            RootAtomicDisplayMarker,
+            Name::new("** Input History"),
+            input_sink,
+            InputHistory::default(),
+        )
+    }
+
+    // The keys of the display in the order of its `InputSink`.
+    fn keys_mut(&mut self) -> Vec<&mut BoundControllerKey> {
+        let mut keys = vec![
+            &mut self.up,
+            &mut self.down,
+            &mut self.left,
+            &mut self.right,
+        ];
+        for button in self.buttons.iter_mut() {
+            keys.push(&mut button.key);
+        }
+        keys
+    }
+
+    fn bind(&mut self, root_entity: Entity) {
+        for (idx, key) in self.keys_mut().into_iter().enumerate() {
+            key.bind(root_entity, idx);
+        }
+    }
+
+    // Returns `true` if every key is bound to its entry of the root's `InputSink`.
+    // Buttons added in the inspector aren't bound until the display is regenerated.
+    fn is_bound(&self, root_entity: Entity) -> bool {
+        let directions = [&self.up, &self.down, &self.left, &self.right];
+        let buttons = self.buttons.iter().map(|button| &button.key);
+
+        directions
+            .into_iter()
+            .chain(buttons)
+            .enumerate()
+            .all(|(idx, key)| {
+                key.id
+                    == Some(InputSinkId {
+                        entity: Some(root_entity),
+                        idx,
+                    })
+            })
+    }
+
+    fn insert_row_bundle(&self, mut commands: EntityCommands, idx: usize) {
+        let position = self.orientation.offset() * self.spacing * idx as f32;
+        let text = Text::with_section("", Default::default(), self.orientation.alignment());
+
+        commands
+            .insert_bundle(Text2dBundle {
+                text,
+                transform: Transform::from_xyz(position.x, position.y, ROW_Z_OFFSET),
+                ..Default::default()
+            })
+            .insert(ChildHistoryRowMarker { idx });
+    }
+
+    // The opacity of the row at `idx`, where 0 is the newest row.
+    fn row_alpha(&self, idx: usize) -> f32 {
+        if self.length <= 1 {
+            return 1.0;
+        }
+
+        let age = idx as f32 / (self.length - 1) as f32;
+        1.0 - (1.0 - self.fade.clamp(0.0, 1.0)) * age
+    }
+}
+
+// An input state shown as one row of the history.
+#[derive(Debug, Clone, PartialEq)]
+struct HistoryEntry {
+    // The held direction in numpad notation.
+    direction: u8,
+    // Whether each of the display's buttons is held.
+    buttons: Vec<bool>,
+    // The value of `Time::seconds_since_startup` when this state started.
+    start: f64,
+}
+
+// The recent input states of an input history display, newest first.
+#[derive(Component, Debug, Default)]
+pub struct InputHistory {
+    entries: VecDeque<HistoryEntry>,
+    // The timed presses of the sink which were already added to the history, since a sink
+    // keeps its timed presses until the next poll.
+    added_presses: Vec<Vec<TimedPress>>,
+}
+
+impl InputHistory {
+    // Start a new entry if the input changed, keeping at most `length` entries.
+    fn update(&mut self, direction: u8, buttons: Vec<bool>, now: f64, length: usize) {
+        let changed = self.entries.front().map_or(true, |entry| {
+            entry.direction != direction || entry.buttons != buttons
+        });
+
+        if changed {
+            self.entries.push_front(HistoryEntry {
+                direction,
+                buttons,
+                start: now,
+            });
+            self.entries.truncate(length);
+        }
+    }
+
+    // The number of frames that the entry at `idx` was held for, up until `now` if it's
+    // still held.
+    fn hold_frames(&self, idx: usize, now: f64, frame_rate: f32) -> u64 {
+        let end = match idx {
+            0 => now,
+            idx => self.entries[idx - 1].start,
+        };
+        let frames = ((end - self.entries[idx].start) * frame_rate as f64).round() as u64;
+
+        frames.min(MAX_HOLD_FRAMES)
+    }
+}
+
+// An entity with this marker will have an `InputSink` with a source vector of the up, down,
+// left and right directions, followed by one entry per button of its `InputHistoryParams`.
+#[derive(Component)]
+pub struct RootInputHistoryMarker;
+
+#[derive(Component)]
+pub struct ChildHistoryRowMarker {
+    // The age of the entry shown by this row, where 0 is the newest entry.
+    pub idx: usize,
+}
+
+pub struct InputHistoryAtomicDisplay;
+
+impl InputHistoryAtomicDisplay {
+    // Parses the held direction in numpad notation, where opposing directions cancel out.
+    fn pressed_to_direction(pressed: &[bool]) -> u8 {
+        let x = pressed[3] as i8 - pressed[2] as i8;
+        let y = pressed[0] as i8 - pressed[1] as i8;
+
+        (5 + x + 3 * y) as u8
+    }
+
+    // Add an entry for each state of `sink` since the last poll, so that keys pressed and
+    // released between two frames still show up, then for its current state.
+    fn record_sink(
+        history: &mut InputHistory,
+        sink: &InputSink,
+        num_buttons: usize,
+        now: f64,
+        length: usize,
+    ) {
+        // The sink is rebuilt by commands after buttons are added or removed, so it may not
+        // have caught up with the params yet.
+        let num_keys = sink.keys.len();
+        if num_keys != NUM_DIRECTIONS + num_buttons {
+            return;
+        }
+        let mut pressed: Vec<bool> = (0..num_keys).map(|i| sink.is_pressed(i)).collect();
+
+        // The presses that haven't been added yet, merged in the order they were logged.
+        // Presses read on the same frame share a time, so their times can't order them.
+        let mut presses: Vec<(usize, &TimedPress)> = vec![];
+        if sink.timed_presses != history.added_presses {
+            presses = (0..num_keys)
+                .flat_map(|i| {
+                    let key_presses = sink.timed_presses.get(i).into_iter().flatten();
+                    key_presses.map(move |press| (i, press))
+                })
+                .collect();
+            presses.sort_by_key(|(_, press)| press.order);
+            history.added_presses = sink.timed_presses.clone();
+        }
+
+        // Each key starts in the opposite state of its first press.
+        for i in 0..num_keys {
+            if let Some((_, first)) = presses.iter().find(|(j, _)| *j == i) {
+                pressed[i] = !first.pressed;
+            }
+        }
+
+        for (i, press) in presses {
+            pressed[i] = press.pressed;
+            let direction = Self::pressed_to_direction(&pressed);
+            history.update(
+                direction,
+                pressed[NUM_DIRECTIONS..].to_vec(),
+                press.time,
+                length,
+            );
+        }
+
+        let pressed: Vec<bool> = (0..num_keys).map(|i| sink.is_pressed(i)).collect();
+        let direction = Self::pressed_to_direction(&pressed);
+        history.update(direction, pressed[NUM_DIRECTIONS..].to_vec(), now, length);
+    }
+
+    fn row_sections(
+        params: &InputHistoryParams,
+        history: &InputHistory,
+        idx: usize,
+        now: f64,
+        font: &Handle<Font>,
+    ) -> Vec<TextSection> {
+        let entry = match history.entries.get(idx) {
+            Some(entry) => entry,
+            None => return vec![],
+        };
+
+        let alpha = params.row_alpha(idx);
+        let section = |value: String, mut color: Color| {
+            color.set_a(color.a() * alpha);
+            TextSection {
+                value,
+                style: TextStyle {
+                    font: font.clone(),
+                    font_size: params.font_size,
+                    color,
+                },
+            }
+        };
+
+        let frames = history.hold_frames(idx, now, params.frame_rate);
+        let mut sections = vec![
+            section(format!("{:>3} ", frames), params.text_color),
+            section(
+                params.notation.write(entry.direction).to_string(),
+                params.text_color,
+            ),
+        ];
+
+        for (button, &held) in params.buttons.iter().zip(entry.buttons.iter()) {
+            if held {
+                sections.push(section(format!(" {}", button.icon), button.color));
+            }
+        }
+
+        sections
+    }
+
+    fn input_history_display_system(
+        time: Res<Time>,
+        asset_server: Res<AssetServer>,
+        mut q_parent: Query<
+            (
+                &InputSink,
+                &Children,
+                &TaggedAtomicParams,
+                &mut InputHistory,
+            ),
+            With<RootInputHistoryMarker>,
+        >,
+        mut q_child_row: Query<(&ChildHistoryRowMarker, &mut Text)>,
+    ) {
+        let now = time.seconds_since_startup();
+        let font = asset_server.load(HISTORY_FONT_PATH);
+
+        for (sink, children, tagged_params, mut history) in q_parent.iter_mut() {
+            if let TaggedAtomicParams::InputHistory(params) = tagged_params {
+                // Record the input states since the last frame
+                Self::record_sink(&mut history, sink, params.buttons.len(), now, params.length);
+
+                // Write each entry to its row
+                for child in children.iter() {
+                    if let Ok((marker, mut text)) = q_child_row.get_mut(*child) {
+                        text.sections =
+                            Self::row_sections(params, &history, marker.idx, now, &font);
+                    }
+                }
+            }
+        }
+    }
+
+    fn regenerate_system(
+        mut commands: Commands,
+        mut parent_query: Query<
+            (Entity, &mut TaggedAtomicParams, &Children),
+            (With<RootInputHistoryMarker>, Changed<TaggedAtomicParams>),
+        >,
+    ) {
+        for (root_entity, mut tagged_params, children) in parent_query.iter_mut() {
+            // Bind buttons added in the inspector, so that they can be rebound.
+            // This only writes to the params when it's needed, since writing to them
+            // regenerates the display again.
+            let needs_bind = match &*tagged_params {
+                TaggedAtomicParams::InputHistory(params) => !params.is_bound(root_entity),
+                _ => false,
+            };
+            if needs_bind {
+                if let TaggedAtomicParams::InputHistory(params) = &mut *tagged_params {
+                    params.bind(root_entity);
+                }
+            }
+
+            if let TaggedAtomicParams::InputHistory(params) = &*tagged_params {
+                // Regenerate the root entity
+                commands
+                    .entity(root_entity)
+                    .insert_bundle(params.root_bundle());
+
+                // Respawn the rows, since the length of the history may have changed
+                for &child_entity in children.iter() {
+                    commands.entity(child_entity).despawn_recursive();
+                }
+                commands.entity(root_entity).with_children(|parent| {
+                    for idx in 0..params.length {
+                        params.insert_row_bundle(parent.spawn(), idx);
+                    }
+                });
+            }
+        }
+    }
+}
+
+impl AtomicInputDisplay<InputHistoryParams> for InputHistoryAtomicDisplay {
+    fn spawn(commands: &mut Commands, params: &InputHistoryParams) -> Entity {
+        let mut my_params = params.clone();
+        let mut root = commands.spawn_bundle(my_params.root_bundle());
+        let root_entity = root.id();
+
+        my_params.bind(root_entity);
+
+        root.insert(TaggedAtomicParams::InputHistory(my_params))
+            .with_children(|parent| {
+                for idx in 0..params.length {
+                    params.insert_row_bundle(parent.spawn(), idx);
+                }
+            })
+            .id()
+    }
+
+    fn add_update_systems(app: &mut App) {
+        app.add_system(Self::input_history_display_system);
+        app.add_system(Self::regenerate_system);
+        app.register_inspectable::<InputHistoryParams>();
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,67 @@
+// Tests of recording an input history display's `InputSink` into history entries.
+
+use bevy::prelude::KeyCode;
+
+use super::{InputHistory, InputHistoryAtomicDisplay, NUM_DIRECTIONS};
+use crate::input::{
+    events::TimedPress,
+    input::{InputSink, InputSource},
+};
+
+// A sink with the four directions and `num_buttons` buttons, none of them bound.
+fn history_sink(num_buttons: usize) -> InputSink {
+    InputSink::new(vec![None; NUM_DIRECTIONS + num_buttons])
+}
+
+fn press(time: f64, order: usize) -> TimedPress {
+    TimedPress {
+        source: InputSource::Key(KeyCode::A),
+        pressed: true,
+        time,
+        order,
+    }
+}
+
+// The direction and buttons of each entry, oldest first.
+fn entries(history: &InputHistory) -> Vec<(u8, Vec<bool>)> {
+    history
+        .entries
+        .iter()
+        .rev()
+        .map(|entry| (entry.direction, entry.buttons.clone()))
+        .collect()
+}
+
+#[test]
+fn presses_at_the_same_time_are_recorded_in_log_order() {
+    let mut sink = history_sink(1);
+    let mut history = InputHistory::default();
+
+    // The button was pressed before up, on the same frame.
+    sink.timed_presses[0] = vec![press(1.0, 1)];
+    sink.timed_presses[NUM_DIRECTIONS] = vec![press(1.0, 0)];
+    sink.pressed[0] = true;
+    sink.pressed[NUM_DIRECTIONS] = true;
+
+    InputHistoryAtomicDisplay::record_sink(&mut history, &sink, 1, 1.5, 10);
+    assert_eq!(entries(&history), vec![(5, vec![true]), (8, vec![true])]);
+
+    // The sink keeps its presses until the next poll, but they're only recorded once.
+    InputHistoryAtomicDisplay::record_sink(&mut history, &sink, 1, 1.75, 10);
+    assert_eq!(history.entries.len(), 2);
+}
+
+#[test]
+fn sinks_not_rebuilt_for_the_buttons_yet_are_skipped() {
+    let mut sink = history_sink(1);
+    let mut history = InputHistory::default();
+    sink.timed_presses[NUM_DIRECTIONS] = vec![press(1.0, 0)];
+    sink.pressed[NUM_DIRECTIONS] = true;
+
+    // A button was added to the params, but the sink still has the old number of keys.
+    InputHistoryAtomicDisplay::record_sink(&mut history, &sink, 2, 1.5, 10);
+    assert!(history.entries.is_empty());
+
+    InputHistoryAtomicDisplay::record_sink(&mut history, &sink, 1, 1.5, 10);
+    assert_eq!(entries(&history), vec![(5, vec![true])]);
+}
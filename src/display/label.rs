@@ -0,0 +1,288 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+use serde::{Deserialize, Serialize};
+
+use crate::{editor::inspector::BoundControllerKey, input::input::InputSink};
+
+use super::{
+    display::{AtomicInputDisplay, RootAtomicDisplayMarker, TaggedAtomicParams},
+    serialization::TransformDef,
+};
+
+// The placeholder in a label's text which is replaced by the binding of its key.
+pub const BINDING_PLACEHOLDER: &'static str = "{binding}";
+
+// The z offsets of the child entities, so that the text draws over its outline.
+const OUTLINE_Z_OFFSET: f32 = 0.1;
+const TEXT_Z_OFFSET: f32 = 0.2;
+
+// The directions in which copies of the text are offset to draw its outline.
+const OUTLINE_DIRECTIONS: [(f32, f32); 8] = [
+    (1.0, 0.0),
+    (1.0, 1.0),
+    (0.0, 1.0),
+    (-1.0, 1.0),
+    (-1.0, 0.0),
+    (-1.0, -1.0),
+    (0.0, -1.0),
+    (1.0, -1.0),
+];
+
+// The font of a label.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Inspectable)]
+pub enum LabelFont {
+    FiraSans,
+    FiraMono,
+    // The path of a font file, relative to the assets directory.
+    // Labels use `FiraSans` until a path is entered.
+    Custom(String),
+}
+
+impl LabelFont {
+    pub fn path(&self) -> &str {
+        match self {
+            LabelFont::FiraMono => "fonts/FiraMono-Medium.ttf",
+            LabelFont::Custom(path) if !path.is_empty() => path,
+            _ => "fonts/FiraSans-Bold.ttf",
+        }
+    }
+}
+
+impl Default for LabelFont {
+    fn default() -> Self {
+        LabelFont::FiraSans
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Inspectable)]
+pub enum LabelAlignment {
+    Left,
+    Center,
+    Right,
+}
+
+impl Default for LabelAlignment {
+    fn default() -> Self {
+        LabelAlignment::Center
+    }
+}
+
+impl Into<TextAlignment> for LabelAlignment {
+    fn into(self) -> TextAlignment {
+        let horizontal = match self {
+            LabelAlignment::Left => HorizontalAlign::Left,
+            LabelAlignment::Center => HorizontalAlign::Center,
+            LabelAlignment::Right => HorizontalAlign::Right,
+        };
+
+        TextAlignment {
+            vertical: VerticalAlign::Center,
+            horizontal,
+        }
+    }
+}
+
+// The data parameterizing a text label display.
+#[derive(Debug, Clone, Serialize, Deserialize, Component, Inspectable)]
+pub struct LabelParams {
+    #[inspectable(label = "Text ({binding} shows the key's binding)")]
+    pub text: String,
+    #[inspectable(label = "Key")]
+    pub binding_key: BoundControllerKey,
+    #[inspectable(label = "Transform")]
+    pub transform: TransformDef,
+    #[inspectable(label = "Font")]
+    pub font: LabelFont,
+    #[inspectable(label = "Font size", min = 1.0, suffix = "px")]
+    pub font_size: f32,
+    #[inspectable(label = "Color", alpha = true)]
+    pub color: Color,
+    // A thickness of 0 draws no outline.
+    #[inspectable(label = "Outline thickness", min = 0.0, suffix = "px")]
+    pub outline_thickness: f32,
+    #[inspectable(label = "Outline color", alpha = true)]
+    pub outline_color: Color,
+    #[inspectable(label = "Alignment")]
+    pub alignment: LabelAlignment,
+    // The counterclockwise rotation of the text, in degrees.
+    #[inspectable(label = "Rotation", min = 0.0, max = 360.0, suffix = "°")]
+    pub rotation: f32,
+}
+
+impl Default for LabelParams {
+    fn default() -> Self {
+        Self {
+            text: "Label".to_string(),
+            binding_key: Default::default(),
+            transform: Default::default(),
+            font: Default::default(),
+            font_size: 20.0,
+            color: Color::BLACK,
+            outline_thickness: 0.0,
+            outline_color: Color::WHITE,
+            alignment: Default::default(),
+            rotation: 0.0,
+        }
+    }
+}
+
+impl LabelParams {
+    fn root_bundle(&self) -> impl Bundle {
+        (
+            GlobalTransform::identity(),
+            Into::<Transform>::into(self.transform),
+            RootLabelMarker,
+            RootAtomicDisplayMarker,
+            Name::new("** Label"),
+            InputSink::new(vec![self.binding_key.player_key()]),
+        )
+    }
+
+    // Insert a copy of the text, offset by `offset` before it's rotated.
+    // The text itself is written by `label_display_system`, once the font is loaded.
+    fn insert_text_bundle(&self, mut commands: EntityCommands, offset: Vec2, z: f32) {
+        let rotation = Quat::from_rotation_z(self.rotation.to_radians());
+        let translation = rotation * offset.extend(z);
+
+        commands
+            .insert_bundle(Text2dBundle {
+                text: Text::with_section("", Default::default(), self.alignment.into()),
+                transform: Transform {
+                    translation,
+                    rotation,
+                    ..Transform::identity()
+                },
+                ..Default::default()
+            })
+            .insert(ChildLabelTextMarker {
+                outline: offset != Vec2::ZERO,
+            });
+    }
+
+    fn spawn_children(&self, parent: &mut ChildBuilder) {
+        if self.outline_thickness > 0.0 {
+            for (x, y) in OUTLINE_DIRECTIONS {
+                let offset = Vec2::new(x, y).normalize() * self.outline_thickness;
+                self.insert_text_bundle(parent.spawn(), offset, OUTLINE_Z_OFFSET);
+            }
+        }
+        self.insert_text_bundle(parent.spawn(), Vec2::ZERO, TEXT_Z_OFFSET);
+    }
+
+    // The text of the label, with the placeholder replaced by the binding of its key.
+    fn display_text(&self, sink: &InputSink) -> String {
+        if !self.text.contains(BINDING_PLACEHOLDER) {
+            return self.text.clone();
+        }
+
+        let binding_name = match sink.bindings.first() {
+            Some(Some(binding)) => binding
+                .chords
+                .first()
+                .map_or("-".to_string(), |chord| chord.to_string()),
+            _ => "-".to_string(),
+        };
+        self.text.replace(BINDING_PLACEHOLDER, &binding_name)
+    }
+}
+
+// An entity with this marker will have an `InputSink` with a source vector of 1 entry,
+// whose binding is shown in place of `BINDING_PLACEHOLDER`.
+#[derive(Component)]
+pub struct RootLabelMarker;
+
+#[derive(Component)]
+pub struct ChildLabelTextMarker {
+    // Whether this copy of the text is part of the outline.
+    pub outline: bool,
+}
+
+pub struct LabelAtomicDisplay;
+
+impl LabelAtomicDisplay {
+    // Write the text of every label, which changes along with the binding of its key.
+    fn label_display_system(
+        asset_server: Res<AssetServer>,
+        q_parent: Query<(&InputSink, &Children, &TaggedAtomicParams), With<RootLabelMarker>>,
+        mut q_child_text: Query<(&ChildLabelTextMarker, &mut Text)>,
+    ) {
+        for (sink, children, tagged_params) in q_parent.iter() {
+            if let TaggedAtomicParams::Label(params) = tagged_params {
+                let value = params.display_text(sink);
+                let font = asset_server.load(params.font.path());
+
+                for child in children.iter() {
+                    if let Ok((marker, mut text)) = q_child_text.get_mut(*child) {
+                        // Only write the text when it changes, so that it isn't laid out again
+                        // every frame.
+                        let is_current = text
+                            .sections
+                            .first()
+                            .map_or(false, |s| s.value == value && s.style.font == font);
+                        if is_current {
+                            continue;
+                        }
+
+                        let color = if marker.outline {
+                            params.outline_color
+                        } else {
+                            params.color
+                        };
+                        text.sections = vec![TextSection {
+                            value: value.clone(),
+                            style: TextStyle {
+                                font: font.clone(),
+                                font_size: params.font_size,
+                                color,
+                            },
+                        }];
+                    }
+                }
+            }
+        }
+    }
+
+    fn regenerate_system(
+        mut commands: Commands,
+        parent_query: Query<
+            (Entity, &TaggedAtomicParams, &Children),
+            (With<RootLabelMarker>, Changed<TaggedAtomicParams>),
+        >,
+    ) {
+        for (root_entity, tagged_params, children) in parent_query.iter() {
+            if let TaggedAtomicParams::Label(params) = tagged_params {
+                // Regenerate the root entity
+                commands
+                    .entity(root_entity)
+                    .insert_bundle(params.root_bundle());
+
+                // Respawn the child entities, since the outline may have been added or removed
+                for &child_entity in children.iter() {
+                    commands.entity(child_entity).despawn_recursive();
+                }
+                commands
+                    .entity(root_entity)
+                    .with_children(|parent| params.spawn_children(parent));
+            }
+        }
+    }
+}
+
+impl AtomicInputDisplay<LabelParams> for LabelAtomicDisplay {
+    fn spawn(commands: &mut Commands, params: &LabelParams) -> Entity {
+        let mut my_params = params.clone();
+        let mut root = commands.spawn_bundle(my_params.root_bundle());
+
+        my_params.binding_key.bind(root.id(), 0);
+
+        root.insert(TaggedAtomicParams::Label(my_params))
+            .with_children(|parent| params.spawn_children(parent))
+            .id()
+    }
+
+    fn add_update_systems(app: &mut App) {
+        app.add_system(Self::label_display_system);
+        app.add_system(Self::regenerate_system);
+        app.register_inspectable::<LabelParams>();
+    }
+}
@@ -2,9 +2,16 @@ pub mod display;
 pub mod present;
 pub mod renderable;
 pub mod serialization;
+pub mod socd;
+#[cfg(test)]
+mod socd_tests;
 pub mod system;
 
 pub mod analog_stick;
 pub mod button;
 pub mod frame;
+pub mod input_history;
+pub mod label;
+pub mod mouse;
 pub mod test;
+pub mod trigger;
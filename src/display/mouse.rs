@@ -0,0 +1,287 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+use bevy_prototype_lyon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{editor::inspector::BoundControllerKey, input::input::InputSink};
+
+use super::{
+    display::{AtomicInputDisplay, RootAtomicDisplayMarker, TaggedAtomicParams},
+    renderable::Renderable,
+    serialization::{CircleDef, DrawModeDef, RectangleDef, TransformDef},
+};
+
+// The number of mouse buttons shown on a mouse display, from left to right.
+const NUM_MOUSE_BUTTONS: usize = 3;
+
+// The z offsets of the child entities, so that buttons and the motion indicator draw over the body.
+const BUTTON_Z_OFFSET: f32 = 0.1;
+const MOTION_Z_OFFSET: f32 = 0.2;
+
+// The data parameterizing a mouse input display.
+#[derive(Debug, Clone, Serialize, Deserialize, Component, Inspectable)]
+pub struct MouseParams {
+    #[inspectable(label = "Left button")]
+    pub left: BoundControllerKey,
+    #[inspectable(label = "Middle button")]
+    pub middle: BoundControllerKey,
+    #[inspectable(label = "Right button")]
+    pub right: BoundControllerKey,
+    #[inspectable(label = "X+ motion")]
+    pub pos_x: BoundControllerKey,
+    #[inspectable(label = "X- motion")]
+    pub neg_x: BoundControllerKey,
+    #[inspectable(label = "Y+ motion")]
+    pub pos_y: BoundControllerKey,
+    #[inspectable(label = "Y- motion")]
+    pub neg_y: BoundControllerKey,
+    #[inspectable(label = "Transform")]
+    pub transform: TransformDef,
+    #[inspectable(label = "Body model")]
+    pub body_display: Renderable,
+    #[inspectable(label = "Body texture")]
+    pub body_mode: DrawModeDef,
+    #[inspectable(label = "Button model")]
+    pub button_display: Renderable,
+    #[inspectable(label = "Button spacing", min = 0.0, suffix = "px")]
+    pub button_spacing: f32,
+    #[inspectable(label = "Button height", suffix = "px")]
+    pub button_height: f32,
+    #[inspectable(label = "Button on texture")]
+    pub button_on_mode: DrawModeDef,
+    #[inspectable(label = "Button off texture")]
+    pub button_off_mode: DrawModeDef,
+    #[inspectable(label = "Motion model")]
+    pub motion_display: Renderable,
+    #[inspectable(label = "Motion texture")]
+    pub motion_mode: DrawModeDef,
+    #[inspectable(label = "Motion radius", min = 0.0, suffix = "px")]
+    pub motion_radius: f32,
+}
+
+impl Default for MouseParams {
+    fn default() -> Self {
+        let body_mode = DrawMode::Outlined {
+            fill_mode: FillMode::color(Color::NONE),
+            outline_mode: StrokeMode::new(Color::BLACK, 3.0),
+        }
+        .into();
+
+        let button_on_mode = DrawMode::Outlined {
+            fill_mode: FillMode::color(Color::RED),
+            outline_mode: StrokeMode::new(Color::BLACK, 1.0),
+        }
+        .into();
+
+        let button_off_mode = DrawMode::Outlined {
+            fill_mode: FillMode::color(Color::WHITE),
+            outline_mode: StrokeMode::new(Color::BLACK, 1.0),
+        }
+        .into();
+
+        let motion_mode = DrawMode::Fill(FillMode::color(Color::BLACK)).into();
+
+        Self {
+            left: Default::default(),
+            middle: Default::default(),
+            right: Default::default(),
+            pos_x: Default::default(),
+            neg_x: Default::default(),
+            pos_y: Default::default(),
+            neg_y: Default::default(),
+            transform: Default::default(),
+            body_display: Renderable::Rectangle(RectangleDef {
+                extents: Vec2::new(50.0, 70.0),
+            }),
+            body_mode,
+            button_display: Renderable::Rectangle(RectangleDef {
+                extents: Vec2::new(12.0, 18.0),
+            }),
+            button_spacing: 15.0,
+            button_height: 20.0,
+            button_on_mode,
+            button_off_mode,
+            motion_display: Renderable::Circle(CircleDef { radius: 5.0 }),
+            motion_mode,
+            motion_radius: 15.0,
+        }
+    }
+}
+
+impl MouseParams {
+    fn root_bundle(&self) -> impl Bundle {
+        // Collect the input sources needed by this display
+        let sources = vec![
+            self.left.player_key(),
+            self.middle.player_key(),
+            self.right.player_key(),
+            self.pos_x.player_key(),
+            self.neg_x.player_key(),
+            self.pos_y.player_key(),
+            self.neg_y.player_key(),
+        ];
+        let input_sink = InputSink::new(sources);
+
+        (
+            GlobalTransform::identity(),
+            Into::<Transform>::into(self.transform),
+            RootMouseMarker,
+            RootAtomicDisplayMarker,
+            Name::new("** Mouse"),
+            input_sink,
+        )
+    }
+
+    fn insert_body_bundle(&self, mut commands: EntityCommands) {
+        self.body_display.insert_bundle(
+            &mut commands,
+            self.body_mode.into(),
+            Transform::identity(),
+        );
+        commands.insert(ChildMouseBodyMarker);
+    }
+
+    fn insert_button_bundle(&self, mut commands: EntityCommands, idx: usize) {
+        // Lay the buttons out left to right, centered horizontally.
+        let x = (idx as f32 - (NUM_MOUSE_BUTTONS - 1) as f32 / 2.0) * self.button_spacing;
+        let transform = Transform::from_xyz(x, self.button_height, BUTTON_Z_OFFSET);
+
+        self.button_display
+            .insert_bundle(&mut commands, self.button_off_mode.into(), transform);
+        commands.insert(ChildMouseButtonMarker { idx });
+    }
+
+    fn insert_motion_bundle(&self, mut commands: EntityCommands) {
+        let transform = Transform::from_xyz(0.0, 0.0, MOTION_Z_OFFSET);
+
+        self.motion_display
+            .insert_bundle(&mut commands, self.motion_mode.into(), transform);
+        commands.insert(ChildMouseMotionMarker);
+    }
+}
+
+// An entity with this marker will have an `InputSink` with a source vector of 7 entries:
+// the left, middle and right buttons, followed by the X+, X-, Y+ and Y- motion axes.
+#[derive(Component)]
+pub struct RootMouseMarker;
+
+#[derive(Component)]
+pub struct ChildMouseBodyMarker;
+
+#[derive(Component)]
+pub struct ChildMouseButtonMarker {
+    // The index of this button in the root's `InputSink`.
+    pub idx: usize,
+}
+
+#[derive(Component)]
+pub struct ChildMouseMotionMarker;
+
+pub struct MouseAtomicDisplay;
+
+impl MouseAtomicDisplay {
+    // Parses the direction of mouse motion as a `Vec2` of length at most 1.
+    fn values_to_motion(sink: &InputSink) -> Vec2 {
+        let motion = Vec2::new(
+            sink.axis_value(3) - sink.axis_value(4),
+            sink.axis_value(5) - sink.axis_value(6),
+        );
+
+        if motion.length() > 1.0 {
+            motion.normalize()
+        } else {
+            motion
+        }
+    }
+
+    fn mouse_display_system(
+        q_parent: Query<(&InputSink, &Children, &TaggedAtomicParams), With<RootMouseMarker>>,
+        mut q_child_button: Query<(&ChildMouseButtonMarker, &mut DrawMode)>,
+        mut q_child_motion: Query<&mut Transform, With<ChildMouseMotionMarker>>,
+    ) {
+        for (sink, children, tagged_params) in q_parent.iter() {
+            if let TaggedAtomicParams::Mouse(params) = tagged_params {
+                for child in children.iter() {
+                    // Swap the button textures according to the button input
+                    if let Ok((marker, mut draw_mode)) = q_child_button.get_mut(*child) {
+                        *draw_mode = if sink.is_pressed(marker.idx) {
+                            params.button_on_mode.into()
+                        } else {
+                            params.button_off_mode.into()
+                        };
+                    }
+
+                    // Move the motion indicator in the direction of mouse motion
+                    if let Ok(mut transform) = q_child_motion.get_mut(*child) {
+                        let motion = Self::values_to_motion(sink);
+                        transform.translation.x = motion.x * params.motion_radius;
+                        transform.translation.y = motion.y * params.motion_radius;
+                    }
+                }
+            }
+        }
+    }
+
+    fn regenerate_system(
+        mut commands: Commands,
+        parent_query: Query<
+            (Entity, &TaggedAtomicParams, &Children),
+            (With<RootMouseMarker>, Changed<TaggedAtomicParams>),
+        >,
+        child_body_query: Query<Entity, With<ChildMouseBodyMarker>>,
+        child_button_query: Query<&ChildMouseButtonMarker>,
+        child_motion_query: Query<Entity, With<ChildMouseMotionMarker>>,
+    ) {
+        for (root_entity, tagged_params, children) in parent_query.iter() {
+            if let TaggedAtomicParams::Mouse(params) = tagged_params {
+                // Regenerate the root entity
+                commands
+                    .entity(root_entity)
+                    .insert_bundle(params.root_bundle());
+
+                // Rengenerate the child entities
+                for &child_entity in children.iter() {
+                    if let Ok(body_entity) = child_body_query.get(child_entity) {
+                        params.insert_body_bundle(commands.entity(body_entity));
+                    } else if let Ok(marker) = child_button_query.get(child_entity) {
+                        params.insert_button_bundle(commands.entity(child_entity), marker.idx);
+                    } else if let Ok(motion_entity) = child_motion_query.get(child_entity) {
+                        params.insert_motion_bundle(commands.entity(motion_entity));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl AtomicInputDisplay<MouseParams> for MouseAtomicDisplay {
+    fn spawn(commands: &mut Commands, params: &MouseParams) -> Entity {
+        let mut my_params = params.clone();
+        let mut root = commands.spawn_bundle(my_params.root_bundle());
+        let root_entity = root.id();
+
+        my_params.left.bind(root_entity, 0);
+        my_params.middle.bind(root_entity, 1);
+        my_params.right.bind(root_entity, 2);
+        my_params.pos_x.bind(root_entity, 3);
+        my_params.neg_x.bind(root_entity, 4);
+        my_params.pos_y.bind(root_entity, 5);
+        my_params.neg_y.bind(root_entity, 6);
+
+        root.insert(TaggedAtomicParams::Mouse(my_params))
+            .with_children(|parent| {
+                params.insert_body_bundle(parent.spawn());
+                for idx in 0..NUM_MOUSE_BUTTONS {
+                    params.insert_button_bundle(parent.spawn(), idx);
+                }
+                params.insert_motion_bundle(parent.spawn());
+            })
+            .id()
+    }
+
+    fn add_update_systems(app: &mut App) {
+        app.add_system(Self::mouse_display_system);
+        app.add_system(Self::regenerate_system);
+        app.register_inspectable::<MouseParams>();
+    }
+}
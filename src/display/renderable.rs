@@ -19,6 +19,29 @@ impl Default for Renderable {
 }
 
 impl Renderable {
+    // The corners of the smallest axis-aligned box containing the model, relative to its
+    // entity's position.
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        match *self {
+            Renderable::None => (Vec2::ZERO, Vec2::ZERO),
+            Renderable::RegularPolygon(rp) => {
+                let angle = std::f32::consts::PI / rp.sides as f32;
+                let radius = match rp.feature {
+                    RegularPolygonFeatureDef::Radius(r) => r,
+                    RegularPolygonFeatureDef::Apothem(a) => a / angle.cos(),
+                    RegularPolygonFeatureDef::SideLength(s) => s / (2.0 * angle.sin()),
+                };
+                (
+                    rp.center - Vec2::splat(radius),
+                    rp.center + Vec2::splat(radius),
+                )
+            }
+            Renderable::Circle(c) => (Vec2::splat(-c.radius), Vec2::splat(c.radius)),
+            // Rectangles are drawn from their bottom left corner.
+            Renderable::Rectangle(r) => (Vec2::ZERO, r.extents),
+        }
+    }
+
     pub fn insert_bundle(
         &self,
         commands: &mut EntityCommands,
@@ -6,6 +6,8 @@ use bevy_prototype_lyon::{
 };
 use serde::{Deserialize, Serialize};
 
+use crate::util::lerp_color;
+
 // Serialization type for `RegularPolygon`
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Inspectable)]
@@ -224,6 +226,41 @@ impl From<DrawMode> for DrawModeDef {
     }
 }
 
+impl DrawModeDef {
+    // Interpolate the colors and thicknesses of two draw modes, where `t` = 0.0 is `self`
+    // and 1.0 is `other`. Modes of different kinds can't be mixed, so the nearest one is used.
+    pub fn lerp(self, other: DrawModeDef, t: f32) -> DrawModeDef {
+        let fill = |a: FillModeDef, b: FillModeDef| FillModeDef {
+            options: a.options,
+            color: lerp_color(a.color, b.color, t),
+        };
+        let stroke = |a: StrokeModeDef, b: StrokeModeDef| StrokeModeDef {
+            thickness: a.thickness + (b.thickness - a.thickness) * t,
+            color: lerp_color(a.color, b.color, t),
+        };
+
+        match (self, other) {
+            (Self::Fill(a), Self::Fill(b)) => Self::Fill(fill(a, b)),
+            (Self::Stroke(a), Self::Stroke(b)) => Self::Stroke(stroke(a, b)),
+            (
+                Self::Outlined {
+                    Fill: a_fill,
+                    Border: a_border,
+                },
+                Self::Outlined {
+                    Fill: b_fill,
+                    Border: b_border,
+                },
+            ) => Self::Outlined {
+                Fill: fill(a_fill, b_fill),
+                Border: stroke(a_border, b_border),
+            },
+            _ if t < 0.5 => self,
+            _ => other,
+        }
+    }
+}
+
 impl Default for DrawModeDef {
     fn default() -> Self {
         DrawModeDef::Fill(Default::default())
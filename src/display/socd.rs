@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
+
+// How an analog stick display resolves simultaneous opposing cardinal directions (SOCD),
+// which happen when the directions of a stick are bound to separate keys or buttons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Inspectable)]
+pub enum SocdMode {
+    // Opposing directions cancel out, leaving the axis at rest.
+    Neutral,
+    // The direction pressed most recently wins.
+    LastInputWins,
+    // The direction pressed first wins until it's released.
+    FirstInputWins,
+    // Up wins over down, and left and right cancel out.
+    UpPriority,
+}
+
+impl Default for SocdMode {
+    fn default() -> Self {
+        SocdMode::Neutral
+    }
+}
+
+// The history of one axis of a stick needed to resolve SOCD.
+#[derive(Debug, Clone, Copy, Default)]
+struct SocdAxisState {
+    // Whether the positive and negative directions were held on the previous update.
+    was_held: (bool, bool),
+    // Whether the positive direction was the most recently pressed one.
+    pos_newest: bool,
+}
+
+impl SocdAxisState {
+    // Resolve the position of an axis from the values of its two directions.
+    // `pos_priority` is the result of an SOCD under `SocdMode::UpPriority`.
+    fn resolve(&mut self, pos: f32, neg: f32, mode: SocdMode, pos_priority: bool) -> f32 {
+        let held = (pos > 0.0, neg > 0.0);
+        if held.0 && !self.was_held.0 {
+            self.pos_newest = true;
+        } else if held.1 && !self.was_held.1 {
+            self.pos_newest = false;
+        }
+        self.was_held = held;
+
+        if !(held.0 && held.1) {
+            return pos - neg;
+        }
+
+        let pos_wins = match mode {
+            SocdMode::Neutral => return 0.0,
+            SocdMode::LastInputWins => self.pos_newest,
+            SocdMode::FirstInputWins => !self.pos_newest,
+            SocdMode::UpPriority if pos_priority => true,
+            SocdMode::UpPriority => return 0.0,
+        };
+
+        if pos_wins {
+            pos
+        } else {
+            -neg
+        }
+    }
+}
+
+// The SOCD history of an analog stick display, stored on its root entity.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct SocdState {
+    x: SocdAxisState,
+    y: SocdAxisState,
+}
+
+impl SocdState {
+    // Resolve the position of a stick from the values of its X+, X-, Y+ and Y- directions.
+    // If `normalize` is set, positions outside the unit circle (such as a digital diagonal)
+    // are scaled back onto it.
+    pub fn resolve(&mut self, values: [f32; 4], mode: SocdMode, normalize: bool) -> Vec2 {
+        let [pos_x, neg_x, pos_y, neg_y] = values;
+        let pos = Vec2::new(
+            self.x.resolve(pos_x, neg_x, mode, false),
+            self.y.resolve(pos_y, neg_y, mode, true),
+        );
+
+        if normalize && pos.length() > 1.0 {
+            pos.normalize()
+        } else {
+            pos
+        }
+    }
+}
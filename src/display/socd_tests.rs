@@ -0,0 +1,121 @@
+// Tests of resolving simultaneous opposing directions on analog stick displays.
+
+use bevy::prelude::Vec2;
+
+use super::socd::{SocdMode, SocdState};
+
+const RIGHT: [f32; 4] = [1.0, 0.0, 0.0, 0.0];
+const LEFT: [f32; 4] = [0.0, 1.0, 0.0, 0.0];
+const LEFT_RIGHT: [f32; 4] = [1.0, 1.0, 0.0, 0.0];
+const UP_DOWN: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+const ALL: [f32; 4] = [1.0; 4];
+
+// Resolve each frame of `frames` in order, returning the stick position after each one.
+fn resolve_frames(mode: SocdMode, frames: &[[f32; 4]]) -> Vec<Vec2> {
+    let mut state = SocdState::default();
+    frames
+        .iter()
+        .map(|&values| state.resolve(values, mode, false))
+        .collect()
+}
+
+fn x(positions: Vec<Vec2>) -> Vec<f32> {
+    positions.into_iter().map(|pos| pos.x).collect()
+}
+
+#[test]
+fn neutral_cancels_opposing_directions() {
+    assert_eq!(
+        x(resolve_frames(
+            SocdMode::Neutral,
+            &[RIGHT, LEFT_RIGHT, LEFT, LEFT_RIGHT]
+        )),
+        vec![1.0, 0.0, -1.0, 0.0]
+    );
+    assert_eq!(
+        resolve_frames(SocdMode::Neutral, &[UP_DOWN]),
+        vec![Vec2::ZERO]
+    );
+}
+
+#[test]
+fn last_input_wins_follows_the_newest_direction() {
+    assert_eq!(
+        x(resolve_frames(
+            SocdMode::LastInputWins,
+            &[RIGHT, LEFT_RIGHT, LEFT_RIGHT, RIGHT]
+        )),
+        vec![1.0, -1.0, -1.0, 1.0]
+    );
+
+    // Pressing a held direction again takes over from the opposing one.
+    assert_eq!(
+        x(resolve_frames(
+            SocdMode::LastInputWins,
+            &[LEFT, LEFT_RIGHT, RIGHT, LEFT_RIGHT]
+        )),
+        vec![-1.0, 1.0, 1.0, -1.0]
+    );
+}
+
+#[test]
+fn first_input_wins_keeps_the_held_direction() {
+    assert_eq!(
+        x(resolve_frames(
+            SocdMode::FirstInputWins,
+            &[RIGHT, LEFT_RIGHT, LEFT_RIGHT, LEFT]
+        )),
+        vec![1.0, 1.0, 1.0, -1.0]
+    );
+
+    // Once the first direction is released and pressed again, the other one was first.
+    assert_eq!(
+        x(resolve_frames(
+            SocdMode::FirstInputWins,
+            &[RIGHT, LEFT_RIGHT, LEFT, LEFT_RIGHT]
+        )),
+        vec![1.0, 1.0, -1.0, -1.0]
+    );
+}
+
+#[test]
+fn directions_pressed_on_the_same_frame_count_positive_as_newest() {
+    assert_eq!(
+        x(resolve_frames(SocdMode::LastInputWins, &[LEFT_RIGHT])),
+        vec![1.0]
+    );
+    assert_eq!(
+        x(resolve_frames(SocdMode::FirstInputWins, &[LEFT_RIGHT])),
+        vec![-1.0]
+    );
+}
+
+#[test]
+fn up_priority_favors_up_and_cancels_left_and_right() {
+    assert_eq!(
+        resolve_frames(SocdMode::UpPriority, &[ALL, UP_DOWN, [0.0, 0.0, 0.0, 1.0]]),
+        vec![
+            Vec2::new(0.0, 1.0),
+            Vec2::new(0.0, 1.0),
+            Vec2::new(0.0, -1.0)
+        ]
+    );
+}
+
+#[test]
+fn digital_diagonals_are_normalized_onto_the_unit_circle() {
+    let mut state = SocdState::default();
+    let diagonal = state.resolve([1.0, 0.0, 1.0, 0.0], SocdMode::Neutral, true);
+    assert!((diagonal - Vec2::new(1.0, 1.0).normalize()).length() < 1e-6);
+
+    assert_eq!(
+        state.resolve([1.0, 0.0, 1.0, 0.0], SocdMode::Neutral, false),
+        Vec2::new(1.0, 1.0)
+    );
+
+    // Positions already inside the circle are left alone.
+    assert_eq!(
+        state.resolve([0.5, 0.0, 0.0, 0.5], SocdMode::Neutral, true),
+        Vec2::new(0.5, -0.5)
+    );
+}
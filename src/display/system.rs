@@ -14,6 +14,10 @@ use super::{
         RootAtomicDisplayMarker, SerialInputDisplay, TaggedAtomicParams,
     },
     frame::FrameAtomicDisplay,
+    input_history::{InputHistoryAtomicDisplay, InputHistoryParams},
+    label::{LabelAtomicDisplay, LabelParams},
+    mouse::{MouseAtomicDisplay, MouseParams},
+    trigger::{TriggerAtomicDisplay, TriggerParams},
 };
 
 pub fn spawn_atomic_display(mut commands: &mut Commands, mut atom: &mut AtomicDisplay) {
@@ -24,6 +28,10 @@ pub fn spawn_atomic_display(mut commands: &mut Commands, mut atom: &mut AtomicDi
             AnalogStickAtomicDisplay::spawn(&mut commands, &asp)
         }
         TaggedAtomicParams::Frame(f) => FrameAtomicDisplay::spawn(&mut commands, &f),
+        TaggedAtomicParams::Mouse(m) => MouseAtomicDisplay::spawn(&mut commands, &m),
+        TaggedAtomicParams::InputHistory(h) => InputHistoryAtomicDisplay::spawn(&mut commands, &h),
+        TaggedAtomicParams::Trigger(t) => TriggerAtomicDisplay::spawn(&mut commands, &t),
+        TaggedAtomicParams::Label(l) => LabelAtomicDisplay::spawn(&mut commands, &l),
     };
 
     // Record the root entity associated to `atom`.
@@ -61,6 +69,26 @@ fn handle_request_spawn_atom_system(
                             ..Default::default()
                         }))
                     }
+                    AtomicParamsTag::Mouse => Box::new(TaggedAtomicParams::Mouse(MouseParams {
+                        transform: Transform::from_xyz(pos.x, pos.y, 0.0).into(),
+                        ..Default::default()
+                    })),
+                    AtomicParamsTag::Trigger => {
+                        Box::new(TaggedAtomicParams::Trigger(TriggerParams {
+                            transform: Transform::from_xyz(pos.x, pos.y, 0.0).into(),
+                            ..Default::default()
+                        }))
+                    }
+                    AtomicParamsTag::InputHistory => {
+                        Box::new(TaggedAtomicParams::InputHistory(InputHistoryParams {
+                            transform: Transform::from_xyz(pos.x, pos.y, 0.0).into(),
+                            ..Default::default()
+                        }))
+                    }
+                    AtomicParamsTag::Label => Box::new(TaggedAtomicParams::Label(LabelParams {
+                        transform: Transform::from_xyz(pos.x, pos.y, 0.0).into(),
+                        ..Default::default()
+                    })),
                 };
 
                 spawn_atomic_display(&mut commands, &mut AtomicDisplay { params, entity })
@@ -184,4 +212,8 @@ pub fn add_display_systems(app: &mut App) {
     ButtonAtomicDisplay::add_update_systems(app);
     AnalogStickAtomicDisplay::add_update_systems(app);
     FrameAtomicDisplay::add_update_systems(app);
+    MouseAtomicDisplay::add_update_systems(app);
+    InputHistoryAtomicDisplay::add_update_systems(app);
+    TriggerAtomicDisplay::add_update_systems(app);
+    LabelAtomicDisplay::add_update_systems(app);
 }
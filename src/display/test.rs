@@ -136,6 +136,7 @@ pub fn debug_button_data() -> Vec<Box<TaggedAtomicParams>> {
             displayable: Renderable::RegularPolygon(shape),
             transform: Transform::from_xyz(z, z, 0.0).into(),
             button_key: button_key.into(),
+            pressure: Default::default(),
         })));
     }
 
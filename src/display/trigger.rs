@@ -0,0 +1,308 @@
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use bevy_inspector_egui::{Inspectable, RegisterInspectable};
+use bevy_prototype_lyon::{
+    prelude::*,
+    shapes::{Circle, Line},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    editor::inspector::BoundControllerKey,
+    input::{
+        input::{InputSink, InputValue},
+        response::DEFAULT_PRESS_THRESHOLD,
+    },
+};
+
+use super::{
+    display::{AtomicInputDisplay, RootAtomicDisplayMarker, TaggedAtomicParams},
+    renderable::Renderable,
+    serialization::{DrawModeDef, FillModeDef, RectangleDef, TransformDef},
+};
+
+// The z offsets of the child entities, so that the fill draws over the background and the
+// threshold marker draws over the fill.
+const FILL_Z_OFFSET: f32 = 0.1;
+const MARKER_Z_OFFSET: f32 = 0.2;
+
+// The direction in which a trigger display fills as its axis is pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Inspectable)]
+pub enum FillDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+    // Grow outwards from the center of the model.
+    Radial,
+}
+
+impl Default for FillDirection {
+    fn default() -> Self {
+        FillDirection::Up
+    }
+}
+
+// The data parameterizing an analog trigger display.
+#[derive(Debug, Clone, Serialize, Deserialize, Component, Inspectable)]
+pub struct TriggerParams {
+    #[inspectable(label = "Trigger")]
+    pub trigger_key: BoundControllerKey,
+    #[inspectable(label = "Transform")]
+    pub transform: TransformDef,
+    #[inspectable(label = "Model")]
+    pub displayable: Renderable,
+    #[inspectable(label = "Fill direction")]
+    pub direction: FillDirection,
+    #[inspectable(label = "BG texture")]
+    pub bg_mode: DrawModeDef,
+    // The texture of the fill once the trigger is pressed past its click threshold.
+    #[inspectable(label = "On texture")]
+    pub on_mode: DrawModeDef,
+    #[inspectable(label = "Off texture")]
+    pub off_mode: DrawModeDef,
+    #[inspectable(label = "Show threshold")]
+    pub show_threshold: bool,
+    #[inspectable(label = "Threshold color", alpha = true)]
+    pub threshold_color: Color,
+    #[inspectable(label = "Threshold thickness", min = 0.0, suffix = "px")]
+    pub threshold_thickness: f32,
+}
+
+impl Default for TriggerParams {
+    fn default() -> Self {
+        let bg_mode = DrawMode::Outlined {
+            fill_mode: FillMode::color(Color::NONE),
+            outline_mode: StrokeMode::new(Color::BLACK, 2.0),
+        }
+        .into();
+
+        Self {
+            trigger_key: Default::default(),
+            transform: Default::default(),
+            displayable: Renderable::Rectangle(RectangleDef {
+                extents: Vec2::new(20.0, 60.0),
+            }),
+            direction: Default::default(),
+            bg_mode,
+            on_mode: DrawModeDef::Fill(FillModeDef {
+                options: Default::default(),
+                color: Color::RED,
+            }),
+            off_mode: DrawModeDef::Fill(FillModeDef {
+                options: Default::default(),
+                color: Color::GRAY,
+            }),
+            show_threshold: true,
+            threshold_color: Color::BLACK,
+            threshold_thickness: 2.0,
+        }
+    }
+}
+
+impl TriggerParams {
+    fn root_bundle(&self) -> impl Bundle {
+        (
+            GlobalTransform::identity(),
+            Into::<Transform>::into(self.transform),
+            RootTriggerMarker,
+            RootAtomicDisplayMarker,
+            Name::new("** Trigger"),
+            InputSink::new(vec![self.trigger_key.player_key()]),
+        )
+    }
+
+    fn insert_bg_bundle(&self, mut commands: EntityCommands) {
+        self.displayable
+            .insert_bundle(&mut commands, self.bg_mode.into(), Transform::identity());
+        commands.insert(ChildTriggerBgMarker);
+    }
+
+    fn insert_fill_bundle(&self, mut commands: EntityCommands) {
+        self.displayable.insert_bundle(
+            &mut commands,
+            self.off_mode.into(),
+            self.fill_transform(0.0),
+        );
+        commands.insert(ChildTriggerFillMarker);
+    }
+
+    fn insert_threshold_bundle(&self, mut commands: EntityCommands, threshold: f32) {
+        let mode = DrawMode::Stroke(StrokeMode::new(
+            self.threshold_color,
+            self.threshold_thickness,
+        ));
+        let transform = Transform::from_xyz(0.0, 0.0, MARKER_Z_OFFSET);
+        let (min, max) = self.displayable.bounds();
+        let size = max - min;
+
+        match self.direction {
+            FillDirection::Radial => {
+                let circle = Circle {
+                    radius: threshold * size.x.min(size.y) / 2.0,
+                    center: (min + max) / 2.0,
+                };
+                commands.insert_bundle(GeometryBuilder::build_as(&circle, mode, transform));
+            }
+            direction => {
+                let line = match direction {
+                    FillDirection::Up => {
+                        let y = min.y + threshold * size.y;
+                        Line(Vec2::new(min.x, y), Vec2::new(max.x, y))
+                    }
+                    FillDirection::Down => {
+                        let y = max.y - threshold * size.y;
+                        Line(Vec2::new(min.x, y), Vec2::new(max.x, y))
+                    }
+                    FillDirection::Left => {
+                        let x = max.x - threshold * size.x;
+                        Line(Vec2::new(x, min.y), Vec2::new(x, max.y))
+                    }
+                    _ => {
+                        let x = min.x + threshold * size.x;
+                        Line(Vec2::new(x, min.y), Vec2::new(x, max.y))
+                    }
+                };
+                commands.insert_bundle(GeometryBuilder::build_as(&line, mode, transform));
+            }
+        }
+
+        commands.insert(ChildTriggerThresholdMarker { threshold });
+    }
+
+    // The transform of the fill when the trigger is pressed to `value`, which scales the
+    // model along the fill direction while keeping its starting edge (or center) in place.
+    fn fill_transform(&self, value: f32) -> Transform {
+        let value = value.clamp(0.0, 1.0);
+        let (min, max) = self.displayable.bounds();
+
+        let (scale, anchor) = match self.direction {
+            FillDirection::Up => (Vec2::new(1.0, value), Vec2::new(0.0, min.y)),
+            FillDirection::Down => (Vec2::new(1.0, value), Vec2::new(0.0, max.y)),
+            FillDirection::Left => (Vec2::new(value, 1.0), Vec2::new(max.x, 0.0)),
+            FillDirection::Right => (Vec2::new(value, 1.0), Vec2::new(min.x, 0.0)),
+            FillDirection::Radial => (Vec2::splat(value), (min + max) / 2.0),
+        };
+        let translation = anchor * (Vec2::ONE - scale);
+
+        Transform {
+            translation: translation.extend(FILL_Z_OFFSET),
+            scale: scale.extend(1.0),
+            ..Transform::identity()
+        }
+    }
+}
+
+// An entity with this marker will have an `InputSink` with a source vector of 1 entry.
+#[derive(Component)]
+pub struct RootTriggerMarker;
+
+#[derive(Component)]
+pub struct ChildTriggerBgMarker;
+
+#[derive(Component)]
+pub struct ChildTriggerFillMarker;
+
+#[derive(Component)]
+pub struct ChildTriggerThresholdMarker {
+    // The click threshold that the marker is currently drawn at.
+    pub threshold: f32,
+}
+
+pub struct TriggerAtomicDisplay;
+
+impl TriggerAtomicDisplay {
+    fn trigger_display_system(
+        mut commands: Commands,
+        q_parent: Query<(&InputSink, &Children, &TaggedAtomicParams), With<RootTriggerMarker>>,
+        mut q_child_fill: Query<(&mut Transform, &mut DrawMode), With<ChildTriggerFillMarker>>,
+        mut q_child_threshold: Query<(&ChildTriggerThresholdMarker, &mut Visibility)>,
+    ) {
+        for (sink, children, tagged_params) in q_parent.iter() {
+            if let TaggedAtomicParams::Trigger(params) = tagged_params {
+                // The click threshold is the press threshold of the trigger's binding.
+                let threshold = sink.bindings[0]
+                    .as_ref()
+                    .map_or(DEFAULT_PRESS_THRESHOLD, |b| b.response.press_threshold);
+                let is_axis = matches!(sink.values[0], Some(InputValue::Axis(_)));
+
+                for &child in children.iter() {
+                    // Fill the model according to the axis input
+                    if let Ok((mut transform, mut draw_mode)) = q_child_fill.get_mut(child) {
+                        *transform = params.fill_transform(sink.axis_value(0));
+                        *draw_mode = if sink.is_pressed(0) {
+                            params.on_mode.into()
+                        } else {
+                            params.off_mode.into()
+                        };
+                    }
+
+                    // Move the threshold marker if the binding's threshold changed.
+                    // Buttons have no threshold, so the marker is hidden for them.
+                    if let Ok((marker, mut vis)) = q_child_threshold.get_mut(child) {
+                        vis.is_visible = params.show_threshold && is_axis;
+                        if marker.threshold != threshold {
+                            params.insert_threshold_bundle(commands.entity(child), threshold);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn regenerate_system(
+        mut commands: Commands,
+        parent_query: Query<
+            (Entity, &TaggedAtomicParams, &Children),
+            (With<RootTriggerMarker>, Changed<TaggedAtomicParams>),
+        >,
+        child_bg_query: Query<Entity, With<ChildTriggerBgMarker>>,
+        child_fill_query: Query<Entity, With<ChildTriggerFillMarker>>,
+        child_threshold_query: Query<&ChildTriggerThresholdMarker>,
+    ) {
+        for (root_entity, tagged_params, children) in parent_query.iter() {
+            if let TaggedAtomicParams::Trigger(params) = tagged_params {
+                // Regenerate the root entity
+                commands
+                    .entity(root_entity)
+                    .insert_bundle(params.root_bundle());
+
+                // Rengenerate the child entities
+                for &child_entity in children.iter() {
+                    if let Ok(bg_entity) = child_bg_query.get(child_entity) {
+                        params.insert_bg_bundle(commands.entity(bg_entity));
+                    } else if let Ok(fill_entity) = child_fill_query.get(child_entity) {
+                        params.insert_fill_bundle(commands.entity(fill_entity));
+                    } else if let Ok(marker) = child_threshold_query.get(child_entity) {
+                        params.insert_threshold_bundle(
+                            commands.entity(child_entity),
+                            marker.threshold,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl AtomicInputDisplay<TriggerParams> for TriggerAtomicDisplay {
+    fn spawn(commands: &mut Commands, params: &TriggerParams) -> Entity {
+        let mut my_params = params.clone();
+        let mut root = commands.spawn_bundle(my_params.root_bundle());
+
+        my_params.trigger_key.bind(root.id(), 0);
+
+        root.insert(TaggedAtomicParams::Trigger(my_params))
+            .with_children(|parent| {
+                params.insert_bg_bundle(parent.spawn());
+                params.insert_fill_bundle(parent.spawn());
+                params.insert_threshold_bundle(parent.spawn(), DEFAULT_PRESS_THRESHOLD);
+            })
+            .id()
+    }
+
+    fn add_update_systems(app: &mut App) {
+        app.add_system(Self::trigger_display_system);
+        app.add_system(Self::regenerate_system);
+        app.register_inspectable::<TriggerParams>();
+    }
+}
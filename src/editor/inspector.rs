@@ -3,27 +3,37 @@ use bevy_inspector_egui::{egui, Inspectable};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    controller::layout::ControllerKey,
+    controller::layout::{ControllerKey, PlayerId, PlayerKey},
     input::listener::{InputListener, ListenerResult},
 };
 
 // Data to identify a specific `InputSource` belonging to an `InputSink` component.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
 pub struct InputSinkId {
     // The entity containing the `InputSink` component.
     pub entity: Option<Entity>,
-    // The index of the identified `InputSource` in the sink's `sources` vector.
+    // The index of the identified `InputSource` in the sink's `bindings` vector.
     pub idx: usize,
 }
 
+// The maximum number of players selectable in the inspector.
+const MAX_PLAYERS: usize = 8;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub struct BoundControllerKey {
     #[serde(skip)]
     pub id: Option<InputSinkId>,
+    // The player whose controller this key is read from.
+    #[serde(default)]
+    pub player: PlayerId,
     pub key: Option<ControllerKey>,
 }
 
 impl BoundControllerKey {
+    pub fn player_key(&self) -> Option<PlayerKey> {
+        self.key.map(|key| PlayerKey::new(self.player, key))
+    }
+
     pub fn bind(&mut self, entity: Entity, idx: usize) {
         self.id = Some(InputSinkId {
             entity: Some(entity),
@@ -36,6 +46,7 @@ impl From<ControllerKey> for BoundControllerKey {
     fn from(key: ControllerKey) -> Self {
         BoundControllerKey {
             id: None,
+            player: 0,
             key: Some(key),
         }
     }
@@ -60,6 +71,21 @@ impl Inspectable for BoundControllerKey {
             };
             ui.label(label_text);
 
+            // Render a selector for the player this key is read from.
+            egui::ComboBox::from_id_source((self.id, "player"))
+                .selected_text(format!("P{}", self.player + 1))
+                .width(40.0)
+                .show_ui(ui, |ui| {
+                    for player in 0..MAX_PLAYERS {
+                        if ui
+                            .selectable_value(&mut self.player, player, format!("P{}", player + 1))
+                            .changed()
+                        {
+                            changed = true;
+                        }
+                    }
+                });
+
             // Render a button to rebind this key.
             context.resource_scope(
                 ui,
@@ -70,7 +96,8 @@ impl Inspectable for BoundControllerKey {
                             ui.button("listening...");
                             if let Some(ListenerResult::KeyToSink(key, _)) = input_listener.result {
                                 println!("found change {:?}", key);
-                                self.key = Some(key);
+                                self.player = key.player;
+                                self.key = Some(key.key);
                                 input_listener.consume_result();
                                 input_listener.stop_listening();
                                 changed = true;
@@ -2,9 +2,18 @@ use bevy::prelude::*;
 use bevy_egui::{egui, EguiContext};
 use std::{fs, path::Path};
 
-use crate::display::{
-    display::AtomicParamsTag,
-    system::{RequestLoadDisplay, RequestSaveDisplay, RequestSpawnAtom},
+use crate::{
+    display::{
+        display::AtomicParamsTag,
+        system::{RequestLoadDisplay, RequestSaveDisplay, RequestSpawnAtom},
+    },
+    input::{
+        recording::{
+            read_saved_sessions_dir, InputRecorder, RequestExportSession, RequestToggleRecording,
+            SessionExportFormat,
+        },
+        stats::StatsUiState,
+    },
 };
 
 const TOP_PANEL_ID: &'static str = "TOP_PANEL";
@@ -49,6 +58,10 @@ pub fn display_top_bar_system(
     mut ew_save: EventWriter<RequestSaveDisplay>,
     mut ew_load: EventWriter<RequestLoadDisplay>,
     mut ew_spawn: EventWriter<RequestSpawnAtom>,
+    recorder: Res<InputRecorder>,
+    mut ew_record: EventWriter<RequestToggleRecording>,
+    mut ew_export: EventWriter<RequestExportSession>,
+    mut stats_ui_state: ResMut<StatsUiState>,
 ) {
     egui::TopBottomPanel::top(TOP_PANEL_ID).show(egui_ctx.ctx_mut(), |ui| {
         ui.horizontal_top(|ui| {
@@ -95,6 +108,51 @@ pub fn display_top_bar_system(
                             ui.selectable_value(&mut state.created_atom, atom, atom.to_string());
                         }
                     });
+
+                ui.separator();
+
+                // button to start/stop recording the input session
+                let record_text = if recorder.is_recording() {
+                    "Stop recording"
+                } else {
+                    "Record"
+                };
+                if ui.button(record_text).clicked() {
+                    ew_record.send(RequestToggleRecording);
+                }
+
+                ui.menu_button("Recordings", |ui| {
+                    let saved_sessions = read_saved_sessions_dir();
+                    for path in saved_sessions.paths.iter() {
+                        ui.horizontal(|ui| {
+                            ui.label(path);
+                            if ui.button("JSON").clicked() {
+                                ew_export.send(RequestExportSession(
+                                    path.clone(),
+                                    SessionExportFormat::Json,
+                                ));
+                                ui.close_menu();
+                            }
+                            if ui.button("CSV").clicked() {
+                                ew_export.send(RequestExportSession(
+                                    path.clone(),
+                                    SessionExportFormat::Csv,
+                                ));
+                                ui.close_menu();
+                            }
+                        });
+                    }
+
+                    if saved_sessions.paths.is_empty() {
+                        ui.label("No recordings.");
+                    }
+                });
+
+                ui.separator();
+
+                if ui.button("Stats").clicked() {
+                    stats_ui_state.open = !stats_ui_state.open;
+                }
             });
         });
     });
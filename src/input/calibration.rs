@@ -0,0 +1,147 @@
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::controller::layout::PlayerKey;
+
+// Axis ranges smaller than this are treated as empty when normalizing.
+pub const MIN_CALIBRATED_RANGE: f32 = 0.01;
+
+// How close to one end of its range an axis has to rest to be treated as a full-range axis,
+// like a trigger.
+pub const FULL_RANGE_MIN_REST: f32 = 0.5;
+
+// The observed range of an axis, used to rescale its raw values to [-1, 1].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AxisCalibration {
+    pub min: f32,
+    pub max: f32,
+    // The value of the axis at rest.
+    pub center: f32,
+}
+
+impl AxisCalibration {
+    // Map `center` to 0.0, `max` to 1.0 and `min` to -1.0, interpolating linearly between them.
+    // Axes that rest at one end of their range (like triggers) only produce one sign of values.
+    pub fn normalize(&self, value: f32) -> f32 {
+        let (offset, range) = if value >= self.center {
+            (value - self.center, self.max - self.center)
+        } else {
+            (value - self.center, self.center - self.min)
+        };
+
+        if range < MIN_CALIBRATED_RANGE {
+            0.0
+        } else {
+            (offset / range).clamp(-1.0, 1.0)
+        }
+    }
+
+    // Returns `true` if enough of the axis was observed for `normalize` to read it: both sides
+    // of a stick, or the side of a trigger away from the end it rests at.
+    pub fn covers_axis(&self) -> bool {
+        let below = self.center - self.min >= MIN_CALIBRATED_RANGE;
+        let above = self.max - self.center >= MIN_CALIBRATED_RANGE;
+
+        if self.center >= FULL_RANGE_MIN_REST {
+            below
+        } else if self.center <= -FULL_RANGE_MIN_REST {
+            above
+        } else {
+            below && above
+        }
+    }
+}
+
+// How much of its range an axis moves through when it's bound, as heard by the input listener.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AxisRange {
+    // The axis rests at the center of its range, and each sign covers half of it (like sticks).
+    Half,
+    // The axis rests at `rest`, one end of its range, and moves through all of it (like triggers).
+    Full { rest: f32 },
+}
+
+impl AxisRange {
+    // The calibration mapping the whole movement of the axis to one sign, if it's full-range.
+    pub fn calibration(&self) -> Option<AxisCalibration> {
+        match *self {
+            AxisRange::Half => None,
+            AxisRange::Full { rest } => Some(AxisCalibration {
+                min: rest.min(-1.0),
+                max: rest.max(1.0),
+                center: rest,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationStep {
+    // Waiting for every stick and trigger to be left at rest.
+    Center,
+    // Recording the extremes reached while sticks are rotated and triggers pressed.
+    Range,
+    // The calibrations were saved, but some axes weren't moved far enough to be calibrated.
+    Skipped,
+}
+
+// The state of an in-progress axis calibration, covering every axis-bound key of a player.
+#[derive(Debug)]
+pub struct CalibrationWizard {
+    pub step: CalibrationStep,
+    // The calibration observed so far for each key.
+    pub observed: HashMap<PlayerKey, AxisCalibration>,
+    // The keys which weren't calibrated when the wizard finished.
+    pub skipped: Vec<PlayerKey>,
+}
+
+impl CalibrationWizard {
+    pub fn new() -> CalibrationWizard {
+        CalibrationWizard {
+            step: CalibrationStep::Center,
+            observed: HashMap::default(),
+            skipped: vec![],
+        }
+    }
+
+    // Record the raw value of `key`'s axis during the current step.
+    pub fn observe(&mut self, key: PlayerKey, value: f32) {
+        match self.step {
+            CalibrationStep::Center => {
+                self.observed.insert(
+                    key,
+                    AxisCalibration {
+                        min: value,
+                        max: value,
+                        center: value,
+                    },
+                );
+            }
+            CalibrationStep::Range => {
+                if let Some(calibration) = self.observed.get_mut(&key) {
+                    calibration.min = calibration.min.min(value);
+                    calibration.max = calibration.max.max(value);
+                }
+            }
+            CalibrationStep::Skipped => {}
+        }
+    }
+
+    // Returns the observed calibrations which cover their axis, and remember the keys whose
+    // axis wasn't moved far enough, since saving their calibration would pin them at 0.
+    pub fn finish(&mut self) -> Vec<(PlayerKey, AxisCalibration)> {
+        let mut calibrations = vec![];
+        self.skipped.clear();
+
+        for (&key, &calibration) in self.observed.iter() {
+            if calibration.covers_axis() {
+                calibrations.push((key, calibration));
+            } else {
+                self.skipped.push(key);
+            }
+        }
+
+        self.skipped.sort_by_key(|key| key.to_string());
+        calibrations
+    }
+}
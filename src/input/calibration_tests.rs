@@ -0,0 +1,122 @@
+// Tests of normalizing axes by their calibration, and of the calibration wizard's steps.
+
+use crate::controller::layout::{ControllerKey, PlayerKey, Ps2Key};
+
+use super::calibration::{AxisCalibration, AxisRange, CalibrationStep, CalibrationWizard};
+
+fn ps2_key(key: Ps2Key) -> PlayerKey {
+    PlayerKey::new(0, ControllerKey::Ps2(key))
+}
+
+fn calibration(min: f32, center: f32, max: f32) -> AxisCalibration {
+    AxisCalibration { min, max, center }
+}
+
+#[test]
+fn sticks_normalize_each_side_of_their_center() {
+    let stick = calibration(-0.75, 0.25, 0.75);
+
+    assert_eq!(stick.normalize(0.25), 0.0);
+    assert_eq!(stick.normalize(0.75), 1.0);
+    assert_eq!(stick.normalize(0.5), 0.5);
+    assert_eq!(stick.normalize(-0.75), -1.0);
+    assert_eq!(stick.normalize(-0.25), -0.5);
+
+    // Values past the observed range are clamped to it.
+    assert_eq!(stick.normalize(1.0), 1.0);
+    assert_eq!(stick.normalize(-1.0), -1.0);
+}
+
+#[test]
+fn triggers_normalize_from_their_rest() {
+    let trigger = AxisRange::Full { rest: -1.0 }.calibration().unwrap();
+
+    assert_eq!(trigger.normalize(-1.0), 0.0);
+    assert_eq!(trigger.normalize(0.0), 0.5);
+    assert_eq!(trigger.normalize(1.0), 1.0);
+    assert_eq!(AxisRange::Half.calibration(), None);
+}
+
+#[test]
+fn empty_ranges_normalize_to_zero() {
+    let unmoved = calibration(0.2, 0.2, 0.2);
+    assert_eq!(unmoved.normalize(0.2), 0.0);
+    assert_eq!(unmoved.normalize(1.0), 0.0);
+
+    // Only the side that wasn't moved reads as 0.
+    let half_moved = calibration(0.0, 0.0, 1.0);
+    assert_eq!(half_moved.normalize(0.5), 0.5);
+    assert_eq!(half_moved.normalize(-0.5), 0.0);
+}
+
+#[test]
+fn calibrations_cover_both_sides_of_sticks_and_one_side_of_triggers() {
+    assert!(calibration(-0.9, 0.0, 0.9).covers_axis());
+    assert!(!calibration(0.0, 0.0, 0.0).covers_axis());
+    assert!(!calibration(0.0, 0.0, 0.9).covers_axis());
+    assert!(!calibration(-0.9, 0.0, 0.0).covers_axis());
+
+    assert!(calibration(-1.0, -1.0, 1.0).covers_axis());
+    assert!(calibration(-1.0, 1.0, 1.0).covers_axis());
+    assert!(!calibration(-1.0, -1.0, -1.0).covers_axis());
+}
+
+#[test]
+fn wizard_records_the_rest_then_the_range_of_each_axis() {
+    let stick = ps2_key(Ps2Key::LeftPosX);
+    let trigger = ps2_key(Ps2Key::L2);
+
+    let mut wizard = CalibrationWizard::new();
+    assert_eq!(wizard.step, CalibrationStep::Center);
+
+    // The rest is the last value seen during the `Center` step.
+    wizard.observe(stick, 0.3);
+    wizard.observe(stick, 0.05);
+    wizard.observe(trigger, -1.0);
+    assert_eq!(wizard.observed[&stick], calibration(0.05, 0.05, 0.05));
+
+    wizard.step = CalibrationStep::Range;
+    for value in [0.5, 0.95, -0.9, 0.0] {
+        wizard.observe(stick, value);
+    }
+    wizard.observe(trigger, 0.8);
+
+    // Keys that weren't seen at rest aren't calibrated.
+    wizard.observe(ps2_key(Ps2Key::R2), 1.0);
+
+    let mut calibrations = wizard.finish();
+    calibrations.sort_by_key(|(key, _)| key.to_string());
+    let mut expected = vec![
+        (stick, calibration(-0.9, 0.05, 0.95)),
+        (trigger, calibration(-1.0, -1.0, 0.8)),
+    ];
+    expected.sort_by_key(|(key, _)| key.to_string());
+
+    assert_eq!(calibrations, expected);
+    assert!(wizard.skipped.is_empty());
+}
+
+#[test]
+fn wizard_skips_axes_that_werent_moved() {
+    let stick_x = ps2_key(Ps2Key::LeftPosX);
+    let stick_y = ps2_key(Ps2Key::LeftPosY);
+    let trigger = ps2_key(Ps2Key::R2);
+
+    let mut wizard = CalibrationWizard::new();
+    wizard.observe(stick_x, 0.0);
+    wizard.observe(stick_y, 0.0);
+    wizard.observe(trigger, -1.0);
+
+    // The stick is only pushed one way along X, and the trigger isn't pressed.
+    wizard.step = CalibrationStep::Range;
+    wizard.observe(stick_x, 0.9);
+    wizard.observe(stick_y, 0.9);
+    wizard.observe(stick_y, -0.9);
+
+    let calibrations = wizard.finish();
+    assert_eq!(calibrations, vec![(stick_y, calibration(-0.9, 0.0, 0.9))]);
+
+    let mut expected = vec![stick_x, trigger];
+    expected.sort_by_key(|key| key.to_string());
+    assert_eq!(wizard.skipped, expected);
+}
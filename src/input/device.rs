@@ -0,0 +1,230 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::controller::{
+    layout::{Binding, ControllerLayoutsRes, SourceChord},
+    system::write_layouts_to_file,
+};
+
+use super::{
+    input::{HidId, InputSink, InputSource},
+    raw_input_reader::RawInputRes,
+    RawInputReader,
+};
+
+// The `HidId` given to sources of saved devices which aren't connected.
+// No joystick has this id, so such sources are never active.
+pub const MISSING_HID_ID: HidId = HidId::MAX;
+
+// How often to check for joysticks being connected or disconnected, in seconds.
+const DEVICE_REFRESH_INTERVAL: f64 = 2.0;
+
+// The persistent identity of a HID device, which survives reconnects and reordering,
+// unlike the runtime `HidId` assigned by the raw input backend.
+// Fields the backend can't provide are left as `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceIdentity {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vendor: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub product: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serial: Option<String>,
+    // The SDL-style GUID of the device.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guid: Option<String>,
+}
+
+impl DeviceIdentity {
+    pub fn named(name: &str) -> DeviceIdentity {
+        DeviceIdentity {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    // Returns `true` if `other` describes the same device as `self`.
+    // Serial numbers are the most specific, but many devices don't report one, in which
+    // case identical devices can only be told apart by the order they're connected in.
+    pub fn matches(&self, other: &DeviceIdentity) -> bool {
+        match (&self.serial, &other.serial) {
+            (Some(a), Some(b)) if !a.is_empty() && !b.is_empty() => {
+                return a == b && self.vendor == other.vendor && self.product == other.product;
+            }
+            _ => {}
+        }
+
+        if let (Some(a), Some(b)) = (&self.guid, &other.guid) {
+            return a == b;
+        }
+
+        self.name == other.name && self.vendor == other.vendor && self.product == other.product
+    }
+}
+
+// Build the SDL2 joystick GUID of a device from its bus type, vendor, product and version,
+// as used by `gamecontrollerdb.txt`.
+pub fn sdl_guid(bus: u16, vendor: u16, product: u16, version: u16) -> String {
+    let words = [bus, 0, vendor, 0, product, 0, version, 0];
+    words
+        .iter()
+        .map(|w| format!("{:02x}{:02x}", w & 0xff, w >> 8))
+        .collect()
+}
+
+// The global resource mapping the devices saved in `ControllerLayoutsRes::devices` to the
+// `HidId`s of the currently connected joysticks.
+// Bindings store the index of their device in `ControllerLayoutsRes::devices` as their `HidId`,
+// which is swapped for the runtime `HidId` when the binding is copied into an `InputSink`.
+#[derive(Debug, Default)]
+pub struct DeviceMapRes {
+    // The saved devices that the map was last built from.
+    saved: Vec<DeviceIdentity>,
+    // The runtime id of each saved device, or `None` if it isn't connected.
+    runtime_ids: Vec<Option<HidId>>,
+    // The identities of the connected joysticks, indexed by runtime id.
+    pub connected: Vec<DeviceIdentity>,
+}
+
+impl DeviceMapRes {
+    // Match each saved device to a connected joystick, in order, so that two identical
+    // devices without serial numbers are each matched once.
+    pub fn rebuild(&mut self, saved: &[DeviceIdentity], connected: Vec<DeviceIdentity>) {
+        let mut used = vec![false; connected.len()];
+
+        self.runtime_ids = saved
+            .iter()
+            .map(|identity| {
+                let runtime_id = (0..connected.len())
+                    .find(|&id| !used[id] && identity.matches(&connected[id]))?;
+                used[runtime_id] = true;
+                Some(runtime_id)
+            })
+            .collect();
+        self.saved = saved.to_vec();
+        self.connected = connected;
+    }
+
+    pub fn is_connected(&self, saved_id: HidId) -> bool {
+        matches!(self.runtime_ids.get(saved_id), Some(Some(_)))
+    }
+
+    // Returns the saved id of the connected joystick with `runtime_id`, if it was saved.
+    pub fn saved_id(&self, runtime_id: HidId) -> Option<HidId> {
+        self.runtime_ids
+            .iter()
+            .position(|id| *id == Some(runtime_id))
+    }
+
+    fn runtime_id(&self, saved_id: HidId) -> HidId {
+        self.runtime_ids
+            .get(saved_id)
+            .copied()
+            .flatten()
+            .unwrap_or(MISSING_HID_ID)
+    }
+
+    // Swap the saved device id of a source for the runtime id of its joystick.
+    pub fn resolve_source(&self, source: &InputSource) -> InputSource {
+        match source.get_hid_id() {
+            Some(id) => source.with_hid_id(self.runtime_id(id)),
+            None => *source,
+        }
+    }
+
+    pub fn resolve_binding(&self, binding: &Binding) -> Binding {
+        let mut binding = binding.clone();
+        for chord in binding.chords.iter_mut() {
+            for source in chord.sources.iter_mut() {
+                *source = self.resolve_source(source);
+            }
+        }
+        binding
+    }
+
+    // Swap the runtime id of a source heard by the listener for the saved id of its joystick,
+    // saving the joystick to `layouts` if it wasn't already.
+    pub fn save_source(
+        &self,
+        layouts: &mut ControllerLayoutsRes,
+        source: InputSource,
+    ) -> InputSource {
+        let runtime_id = match source.get_hid_id() {
+            Some(id) => id,
+            None => return source,
+        };
+
+        match self.saved_id(runtime_id) {
+            Some(saved_id) => source.with_hid_id(saved_id),
+            None => {
+                let identity =
+                    self.connected.get(runtime_id).cloned().unwrap_or_else(|| {
+                        DeviceIdentity::named(&format!("Joystick {}", runtime_id))
+                    });
+                source.with_hid_id(layouts.add_device(identity))
+            }
+        }
+    }
+
+    pub fn save_chord(
+        &self,
+        layouts: &mut ControllerLayoutsRes,
+        chord: &SourceChord,
+    ) -> SourceChord {
+        SourceChord {
+            sources: chord
+                .sources
+                .iter()
+                .map(|&source| self.save_source(layouts, source))
+                .collect(),
+        }
+    }
+
+    // The saved id of a source heard by the listener, without saving new joysticks.
+    pub fn saved_source(&self, source: &InputSource) -> Option<InputSource> {
+        match source.get_hid_id() {
+            Some(id) => Some(source.with_hid_id(self.saved_id(id)?)),
+            None => Some(*source),
+        }
+    }
+}
+
+// Keep the `DeviceMapRes` in sync with the connected joysticks and the saved devices, and
+// rebind every `InputSink` when either changes.
+// Other edits to the layouts dirty the sinks they affect themselves.
+pub fn update_device_map_system(
+    time: Res<Time>,
+    mut raw_input: NonSendMut<RawInputRes>,
+    mut layouts: ResMut<ControllerLayoutsRes>,
+    mut device_map: ResMut<DeviceMapRes>,
+    mut last_refresh: Local<Option<f64>>,
+    mut sinks: Query<&mut InputSink>,
+) {
+    let now = time.seconds_since_startup();
+    let first_run = last_refresh.is_none();
+
+    let mut devices_changed = first_run;
+    if last_refresh.map_or(true, |last| now - last >= DEVICE_REFRESH_INTERVAL) {
+        *last_refresh = Some(now);
+        devices_changed |= raw_input.0.refresh_devices();
+    }
+
+    if !devices_changed && device_map.saved == layouts.devices {
+        return;
+    }
+
+    let connected = raw_input.0.device_identities();
+
+    // Layouts saved before devices were tracked store runtime ids. Save the identities
+    // they're migrated to, so that they stay bound to the same devices after a restart.
+    if layouts.migrate_legacy_devices(&connected) {
+        write_layouts_to_file(&layouts);
+    }
+
+    device_map.rebuild(&layouts.devices, connected);
+    for mut sink in sinks.iter_mut() {
+        sink.sources_dirty = true;
+    }
+}
@@ -0,0 +1,203 @@
+use std::time::SystemTime;
+
+use bevy::prelude::*;
+
+use crate::controller::layout::Binding;
+
+use super::{
+    global_keyboard::GlobalKeyboardRes,
+    input::{all_input_values, any_input_values, InputSource, InputValue},
+    raw_input::RawInputRes,
+    settings::{input_time, InputSettings, ManualInputClock},
+};
+
+// A button press (`pressed == true`) or release of an input source.
+// For the presses of a bound key, `source` is the source whose event pressed or released it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimedPress {
+    pub source: InputSource,
+    pub pressed: bool,
+    // The input time the event happened at. Events read from the window happen on the frame
+    // they're read, while events read from devices keep the time the device reported.
+    pub time: f64,
+    // The index of the event in the `InputEventLog` it was read from, which orders the events
+    // of different sources read at the same time.
+    pub order: usize,
+}
+
+// The global resource of button presses and releases read since the last input poll.
+// Events are collected every frame, so presses and releases which both happen between two
+// polls aren't lost even though the button's state is the same at both polls.
+#[derive(Debug, Default)]
+pub struct InputEventLog {
+    events: Vec<TimedPress>,
+}
+
+impl InputEventLog {
+    pub fn push(&mut self, source: InputSource, pressed: bool, time: f64) {
+        self.events.push(TimedPress {
+            source,
+            pressed,
+            time,
+            order: self.events.len(),
+        });
+    }
+
+    // Returns `true` if `source` was pressed at some point since the last poll.
+    pub fn was_pressed(&self, source: &InputSource) -> bool {
+        self.events
+            .iter()
+            .any(|event| event.pressed && event.source == *source)
+    }
+
+    // The presses and releases of the key bound to `binding`, in the order they happened.
+    // The binding is evaluated after each event of its sources, so that e.g. one source of a
+    // chord can't press the key on its own. `source_values` are the polled values of the
+    // binding's sources, chord by chord, which sources without events keep throughout.
+    pub fn binding_presses(
+        &self,
+        binding: &Binding,
+        source_values: &[Vec<Option<InputValue>>],
+    ) -> Vec<TimedPress> {
+        let events: Vec<&TimedPress> = self
+            .events
+            .iter()
+            .filter(|event| binding.has_source(&event.source))
+            .collect();
+        if events.is_empty() {
+            return vec![];
+        }
+
+        let response = &binding.response;
+        let mut values = source_values.to_vec();
+        let set_source =
+            |values: &mut [Vec<Option<InputValue>>], source: &InputSource, pressed: bool| {
+                for (chord, chord_values) in binding.chords.iter().zip(values.iter_mut()) {
+                    for (s, value) in chord.sources.iter().zip(chord_values.iter_mut()) {
+                        if s == source {
+                            *value = Some(InputValue::Button(response.apply_button(pressed)));
+                        }
+                    }
+                }
+            };
+        let is_pressed = |values: &[Vec<Option<InputValue>>], was_pressed: bool| {
+            let chord_values: Vec<Option<InputValue>> =
+                values.iter().map(|v| all_input_values(v)).collect();
+            match any_input_values(&chord_values) {
+                Some(InputValue::Button(pressed)) => pressed,
+                Some(InputValue::Axis(v)) => response.apply_threshold(v.abs(), was_pressed),
+                None => false,
+            }
+        };
+
+        // Each source with events was in the opposite state before its first one.
+        for (i, event) in events.iter().enumerate() {
+            if !events[..i].iter().any(|e| e.source == event.source) {
+                set_source(&mut values, &event.source, !event.pressed);
+            }
+        }
+
+        let mut pressed = is_pressed(&values, false);
+        let mut presses = vec![];
+        for event in events {
+            set_source(&mut values, &event.source, event.pressed);
+            let now_pressed = is_pressed(&values, pressed);
+            if now_pressed != pressed {
+                presses.push(TimedPress {
+                    pressed: now_pressed,
+                    ..*event
+                });
+                pressed = now_pressed;
+            }
+        }
+
+        presses
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+}
+
+// Push the presses and releases of `Input<T>` this frame to the event log.
+fn log_input_events<T>(
+    log: &mut InputEventLog,
+    input: &Input<T>,
+    to_source: impl Fn(T) -> InputSource,
+    now: f64,
+) where
+    T: Copy + Eq + std::hash::Hash,
+{
+    for &button in input.get_just_pressed() {
+        // A button that was both pressed and released within the same frame is in both sets,
+        // and whether it's still pressed tells which came last.
+        if !input.just_released(button) {
+            log.push(to_source(button), true, now);
+        } else if input.pressed(button) {
+            log.push(to_source(button), false, now);
+            log.push(to_source(button), true, now);
+        } else {
+            log.push(to_source(button), true, now);
+            log.push(to_source(button), false, now);
+        }
+    }
+
+    for &button in input.get_just_released() {
+        if !input.just_pressed(button) {
+            log.push(to_source(button), false, now);
+        }
+    }
+}
+
+// Convert the wall clock `time` of an event to input time, given that the input time is `now`
+// and the wall clock reads `wall_now`. Events the wall clock puts in the future happen `now`.
+pub fn event_input_time(now: f64, wall_now: SystemTime, time: SystemTime) -> f64 {
+    now - wall_now
+        .duration_since(time)
+        .map_or(0.0, |age| age.as_secs_f64())
+}
+
+// Collect the button events read this frame into the `InputEventLog`.
+pub fn collect_input_events_system(
+    time: Res<Time>,
+    clock: Option<Res<ManualInputClock>>,
+    settings: Res<InputSettings>,
+    mut log: ResMut<InputEventLog>,
+    keyboard: Res<Input<KeyCode>>,
+    global_keyboard: NonSend<GlobalKeyboardRes>,
+    mut raw_input: NonSendMut<RawInputRes>,
+    buttons: Res<Input<GamepadButton>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+) {
+    // Always drain the joystick events so that they don't pile up while they aren't logged.
+    let joystick_events = raw_input.0.take_button_events();
+
+    if !settings.timestamped_presses {
+        log.clear();
+        return;
+    }
+
+    let now = input_time(&time, clock.as_deref());
+    let wall_now = SystemTime::now();
+
+    // While the keyboard is captured, its events are read from the capture instead, since
+    // the window's key events would log every key press twice.
+    if !global_keyboard.is_capturing() {
+        log_input_events(&mut log, &keyboard, InputSource::Key, now);
+    }
+    log_input_events(&mut log, &buttons, InputSource::Button, now);
+    log_input_events(&mut log, &mouse_buttons, InputSource::MouseButton, now);
+
+    for &(key, pressed, time) in global_keyboard.get_events() {
+        log.push(
+            InputSource::Key(key),
+            pressed,
+            event_input_time(now, wall_now, time),
+        );
+    }
+
+    for (source, pressed, time) in joystick_events {
+        let time = time.map_or(now, |time| event_input_time(now, wall_now, time));
+        log.push(source, pressed, time);
+    }
+}
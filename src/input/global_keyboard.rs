@@ -0,0 +1,348 @@
+use std::{collections::HashSet, time::SystemTime};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::util::{read_from_file, write_to_file};
+
+pub const GLOBAL_KEYBOARD_FILE_PATH: &'static str = "keyboard.json";
+
+// Which keyboard devices to read when capturing the keyboard in the background.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GlobalKeyboardSettings {
+    // Whether background keyboard capture is enabled at all.
+    pub enabled: bool,
+    // The names of the keyboard devices to read. If empty, every keyboard is read.
+    pub devices: Vec<String>,
+}
+
+// A source of keyboard events that doesn't depend on the vert window having focus.
+pub trait KeyboardBackend {
+    // The names of all keyboard devices that can be captured.
+    fn device_names(&self) -> Vec<String>;
+
+    // Returns the key presses (`true`) and releases (`false`) read since the last call, with
+    // the time each happened, from the devices named in `devices`, or from every device if
+    // `devices` is empty.
+    fn read_key_events(&mut self, devices: &[String]) -> Vec<(KeyCode, bool, SystemTime)>;
+}
+
+pub struct NoopKeyboardBackend;
+
+impl KeyboardBackend for NoopKeyboardBackend {
+    fn device_names(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn read_key_events(&mut self, _devices: &[String]) -> Vec<(KeyCode, bool, SystemTime)> {
+        vec![]
+    }
+}
+
+// The non-send bevy resource holding the background keyboard state.
+// Key sources are considered pressed if either bevy's `Input<KeyCode>` or this resource
+// says they are, so capture keeps working while the window is unfocused.
+pub struct GlobalKeyboardRes {
+    pub settings: GlobalKeyboardSettings,
+    backend: Box<dyn KeyboardBackend>,
+    pressed: HashSet<KeyCode>,
+    just_pressed: HashSet<KeyCode>,
+    // The key events read during the last update, in order.
+    events: Vec<(KeyCode, bool, SystemTime)>,
+}
+
+impl Default for GlobalKeyboardRes {
+    fn default() -> Self {
+        let settings =
+            read_from_file::<GlobalKeyboardSettings>(GLOBAL_KEYBOARD_FILE_PATH).unwrap_or_default();
+
+        #[cfg(target_os = "linux")]
+        let backend = Box::new(linux::EvdevKeyboardBackend::default());
+
+        #[cfg(not(target_os = "linux"))]
+        let backend = Box::new(NoopKeyboardBackend);
+
+        GlobalKeyboardRes::with_backend(settings, backend)
+    }
+}
+
+impl GlobalKeyboardRes {
+    pub fn with_backend(
+        settings: GlobalKeyboardSettings,
+        backend: Box<dyn KeyboardBackend>,
+    ) -> GlobalKeyboardRes {
+        GlobalKeyboardRes {
+            settings,
+            backend,
+            pressed: HashSet::new(),
+            just_pressed: HashSet::new(),
+            events: vec![],
+        }
+    }
+
+    // Whether key events are read from the backend, in which case they're read whether or
+    // not the window has focus.
+    pub fn is_capturing(&self) -> bool {
+        self.settings.enabled
+    }
+
+    pub fn pressed(&self, key: KeyCode) -> bool {
+        self.pressed.contains(&key)
+    }
+
+    pub fn get_pressed(&self) -> impl Iterator<Item = &KeyCode> {
+        self.pressed.iter()
+    }
+
+    pub fn get_just_pressed(&self) -> impl Iterator<Item = &KeyCode> {
+        self.just_pressed.iter()
+    }
+
+    // The key presses (`true`) and releases (`false`) read during the last update, with the
+    // time each happened.
+    pub fn get_events(&self) -> &[(KeyCode, bool, SystemTime)] {
+        &self.events
+    }
+
+    pub fn device_names(&self) -> Vec<String> {
+        self.backend.device_names()
+    }
+
+    // Sync the pressed keys with the events read from the backend.
+    pub fn update(&mut self) {
+        self.just_pressed.clear();
+        self.events.clear();
+
+        // Always drain the backend so that stale events don't pile up while capture is disabled.
+        let events = self.backend.read_key_events(&self.settings.devices);
+        if !self.settings.enabled {
+            self.pressed.clear();
+            return;
+        }
+
+        for &(key, pressed, _) in events.iter() {
+            if pressed {
+                if self.pressed.insert(key) {
+                    self.just_pressed.insert(key);
+                }
+            } else {
+                self.pressed.remove(&key);
+            }
+        }
+        self.events = events;
+    }
+
+    pub fn save_settings(&self) {
+        write_to_file(&self.settings, GLOBAL_KEYBOARD_FILE_PATH);
+    }
+}
+
+pub fn update_global_keyboard_system(mut global_keyboard: NonSendMut<GlobalKeyboardRes>) {
+    global_keyboard.update();
+}
+
+#[cfg(target_os = "linux")]
+pub mod linux {
+    use std::time::SystemTime;
+
+    use bevy::prelude::KeyCode;
+    use evdev::Key;
+
+    use super::KeyboardBackend;
+    use crate::input::raw_input::linux::{DeviceEvent, EvdevDevice, EventDevice};
+
+    // Reads key events from evdev keyboard devices.
+    // Reading `/dev/input/event*` usually requires the user to be in the `input` group.
+    pub struct EvdevKeyboardBackend {
+        devices: Vec<Box<dyn EventDevice>>,
+    }
+
+    impl Default for EvdevKeyboardBackend {
+        fn default() -> Self {
+            let mut devices: Vec<Box<dyn EventDevice>> = vec![];
+
+            for (path, device) in evdev::enumerate() {
+                match EvdevDevice::from_device(device) {
+                    Ok(device) => devices.push(Box::new(device)),
+                    Err(e) => println!("Error opening evdev device {:?}: {:?}", path, e),
+                }
+            }
+
+            EvdevKeyboardBackend::from_devices(devices)
+        }
+    }
+
+    impl EvdevKeyboardBackend {
+        // Build a backend from an explicit list of devices.
+        // Devices that don't look like keyboards are ignored.
+        pub fn from_devices(devices: Vec<Box<dyn EventDevice>>) -> EvdevKeyboardBackend {
+            let devices = devices
+                .into_iter()
+                .filter(|device| is_keyboard(&**device))
+                .collect();
+
+            EvdevKeyboardBackend { devices }
+        }
+    }
+
+    impl KeyboardBackend for EvdevKeyboardBackend {
+        fn device_names(&self) -> Vec<String> {
+            self.devices.iter().map(|device| device.name()).collect()
+        }
+
+        fn read_key_events(&mut self, devices: &[String]) -> Vec<(KeyCode, bool, SystemTime)> {
+            let mut result = vec![];
+
+            for device in self.devices.iter_mut() {
+                let events = device.read_events();
+                if !devices.is_empty() && !devices.contains(&device.name()) {
+                    continue;
+                }
+
+                for (event, time) in events {
+                    match event {
+                        // Ignore key repeat events, which have a value of 2.
+                        DeviceEvent::Key(code, value @ (0 | 1)) => {
+                            if let Some(key) = keycode_from_evdev(code) {
+                                result.push((key, value == 1, time));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            result
+        }
+    }
+
+    // Returns `true` if the device has letter keys, which excludes power buttons, lid
+    // switches and the like that also report `EV_KEY` events.
+    fn is_keyboard(device: &dyn EventDevice) -> bool {
+        let codes = device.key_codes();
+        codes.contains(&Key::KEY_A.code()) && codes.contains(&Key::KEY_Z.code())
+    }
+
+    // Map an evdev key code to the corresponding bevy `KeyCode`, if there is one.
+    pub fn keycode_from_evdev(code: u16) -> Option<KeyCode> {
+        let key = match Key::new(code) {
+            Key::KEY_ESC => KeyCode::Escape,
+            Key::KEY_1 => KeyCode::Key1,
+            Key::KEY_2 => KeyCode::Key2,
+            Key::KEY_3 => KeyCode::Key3,
+            Key::KEY_4 => KeyCode::Key4,
+            Key::KEY_5 => KeyCode::Key5,
+            Key::KEY_6 => KeyCode::Key6,
+            Key::KEY_7 => KeyCode::Key7,
+            Key::KEY_8 => KeyCode::Key8,
+            Key::KEY_9 => KeyCode::Key9,
+            Key::KEY_0 => KeyCode::Key0,
+            Key::KEY_MINUS => KeyCode::Minus,
+            Key::KEY_EQUAL => KeyCode::Equals,
+            Key::KEY_BACKSPACE => KeyCode::Back,
+            Key::KEY_TAB => KeyCode::Tab,
+            Key::KEY_Q => KeyCode::Q,
+            Key::KEY_W => KeyCode::W,
+            Key::KEY_E => KeyCode::E,
+            Key::KEY_R => KeyCode::R,
+            Key::KEY_T => KeyCode::T,
+            Key::KEY_Y => KeyCode::Y,
+            Key::KEY_U => KeyCode::U,
+            Key::KEY_I => KeyCode::I,
+            Key::KEY_O => KeyCode::O,
+            Key::KEY_P => KeyCode::P,
+            Key::KEY_LEFTBRACE => KeyCode::LBracket,
+            Key::KEY_RIGHTBRACE => KeyCode::RBracket,
+            Key::KEY_ENTER => KeyCode::Return,
+            Key::KEY_LEFTCTRL => KeyCode::LControl,
+            Key::KEY_A => KeyCode::A,
+            Key::KEY_S => KeyCode::S,
+            Key::KEY_D => KeyCode::D,
+            Key::KEY_F => KeyCode::F,
+            Key::KEY_G => KeyCode::G,
+            Key::KEY_H => KeyCode::H,
+            Key::KEY_J => KeyCode::J,
+            Key::KEY_K => KeyCode::K,
+            Key::KEY_L => KeyCode::L,
+            Key::KEY_SEMICOLON => KeyCode::Semicolon,
+            Key::KEY_APOSTROPHE => KeyCode::Apostrophe,
+            Key::KEY_GRAVE => KeyCode::Grave,
+            Key::KEY_LEFTSHIFT => KeyCode::LShift,
+            Key::KEY_BACKSLASH => KeyCode::Backslash,
+            Key::KEY_Z => KeyCode::Z,
+            Key::KEY_X => KeyCode::X,
+            Key::KEY_C => KeyCode::C,
+            Key::KEY_V => KeyCode::V,
+            Key::KEY_B => KeyCode::B,
+            Key::KEY_N => KeyCode::N,
+            Key::KEY_M => KeyCode::M,
+            Key::KEY_COMMA => KeyCode::Comma,
+            Key::KEY_DOT => KeyCode::Period,
+            Key::KEY_SLASH => KeyCode::Slash,
+            Key::KEY_RIGHTSHIFT => KeyCode::RShift,
+            Key::KEY_KPASTERISK => KeyCode::NumpadMultiply,
+            Key::KEY_LEFTALT => KeyCode::LAlt,
+            Key::KEY_SPACE => KeyCode::Space,
+            Key::KEY_CAPSLOCK => KeyCode::Capital,
+            Key::KEY_F1 => KeyCode::F1,
+            Key::KEY_F2 => KeyCode::F2,
+            Key::KEY_F3 => KeyCode::F3,
+            Key::KEY_F4 => KeyCode::F4,
+            Key::KEY_F5 => KeyCode::F5,
+            Key::KEY_F6 => KeyCode::F6,
+            Key::KEY_F7 => KeyCode::F7,
+            Key::KEY_F8 => KeyCode::F8,
+            Key::KEY_F9 => KeyCode::F9,
+            Key::KEY_F10 => KeyCode::F10,
+            Key::KEY_NUMLOCK => KeyCode::Numlock,
+            Key::KEY_SCROLLLOCK => KeyCode::Scroll,
+            Key::KEY_KP7 => KeyCode::Numpad7,
+            Key::KEY_KP8 => KeyCode::Numpad8,
+            Key::KEY_KP9 => KeyCode::Numpad9,
+            Key::KEY_KPMINUS => KeyCode::NumpadSubtract,
+            Key::KEY_KP4 => KeyCode::Numpad4,
+            Key::KEY_KP5 => KeyCode::Numpad5,
+            Key::KEY_KP6 => KeyCode::Numpad6,
+            Key::KEY_KPPLUS => KeyCode::NumpadAdd,
+            Key::KEY_KP1 => KeyCode::Numpad1,
+            Key::KEY_KP2 => KeyCode::Numpad2,
+            Key::KEY_KP3 => KeyCode::Numpad3,
+            Key::KEY_KP0 => KeyCode::Numpad0,
+            Key::KEY_KPDOT => KeyCode::NumpadDecimal,
+            Key::KEY_102ND => KeyCode::Oem102,
+            Key::KEY_F11 => KeyCode::F11,
+            Key::KEY_F12 => KeyCode::F12,
+            Key::KEY_KPENTER => KeyCode::NumpadEnter,
+            Key::KEY_RIGHTCTRL => KeyCode::RControl,
+            Key::KEY_KPSLASH => KeyCode::NumpadDivide,
+            Key::KEY_SYSRQ => KeyCode::Snapshot,
+            Key::KEY_RIGHTALT => KeyCode::RAlt,
+            Key::KEY_HOME => KeyCode::Home,
+            Key::KEY_UP => KeyCode::Up,
+            Key::KEY_PAGEUP => KeyCode::PageUp,
+            Key::KEY_LEFT => KeyCode::Left,
+            Key::KEY_RIGHT => KeyCode::Right,
+            Key::KEY_END => KeyCode::End,
+            Key::KEY_DOWN => KeyCode::Down,
+            Key::KEY_PAGEDOWN => KeyCode::PageDown,
+            Key::KEY_INSERT => KeyCode::Insert,
+            Key::KEY_DELETE => KeyCode::Delete,
+            Key::KEY_MUTE => KeyCode::Mute,
+            Key::KEY_VOLUMEDOWN => KeyCode::VolumeDown,
+            Key::KEY_VOLUMEUP => KeyCode::VolumeUp,
+            Key::KEY_KPEQUAL => KeyCode::NumpadEquals,
+            Key::KEY_PAUSE => KeyCode::Pause,
+            Key::KEY_KPCOMMA => KeyCode::NumpadComma,
+            Key::KEY_YEN => KeyCode::Yen,
+            Key::KEY_LEFTMETA => KeyCode::LWin,
+            Key::KEY_RIGHTMETA => KeyCode::RWin,
+            Key::KEY_COMPOSE => KeyCode::Compose,
+            _ => return None,
+        };
+
+        Some(key)
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod linux_tests;
@@ -0,0 +1,146 @@
+// Tests of the evdev `KeyboardBackend`, driven by `MockEventDevice`s instead of real keyboards.
+
+use std::time::{Duration, SystemTime};
+
+use bevy::prelude::KeyCode;
+use evdev::Key;
+
+use super::{
+    linux::{keycode_from_evdev, EvdevKeyboardBackend},
+    GlobalKeyboardRes, GlobalKeyboardSettings, KeyboardBackend,
+};
+use crate::input::raw_input::linux::{DeviceEvent, EventDevice, MockEventDevice};
+
+fn backend_with(devices: &[&MockEventDevice]) -> EvdevKeyboardBackend {
+    EvdevKeyboardBackend::from_devices(
+        devices
+            .iter()
+            .map(|&device| Box::new(device.clone()) as Box<dyn EventDevice>)
+            .collect(),
+    )
+}
+
+fn press(device: &MockEventDevice, key: Key, value: i32) {
+    device.push(DeviceEvent::Key(key.code(), value));
+}
+
+// The keys and states of key events, without their times.
+fn keys(events: &[(KeyCode, bool, SystemTime)]) -> Vec<(KeyCode, bool)> {
+    events
+        .iter()
+        .map(|&(key, pressed, _)| (key, pressed))
+        .collect()
+}
+
+#[test]
+fn only_keyboards_are_read() {
+    let keyboard = MockEventDevice::keyboard("Keyboard");
+    let gamepad = MockEventDevice::gamepad("Pad");
+    let backend = backend_with(&[&gamepad, &keyboard]);
+
+    assert_eq!(backend.device_names(), vec!["Keyboard".to_string()]);
+}
+
+#[test]
+fn only_picked_devices_are_read() {
+    let keyboard_a = MockEventDevice::keyboard("Keyboard A");
+    let keyboard_b = MockEventDevice::keyboard("Keyboard B");
+    let mut backend = backend_with(&[&keyboard_a, &keyboard_b]);
+
+    press(&keyboard_a, Key::KEY_A, 1);
+    press(&keyboard_b, Key::KEY_B, 1);
+    assert_eq!(
+        keys(&backend.read_key_events(&["Keyboard B".to_string()])),
+        vec![(KeyCode::B, true)]
+    );
+
+    // The events of devices that weren't picked are dropped rather than read later.
+    assert!(backend.read_key_events(&[]).is_empty());
+
+    press(&keyboard_a, Key::KEY_A, 0);
+    press(&keyboard_b, Key::KEY_B, 0);
+    assert_eq!(
+        keys(&backend.read_key_events(&[])),
+        vec![(KeyCode::A, false), (KeyCode::B, false)]
+    );
+}
+
+#[test]
+fn repeats_are_ignored() {
+    let keyboard = MockEventDevice::keyboard("Keyboard");
+    let mut backend = backend_with(&[&keyboard]);
+
+    press(&keyboard, Key::KEY_A, 1);
+    press(&keyboard, Key::KEY_A, 2);
+    press(&keyboard, Key::KEY_A, 2);
+    press(&keyboard, Key::KEY_A, 0);
+    assert_eq!(
+        keys(&backend.read_key_events(&[])),
+        vec![(KeyCode::A, true), (KeyCode::A, false)]
+    );
+}
+
+#[test]
+fn key_events_keep_the_device_time() {
+    let keyboard = MockEventDevice::keyboard("Keyboard");
+    let mut backend = backend_with(&[&keyboard]);
+
+    let pressed_at = SystemTime::UNIX_EPOCH + Duration::from_millis(1500);
+    let released_at = pressed_at + Duration::from_millis(20);
+    keyboard.push_at(DeviceEvent::Key(Key::KEY_A.code(), 1), pressed_at);
+    keyboard.push_at(DeviceEvent::Key(Key::KEY_A.code(), 0), released_at);
+    assert_eq!(
+        backend.read_key_events(&[]),
+        vec![
+            (KeyCode::A, true, pressed_at),
+            (KeyCode::A, false, released_at)
+        ]
+    );
+}
+
+#[test]
+fn evdev_keys_map_to_bevy_keys() {
+    for (key, key_code) in [
+        (Key::KEY_ESC, KeyCode::Escape),
+        (Key::KEY_0, KeyCode::Key0),
+        (Key::KEY_A, KeyCode::A),
+        (Key::KEY_ENTER, KeyCode::Return),
+        (Key::KEY_KPENTER, KeyCode::NumpadEnter),
+        (Key::KEY_LEFTMETA, KeyCode::LWin),
+        (Key::KEY_F12, KeyCode::F12),
+    ] {
+        assert_eq!(keycode_from_evdev(key.code()), Some(key_code));
+    }
+
+    // Keys that bevy has no `KeyCode` for aren't read.
+    assert_eq!(keycode_from_evdev(Key::KEY_PROG1.code()), None);
+    assert_eq!(keycode_from_evdev(Key::BTN_SOUTH.code()), None);
+}
+
+#[test]
+fn disabling_capture_releases_keys() {
+    let keyboard = MockEventDevice::keyboard("Keyboard");
+    let settings = GlobalKeyboardSettings {
+        enabled: true,
+        devices: vec![],
+    };
+    let mut global_keyboard =
+        GlobalKeyboardRes::with_backend(settings, Box::new(backend_with(&[&keyboard])));
+
+    press(&keyboard, Key::KEY_A, 1);
+    global_keyboard.update();
+    assert!(global_keyboard.pressed(KeyCode::A));
+    assert_eq!(keys(global_keyboard.get_events()), vec![(KeyCode::A, true)]);
+
+    global_keyboard.settings.enabled = false;
+    global_keyboard.update();
+    assert!(!global_keyboard.pressed(KeyCode::A));
+    assert!(global_keyboard.get_events().is_empty());
+
+    // Events read while capture is disabled are dropped.
+    press(&keyboard, Key::KEY_B, 1);
+    global_keyboard.update();
+    global_keyboard.settings.enabled = true;
+    global_keyboard.update();
+    assert!(!global_keyboard.pressed(KeyCode::B));
+}
@@ -1,26 +1,56 @@
-use bevy::core::FixedTimestep;
 use bevy::prelude::*;
 use serde::Deserialize;
 use serde::Serialize;
 
-use crate::controller::layout::ControllerKey;
+use crate::controller::layout::Binding;
 use crate::controller::layout::ControllerLayoutsRes;
-
+use crate::controller::layout::PlayerKey;
+
+use super::device::update_device_map_system;
+use super::device::DeviceMapRes;
+use super::events::collect_input_events_system;
+use super::events::InputEventLog;
+use super::events::TimedPress;
+use super::global_keyboard::update_global_keyboard_system;
+use super::global_keyboard::GlobalKeyboardRes;
 use super::listener::cleanup_input_listener_system;
 use super::listener::input_listener_system;
 use super::listener::ListenerResult;
+use super::mouse::update_mouse_input_system;
+use super::mouse::MouseAxis;
+use super::mouse::MouseInputRes;
+use super::playback::InputPlayback;
 use super::raw_input_reader::*;
+use super::recording::add_recording_systems;
+use super::recording::record_input_system;
+use super::response::partner_gamepad_axis;
+use super::response::partner_hid_axis;
+use super::response::InputResponse;
+use super::settings::poll_input_run_criteria;
+use super::settings::InputSettings;
+use super::stats::add_stats_systems;
+use super::stats::update_input_stats_system;
 use super::RawInputReader;
 
 // The smallest axis magnitude that isn't ignored when listening for axis input.
 pub const MIN_LISTENABLE_AXIS_MAG: f64 = 0.4;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum InputValue {
     Axis(f32),
     Button(bool),
 }
 
+impl InputValue {
+    // The strength of the value, counting pressed buttons as 1.0.
+    pub fn magnitude(self) -> f32 {
+        match self {
+            InputValue::Axis(v) => v.abs(),
+            InputValue::Button(pressed) => (pressed as u8) as f32,
+        }
+    }
+}
+
 pub type HidId = usize;
 pub type HidButtonId = usize;
 
@@ -45,6 +75,20 @@ impl HidHatSwitchId {
             _ => None,
         }
     }
+
+    // Map an evdev `ABS_HAT0X`/`ABS_HAT0Y` event to the hatswitch direction it presses.
+    #[cfg(target_os = "linux")]
+    pub fn from_evdev_hat(code: u16, value: i32) -> Option<HidHatSwitchId> {
+        use evdev::AbsoluteAxisType;
+
+        match (AbsoluteAxisType(code), value.signum()) {
+            (AbsoluteAxisType::ABS_HAT0X, -1) => Some(HidHatSwitchId::Left),
+            (AbsoluteAxisType::ABS_HAT0X, 1) => Some(HidHatSwitchId::Right),
+            (AbsoluteAxisType::ABS_HAT0Y, -1) => Some(HidHatSwitchId::Up),
+            (AbsoluteAxisType::ABS_HAT0Y, 1) => Some(HidHatSwitchId::Down),
+            _ => None,
+        }
+    }
 }
 
 // Duplicate of multiinput::Axis
@@ -72,6 +116,23 @@ impl HidAxisId {
             multiinput::Axis::SLIDER => HidAxisId::SLIDER,
         }
     }
+
+    // Map an evdev absolute axis code to the corresponding `HidAxisId`, if there is one.
+    #[cfg(target_os = "linux")]
+    pub fn from_evdev_axis(code: u16) -> Option<HidAxisId> {
+        use evdev::AbsoluteAxisType;
+
+        match AbsoluteAxisType(code) {
+            AbsoluteAxisType::ABS_X => Some(HidAxisId::X),
+            AbsoluteAxisType::ABS_Y => Some(HidAxisId::Y),
+            AbsoluteAxisType::ABS_Z => Some(HidAxisId::Z),
+            AbsoluteAxisType::ABS_RX => Some(HidAxisId::RX),
+            AbsoluteAxisType::ABS_RY => Some(HidAxisId::RY),
+            AbsoluteAxisType::ABS_RZ => Some(HidAxisId::RZ),
+            AbsoluteAxisType::ABS_THROTTLE => Some(HidAxisId::SLIDER),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -104,6 +165,11 @@ pub enum InputSource {
     HidButton(HidId, HidButtonId),
     HidAxis(HidId, HidAxisId, AxisSign),
     HidHatSwitch(HidId, HidHatSwitchId),
+    MouseButton(MouseButton),
+    // Mouse motion since the last few frames, as a decaying axis.
+    MouseMotion(MouseAxis, AxisSign),
+    // Mouse wheel ticks, as a decaying axis. Scrolling up is positive.
+    MouseWheel(AxisSign),
 }
 
 impl InputSource {
@@ -116,6 +182,16 @@ impl InputSource {
         }
     }
 
+    // Returns the same source on the HID device `id`, if this is a HID source.
+    pub fn with_hid_id(self, id: HidId) -> InputSource {
+        match self {
+            InputSource::HidButton(_, button) => InputSource::HidButton(id, button),
+            InputSource::HidAxis(_, axis, sign) => InputSource::HidAxis(id, axis, sign),
+            InputSource::HidHatSwitch(_, hat) => InputSource::HidHatSwitch(id, hat),
+            source => source,
+        }
+    }
+
     pub fn to_string(self) -> String {
         format!("{:?}", self)
     }
@@ -132,75 +208,295 @@ pub const GAMEPAD_AXES: [GamepadAxisType; 8] = [
     GamepadAxisType::DPadY,
 ];
 
-// Poll each `InputSource` in `sources`, storing the results as a vector of `Option<InputValue>`.
+pub const HID_AXES: [HidAxisId; 7] = [
+    HidAxisId::X,
+    HidAxisId::Y,
+    HidAxisId::Z,
+    HidAxisId::RX,
+    HidAxisId::RY,
+    HidAxisId::RZ,
+    HidAxisId::SLIDER,
+];
+
+pub const HID_HAT_SWITCHES: [HidHatSwitchId; 5] = [
+    HidHatSwitchId::Center,
+    HidHatSwitchId::Up,
+    HidHatSwitchId::Right,
+    HidHatSwitchId::Down,
+    HidHatSwitchId::Left,
+];
+
+// Read the signed value of a HID axis, combining the values of its two half-axes.
+fn poll_signed_hid_axis(
+    raw_input: &mut dyn RawInputReader,
+    id: HidId,
+    axis: HidAxisId,
+) -> Option<f32> {
+    let plus = raw_input.poll_hid_axis(&id, &axis, &AxisSign::Plus)?;
+    let minus = raw_input.poll_hid_axis(&id, &axis, &AxisSign::Minus)?;
+
+    match (plus, minus) {
+        (InputValue::Axis(plus), InputValue::Axis(minus)) => Some(plus - minus),
+        _ => None,
+    }
+}
+
+// Read the raw value of an axis source, before any calibration or response settings.
+// Returns `None` if `source` isn't an axis.
+pub fn poll_raw_axis(
+    source: &InputSource,
+    axis_input: &Axis<GamepadAxis>,
+    raw_input: &mut RawInputRes,
+) -> Option<f32> {
+    match *source {
+        InputSource::Axis(axis, _) => axis_input.get(axis),
+        InputSource::HidAxis(id, axis, _) => poll_signed_hid_axis(&mut *raw_input.0, id, axis),
+        _ => None,
+    }
+}
+
+// Combine the values of a chord's sources, which is only active if all of its sources are.
+// A chord of buttons is a button, and a chord containing an axis is an axis held to the value
+// of its weakest source.
+pub fn all_input_values(values: &[Option<InputValue>]) -> Option<InputValue> {
+    if values.iter().all(|value| value.is_none()) {
+        return None;
+    }
+
+    let magnitudes = values
+        .iter()
+        .map(|value| value.map_or(0.0, |v| v.magnitude()));
+    if values
+        .iter()
+        .any(|value| matches!(value, Some(InputValue::Axis(_))))
+    {
+        Some(InputValue::Axis(magnitudes.fold(1.0, f32::min)))
+    } else {
+        Some(InputValue::Button(magnitudes.fold(1.0, f32::min) > 0.0))
+    }
+}
+
+// Combine the values of a binding's chords, which is active if any of its chords are.
+// If any chord is an axis, the result is an axis with the value of the strongest chord.
+pub fn any_input_values(values: &[Option<InputValue>]) -> Option<InputValue> {
+    if values.iter().all(|value| value.is_none()) {
+        return None;
+    }
+
+    let magnitudes = values
+        .iter()
+        .map(|value| value.map_or(0.0, |v| v.magnitude()));
+    if values
+        .iter()
+        .any(|value| matches!(value, Some(InputValue::Axis(_))))
+    {
+        Some(InputValue::Axis(magnitudes.fold(0.0, f32::max)))
+    } else {
+        Some(InputValue::Button(magnitudes.fold(0.0, f32::max) > 0.0))
+    }
+}
+
+// Poll each `Binding` in `bindings`, storing the results as a vector of `Option<InputValue>`.
+// The calibration of each binding is applied to its primary source and its response settings
+// to each of its sources, before the sources are combined into chords and the chords into a
+// single value.
+// Buttons pressed at any point since the last poll, according to `event_log`, count as pressed.
+// Each value is returned along with the presses and releases of its binding since the last poll.
 pub fn poll_input_sources(
     keyboard_input: Res<Input<KeyCode>>,
+    global_keyboard: NonSend<GlobalKeyboardRes>,
     button_input: Res<Input<GamepadButton>>,
     axis_input: Res<Axis<GamepadAxis>>,
     mut raw_input: NonSendMut<RawInputRes>,
-    sources: Vec<&Option<InputSource>>,
-) -> Vec<Option<InputValue>> {
+    mouse_button_input: Res<Input<MouseButton>>,
+    mouse_input: Res<MouseInputRes>,
+    event_log: &InputEventLog,
+    bindings: Vec<&Option<Binding>>,
+) -> Vec<(Option<InputValue>, Vec<TimedPress>)> {
     use self::InputSource::*;
 
-    sources
+    bindings
         .iter()
-        .map(|wrapped_source| match wrapped_source {
-            None => None,
-            Some(Key(key_code)) => {
-                let pressed = keyboard_input.pressed(*key_code);
-                Some(InputValue::Button(pressed))
-            }
-            Some(Button(button)) => {
-                let pressed = button_input.pressed(*button);
-                Some(InputValue::Button(pressed))
-            }
-            Some(Axis(axis, sign)) => axis_input
-                .get(*axis)
-                .map(|value| InputValue::Axis(sign.clamp_f32(value))),
-            Some(HidAxis(id, axis, sign)) => raw_input.0.poll_hid_axis(&id, &axis, &sign),
-            Some(HidButton(id, button)) => raw_input.0.poll_hid_button(&id, &button),
-            Some(HidHatSwitch(id, hatswitch)) => raw_input.0.poll_hid_hatswitch(&id, &hatswitch),
+        .map(|wrapped_binding| {
+            let binding = wrapped_binding.as_ref()?;
+            let Binding {
+                chords,
+                response,
+                calibration,
+            } = binding;
+            // The partner of an axis is calibrated along with it, so that radial deadzones
+            // compare values on the same scale.
+            let primary_source = binding.primary_source().copied();
+            let calibrate = |source: &InputSource, value: f32| match calibration {
+                Some(c) if primary_source == Some(*source) => c.normalize(value),
+                _ => value,
+            };
+
+            let mut poll_source = |source: &InputSource| {
+                let value = match *source {
+                    Key(key_code) => {
+                        let pressed = keyboard_input.pressed(key_code)
+                            || global_keyboard.pressed(key_code)
+                            || event_log.was_pressed(source);
+                        Some(InputValue::Button(pressed))
+                    }
+                    Button(button) => {
+                        let pressed = button_input.pressed(button) || event_log.was_pressed(source);
+                        Some(InputValue::Button(pressed))
+                    }
+                    Axis(axis, sign) => {
+                        let value = calibrate(source, axis_input.get(axis)?);
+                        let partner_value = partner_gamepad_axis(axis.1)
+                            .and_then(|partner| axis_input.get(GamepadAxis(axis.0, partner)))
+                            .map_or(0.0, |partner_value| calibrate(source, partner_value));
+                        let value = response.apply_axis(value, partner_value, sign);
+                        Some(InputValue::Axis(value))
+                    }
+                    HidAxis(id, axis, sign) => {
+                        let value =
+                            calibrate(source, poll_signed_hid_axis(&mut *raw_input.0, id, axis)?);
+                        let partner_value = partner_hid_axis(axis)
+                            .and_then(|partner| {
+                                poll_signed_hid_axis(&mut *raw_input.0, id, partner)
+                            })
+                            .map_or(0.0, |partner_value| calibrate(source, partner_value));
+                        let value = response.apply_axis(value, partner_value, sign);
+                        Some(InputValue::Axis(value))
+                    }
+                    HidButton(id, button) => {
+                        let value = raw_input.0.poll_hid_button(&id, &button)?;
+                        let pressed =
+                            value == InputValue::Button(true) || event_log.was_pressed(source);
+                        Some(InputValue::Button(pressed))
+                    }
+                    HidHatSwitch(id, hatswitch) => {
+                        let value = raw_input.0.poll_hid_hatswitch(&id, &hatswitch)?;
+                        let pressed =
+                            value == InputValue::Button(true) || event_log.was_pressed(source);
+                        Some(InputValue::Button(pressed))
+                    }
+                    InputSource::MouseButton(button) => {
+                        let pressed =
+                            mouse_button_input.pressed(button) || event_log.was_pressed(source);
+                        Some(InputValue::Button(pressed))
+                    }
+                    InputSource::MouseMotion(axis, sign) => {
+                        let value = mouse_input.axis(axis);
+                        let partner_value = mouse_input.axis(axis.partner());
+                        let value = response.apply_axis(value, partner_value, sign);
+                        Some(InputValue::Axis(value))
+                    }
+                    InputSource::MouseWheel(sign) => {
+                        let value = response.apply_axis(mouse_input.wheel(), 0.0, sign);
+                        Some(InputValue::Axis(value))
+                    }
+                };
+
+                match value {
+                    Some(InputValue::Button(pressed)) => {
+                        Some(InputValue::Button(response.apply_button(pressed)))
+                    }
+                    value => value,
+                }
+            };
+
+            let source_values: Vec<Vec<Option<InputValue>>> = chords
+                .iter()
+                .map(|chord| chord.sources.iter().map(|s| poll_source(s)).collect())
+                .collect();
+            let chord_values: Vec<Option<InputValue>> = source_values
+                .iter()
+                .map(|values| all_input_values(values))
+                .collect();
+
+            let presses = event_log.binding_presses(binding, &source_values);
+            Some((any_input_values(&chord_values), presses))
         })
+        .map(|polled| polled.unwrap_or((None, vec![])))
         .collect()
 }
 
 #[derive(Component)]
 pub struct InputSink {
     // The abstract controller keys associated with this sink.
-    // Updating `keys` will automatically propagate to both `sources` and then `values`.
-    pub keys: Vec<Option<ControllerKey>>,
+    // Updating `keys` will automatically propagate to both `bindings` and then `values`.
+    pub keys: Vec<Option<PlayerKey>>,
 
-    // The concrete input sources associated with this sink.
-    // Updating `sources` will automatically propagate to `values`.
-    pub sources: Vec<Option<InputSource>>,
+    // The concrete bindings (input sources and their response settings) associated with this sink.
+    // Updating `bindings` will automatically propagate to `values`.
+    pub bindings: Vec<Option<Binding>>,
 
-    // The concrete input values associated with the sources in `sources`.
+    // The concrete input values associated with the bindings in `bindings`.
     // Automatically updated by the input sink resolution system.
     pub values: Vec<Option<InputValue>>,
 
-    // A flag indicating that `sources` is not synced with `keys`.
+    // Whether each entry of `values` is pressed when read as a button.
+    // Axis values are compared against the thresholds of their binding's response, so this
+    // is latched from one update to the next; call `latch_pressed` after writing `values`.
+    pub pressed: Vec<bool>,
+
+    // The presses and releases of each binding since the previous poll, found by evaluating
+    // the binding after each button event of its sources.
+    // These are only collected if `InputSettings::timestamped_presses` is set.
+    pub timed_presses: Vec<Vec<TimedPress>>,
+
+    // A flag indicating that `bindings` is not synced with `keys`.
     // The input sink resolution system will resync these vectors during the next
     // execution of the input sink resolution system.
     pub sources_dirty: bool,
 }
 
 impl InputSink {
-    pub fn new(keys: Vec<Option<ControllerKey>>) -> InputSink {
+    pub fn new(keys: Vec<Option<PlayerKey>>) -> InputSink {
         let size = keys.len();
         InputSink {
             keys,
-            sources: vec![None; size],
+            bindings: vec![None; size],
             values: vec![None; size],
+            pressed: vec![false; size],
+            timed_presses: vec![vec![]; size],
             sources_dirty: true,
         }
     }
+
+    // Recompute `pressed` from the current `values`.
+    pub fn latch_pressed(&mut self) {
+        let default_response = InputResponse::default();
+        for i in 0..self.values.len() {
+            let response = self.bindings[i]
+                .as_ref()
+                .map_or(&default_response, |binding| &binding.response);
+
+            self.pressed[i] = match self.values[i] {
+                Some(InputValue::Button(pressed)) => pressed,
+                Some(InputValue::Axis(v)) => response.apply_threshold(v.abs(), self.pressed[i]),
+                None => false,
+            };
+        }
+    }
+
+    // Returns `true` if the value at `idx` is pressed when read as a button.
+    pub fn is_pressed(&self, idx: usize) -> bool {
+        self.pressed.get(idx).copied().unwrap_or(false)
+    }
+
+    // Returns the value at `idx` read as an axis, where buttons are either 0.0 or 1.0.
+    pub fn axis_value(&self, idx: usize) -> f32 {
+        match self.values.get(idx) {
+            Some(Some(InputValue::Axis(v))) => *v,
+            Some(Some(InputValue::Button(pressed))) => (*pressed as u8) as f32,
+            _ => 0.0,
+        }
+    }
 }
 
 // Mutate each `InputSink` with the `sources_dirty` flag set to `true`.
-// Update a dirty sink's `sources` vectors by mapping each entry of its `keys` vector
-// to the binding found in the global controller layout resource.
+// Update a dirty sink's `bindings` vectors by mapping each entry of its `keys` vector
+// to the binding found in the global controller layout resource, with its saved devices
+// resolved to the connected joysticks.
 pub fn resolve_dirty_sources_system(
     layouts: Res<ControllerLayoutsRes>,
+    device_map: Res<DeviceMapRes>,
     mut query: Query<&mut InputSink>,
 ) {
     for mut sink in query.iter_mut() {
@@ -208,15 +504,15 @@ pub fn resolve_dirty_sources_system(
             continue;
         }
 
-        // Collect the `InputSource` bindings associated to each `ControllerKey`.
+        // Collect the bindings associated to each `PlayerKey`.
         let mut bindings = vec![];
         for &key in sink.keys.iter() {
             bindings.push(key.map(|k| layouts.get_binding(k)).flatten());
         }
 
         // Write those bindings to the `InputSink`.
-        for (i, &source) in bindings.iter().enumerate() {
-            sink.sources[i] = source.map(|source| *source);
+        for (i, binding) in bindings.into_iter().enumerate() {
+            sink.bindings[i] = binding.map(|b| device_map.resolve_binding(b));
         }
 
         sink.sources_dirty = false;
@@ -225,54 +521,85 @@ pub fn resolve_dirty_sources_system(
 
 // Mutate each `InputSink` component with the current value of the input source
 // given by its `InputSource` field.
+// This is skipped while a recorded session is being played back into the sinks.
 pub fn resolve_input_sinks_system(
+    playback: Res<InputPlayback>,
     keyboard_input: Res<Input<KeyCode>>,
+    global_keyboard: NonSend<GlobalKeyboardRes>,
     button_input: Res<Input<GamepadButton>>,
     axis_input: Res<Axis<GamepadAxis>>,
     raw_input: NonSendMut<RawInputRes>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    mouse_input: Res<MouseInputRes>,
+    mut event_log: ResMut<InputEventLog>,
     mut query: Query<&mut InputSink>,
 ) {
-    let mut sources = Vec::new();
+    if playback.is_active() {
+        event_log.clear();
+        return;
+    }
 
-    // Collect the input sources from all sinks.
+    let mut bindings = Vec::new();
+
+    // Collect the bindings from all sinks.
     for sink in query.iter() {
-        for source in sink.sources.iter() {
-            sources.push(source);
+        for binding in sink.bindings.iter() {
+            bindings.push(binding);
         }
     }
 
     // Poll the value of each input source
-    let input_values =
-        poll_input_sources(keyboard_input, button_input, axis_input, raw_input, sources);
+    let input_values = poll_input_sources(
+        keyboard_input,
+        global_keyboard,
+        button_input,
+        axis_input,
+        raw_input,
+        mouse_button_input,
+        mouse_input,
+        &event_log,
+        bindings,
+    );
 
     // Write those values to their associated sources
     let mut sink_start = 0;
     for mut sink in query.iter_mut() {
         let sink_len = sink.keys.len();
         for i in 0..sink_len {
-            sink.values[i] = input_values[sink_start + i];
+            let (value, presses) = &input_values[sink_start + i];
+            sink.values[i] = *value;
+            sink.timed_presses[i] = presses.clone();
         }
+        sink.latch_pressed();
         sink_start += sink_len;
     }
-}
 
-const POLL_INPUT_TIME_STEP: f32 = 1.0 / 60.0;
-// const POLL_INPUT_TIME_STEP: f32 = 1.0 / 3.0;
+    event_log.clear();
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, SystemLabel)]
 pub enum InputSystemLabel {
     PollRawinput,
+    UpdateGlobalKeyboard,
+    UpdateMouse,
+    UpdateDevices,
+    CollectEvents,
     ResolveDirtySources,
     ResolveInputValues,
+    RecordInput,
+    UpdateStats,
 }
 
 pub fn add_input_systems(app: &mut App) {
-    // Add the rawinput polling system when targeting Windows.
-    #[cfg(target_family = "windows")]
+    // Add the input settings, which control how often the polling systems below run.
+    app.insert_resource(InputSettings::read());
+
+    // Add the rawinput polling system when targeting Windows or Linux.
+    #[cfg(any(target_family = "windows", target_os = "linux"))]
     {
         app.add_system_set(
             SystemSet::new()
-                .with_run_criteria(FixedTimestep::step(POLL_INPUT_TIME_STEP as f64))
+                .with_run_criteria(poll_input_run_criteria)
                 .with_system(
                     super::raw_input_reader::poll_rawinput_system
                         .label(InputSystemLabel::PollRawinput),
@@ -280,24 +607,73 @@ pub fn add_input_systems(app: &mut App) {
         );
     }
 
-    // Add the global rawinput resource, which is a no-op on platforms besides Windows.
+    // Add the global rawinput resource, which is a no-op on platforms besides Windows and Linux.
     app.init_non_send_resource::<RawInputRes>();
 
+    // Add the device map, which resolves the devices saved in bindings to connected joysticks.
+    app.init_resource::<DeviceMapRes>();
+    app.add_system(update_device_map_system.label(InputSystemLabel::UpdateDevices));
+
+    // Add the background keyboard resource, which only reads devices on Linux.
+    // This is updated every frame so that the input listener doesn't miss short key presses.
+    app.init_non_send_resource::<GlobalKeyboardRes>();
+    app.add_system(update_global_keyboard_system.label(InputSystemLabel::UpdateGlobalKeyboard));
+
+    // Add the mouse resource, which turns mouse motion and wheel events into decaying axes.
+    app.init_resource::<MouseInputRes>();
+    app.add_system(update_mouse_input_system.label(InputSystemLabel::UpdateMouse));
+
+    // Add the event log, which keeps button taps between polls from being lost.
+    app.init_resource::<InputEventLog>();
+    app.add_system(
+        collect_input_events_system
+            .label(InputSystemLabel::CollectEvents)
+            .after(InputSystemLabel::PollRawinput)
+            .after(InputSystemLabel::UpdateGlobalKeyboard),
+    );
+
     // Add the input resolution system to write up-to-date input to `InputSink` components.
     app.add_system_set(
         SystemSet::new()
-            .with_run_criteria(FixedTimestep::step(POLL_INPUT_TIME_STEP as f64))
-            .with_system(resolve_dirty_sources_system.label(InputSystemLabel::ResolveDirtySources))
+            .with_run_criteria(poll_input_run_criteria)
+            .with_system(
+                resolve_dirty_sources_system
+                    .label(InputSystemLabel::ResolveDirtySources)
+                    .after(InputSystemLabel::UpdateDevices),
+            )
             .with_system(
                 resolve_input_sinks_system
                     .label(InputSystemLabel::ResolveInputValues)
                     .after(InputSystemLabel::PollRawinput)
+                    .after(InputSystemLabel::UpdateGlobalKeyboard)
+                    .after(InputSystemLabel::UpdateMouse)
+                    .after(InputSystemLabel::CollectEvents)
                     .after(InputSystemLabel::ResolveDirtySources),
+            )
+            .with_system(
+                record_input_system
+                    .label(InputSystemLabel::RecordInput)
+                    .after(InputSystemLabel::ResolveInputValues),
+            )
+            .with_system(
+                update_input_stats_system
+                    .label(InputSystemLabel::UpdateStats)
+                    .after(InputSystemLabel::ResolveInputValues),
             ),
     );
 
+    // Add the systems to start, stop and export input recordings.
+    add_recording_systems(app);
+
+    // Add the input statistics, which are updated above and saved every so often.
+    add_stats_systems(app);
+
     // Add the input listener system and events
     app.add_event::<ListenerResult>();
-    app.add_system(input_listener_system);
+    app.add_system(
+        input_listener_system
+            .after(InputSystemLabel::UpdateGlobalKeyboard)
+            .after(InputSystemLabel::UpdateMouse),
+    );
     app.add_system(cleanup_input_listener_system);
 }
@@ -1,17 +1,36 @@
-use std::fs::OpenOptions;
+use std::{cmp::Ordering, fs::OpenOptions};
 
 use bevy::prelude::*;
 
 use crate::{
-    controller::layout::{ControllerKey, ControllerLayout, ControllerLayoutsRes},
+    controller::layout::{ControllerLayoutsRes, PlayerKey, SourceChord},
     editor::inspector::InputSinkId,
     input::{
-        input::{AxisSign, InputSink, InputSource, GAMEPAD_AXES, MIN_LISTENABLE_AXIS_MAG},
+        calibration::{AxisRange, FULL_RANGE_MIN_REST},
+        device::DeviceMapRes,
+        global_keyboard::GlobalKeyboardRes,
+        input::{
+            poll_raw_axis, AxisSign, InputSink, InputSource, InputValue, GAMEPAD_AXES, HID_AXES,
+            MIN_LISTENABLE_AXIS_MAG,
+        },
+        mouse::{MouseAxis, MouseInputRes},
         raw_input_reader::RawInputRes,
+        settings::InputSettings,
         RawInputReader,
     },
+    state::is_state_hotkey,
 };
 
+// The smallest mouse motion axis magnitude that isn't ignored when listening for mouse input.
+// This is higher than for other axes so that moving the cursor to the UI doesn't bind motion.
+const MIN_LISTENABLE_MOUSE_MOTION: f32 = 1.0;
+// The smallest mouse wheel axis magnitude that isn't ignored when listening for mouse input.
+const MIN_LISTENABLE_MOUSE_WHEEL: f32 = 0.5;
+
+// The key which cancels listening for input. Only a key cancels, so that every mouse
+// button can be bound.
+const CANCEL_KEY: KeyCode = KeyCode::Escape;
+
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 enum ListenerState {
     // Not listening for input.
@@ -25,19 +44,37 @@ enum ListenerState {
 // The consumers who can invoke the listener to capture the next heard input.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ListenerConsumer {
-    Key(ControllerKey),
+    Key(PlayerKey),
     Sink(InputSinkId),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ListenerResult {
-    SourceToKey(InputSource, ControllerKey),
-    KeyToSink(ControllerKey, InputSinkId),
+    // The chord heard for a player key, and the slot of the key's binding that it replaces.
+    // A slot of `None` means the chord is added to the binding.
+    // If the chord starts with an axis, the range that axis was heard moving through is included.
+    SourceToKey(SourceChord, PlayerKey, Option<usize>, Option<AxisRange>),
+    KeyToSink(PlayerKey, InputSinkId),
 }
 
 pub struct InputListener {
     state: ListenerState,
     consumer: Option<ListenerConsumer>,
+    // The binding slot replaced by the chord being listened for.
+    slot: Option<usize>,
+    // The sources heard so far while listening for a chord, in the order they were pressed.
+    chord: Vec<InputSource>,
+    // The range of the first source of `chord`, if it's an axis.
+    range: Option<AxisRange>,
+    // The value of every axis when the listener started.
+    baseline: AxisBaseline,
+    // Whether the listener has set up its timeout and ignored sources since it started.
+    started: bool,
+    // The seconds left before the listener gives up, if it has a timeout.
+    time_left: Option<f32>,
+    // The buttons that were held when the listener started, such as the key or mouse button
+    // that opened it, which aren't heard until they're released.
+    ignored: Vec<InputSource>,
     pub result: Option<ListenerResult>,
 }
 
@@ -46,6 +83,13 @@ impl Default for InputListener {
         InputListener {
             state: ListenerState::Inactive,
             consumer: None,
+            slot: None,
+            chord: vec![],
+            range: None,
+            baseline: AxisBaseline::default(),
+            started: false,
+            time_left: None,
+            ignored: vec![],
             result: None,
         }
     }
@@ -53,25 +97,41 @@ impl Default for InputListener {
 
 impl InputListener {
     // Start listening for a new binding.
-    // This is used to register an input source to a controller key.
-    pub fn listen_input_source(&mut self, key: ControllerKey) {
+    // This is used to register a chord of input sources to a player key, either replacing
+    // the chord in `slot` of the key's binding or adding a new chord if `slot` is `None`.
+    // The chord is complete once any of its held sources is released.
+    pub fn listen_input_source(&mut self, key: PlayerKey, slot: Option<usize>) {
         self.state = ListenerState::ListenInputSource;
         self.consumer = Some(ListenerConsumer::Key(key));
+        self.slot = slot;
+        self.chord.clear();
+        self.range = None;
+        self.started = false;
     }
 
     pub fn listening_for_input_source(&self) -> bool {
         self.state == ListenerState::ListenInputSource
     }
 
-    pub fn has_key_consumer(&self, key: ControllerKey) -> bool {
+    pub fn has_key_consumer(&self, key: PlayerKey) -> bool {
         self.consumer == Some(ListenerConsumer::Key(key))
     }
 
+    pub fn has_slot_consumer(&self, key: PlayerKey, slot: Option<usize>) -> bool {
+        self.has_key_consumer(key) && self.slot == slot
+    }
+
+    // The sources of the chord heard so far, while listening for a binding.
+    pub fn partial_chord(&self) -> &[InputSource] {
+        &self.chord
+    }
+
     // Start listening for input from an already-bound `ControllerKey`.
     // This is used to register a controller key to an input display.
     pub fn listen_for_controller_key(&mut self, sink: InputSinkId) {
         self.state = ListenerState::ListenControllerKey;
         self.consumer = Some(ListenerConsumer::Sink(sink));
+        self.started = false;
     }
 
     pub fn listening_for_controller_key(&self) -> bool {
@@ -86,54 +146,293 @@ impl InputListener {
     // inspector UI is awkward, so instead the UI will read the
     // listener result from here.
     pub fn consume_result(&mut self) -> Option<ListenerResult> {
-        let result = self.result;
-        self.result = None;
-        result
+        self.result.take()
+    }
+
+    // The seconds left before the listener gives up, if it's listening with a timeout.
+    pub fn time_left(&self) -> Option<f32> {
+        match self.state {
+            ListenerState::Inactive => None,
+            _ => self.time_left,
+        }
     }
 
     pub fn stop_listening(&mut self) {
         self.state = ListenerState::Inactive;
         self.consumer = None;
+        self.slot = None;
+        self.chord.clear();
+        self.range = None;
+        self.baseline = AxisBaseline::default();
+        self.started = false;
+        self.time_left = None;
+        self.ignored.clear();
+    }
+
+    // Returns `true` if `source` should never be heard by the listener.
+    fn is_ignored(&self, source: &InputSource) -> bool {
+        match source {
+            InputSource::Key(k) => is_state_hotkey(*k) || self.ignored.contains(source),
+            _ => self.ignored.contains(source),
+        }
+    }
+}
+
+// Returns `true` if the user asked to stop listening this frame.
+fn cancel_requested(keyboard: &Input<KeyCode>, global_keyboard: &GlobalKeyboardRes) -> bool {
+    keyboard.just_pressed(CANCEL_KEY)
+        || global_keyboard.get_just_pressed().any(|k| *k == CANCEL_KEY)
+}
+
+// The keys, gamepad buttons, joystick buttons and hat directions, and mouse buttons
+// currently held down.
+fn held_buttons(
+    keyboard: &Input<KeyCode>,
+    global_keyboard: &GlobalKeyboardRes,
+    buttons: &Input<GamepadButton>,
+    raw: &mut RawInputRes,
+    mouse_buttons: &Input<MouseButton>,
+) -> Vec<InputSource> {
+    keyboard
+        .get_pressed()
+        .chain(global_keyboard.get_pressed())
+        .map(|k| InputSource::Key(*k))
+        .chain(buttons.get_pressed().map(|b| InputSource::Button(*b)))
+        .chain(raw.0.held_buttons())
+        .chain(
+            mouse_buttons
+                .get_pressed()
+                .map(|b| InputSource::MouseButton(*b)),
+        )
+        .collect()
+}
+
+// The same axis source as `source` with the given sign, or `source` itself if it isn't an axis.
+fn with_axis_sign(source: &InputSource, sign: AxisSign) -> InputSource {
+    match *source {
+        InputSource::Axis(axis, _) => InputSource::Axis(axis, sign),
+        InputSource::HidAxis(id, axis, _) => InputSource::HidAxis(id, axis, sign),
+        source => source,
+    }
+}
+
+// The signed value of every gamepad and HID axis, each keyed by its `Plus` axis source.
+fn read_axes(
+    axes: &Axis<GamepadAxis>,
+    gamepads: &Gamepads,
+    raw: &mut RawInputRes,
+) -> Vec<(InputSource, f32)> {
+    let mut values = vec![];
+
+    for gamepad in gamepads.iter() {
+        for axis in GAMEPAD_AXES {
+            let gamepad_axis = GamepadAxis(*gamepad, axis);
+            if let Some(value) = axes.get(gamepad_axis) {
+                values.push((InputSource::Axis(gamepad_axis, AxisSign::Plus), value));
+            }
+        }
+    }
+
+    for id in 0..raw.0.num_joysticks() {
+        for axis in HID_AXES {
+            let source = InputSource::HidAxis(id, axis, AxisSign::Plus);
+            if let Some(value) = poll_raw_axis(&source, axes, raw) {
+                values.push((source, value));
+            }
+        }
+    }
+
+    values
+}
+
+// The value of every axis when the listener started, which axis movement is measured from.
+// Triggers rest at one end of their range and worn sticks drift, so an axis is only heard
+// once it moves far enough away from where it was resting.
+#[derive(Debug, Default)]
+pub struct AxisBaseline {
+    rest: Vec<(InputSource, f32)>,
+}
+
+impl AxisBaseline {
+    pub fn capture(
+        axes: &Axis<GamepadAxis>,
+        gamepads: &Gamepads,
+        raw: &mut RawInputRes,
+    ) -> AxisBaseline {
+        AxisBaseline {
+            rest: read_axes(axes, gamepads, raw),
+        }
+    }
+
+    // The value of the axis of `source` when the baseline was captured.
+    // Axes which weren't connected yet are assumed to rest at the center.
+    pub fn rest(&self, source: &InputSource) -> f32 {
+        let source = with_axis_sign(source, AxisSign::Plus);
+        self.rest
+            .iter()
+            .find(|(s, _)| *s == source)
+            .map_or(0.0, |&(_, value)| value)
+    }
+
+    // The axis that moved the furthest from its rest, if any moved far enough to be heard,
+    // along with the range it's moving through.
+    pub fn moved_axis(
+        &self,
+        axes: &Axis<GamepadAxis>,
+        gamepads: &Gamepads,
+        raw: &mut RawInputRes,
+    ) -> Option<(InputSource, AxisRange)> {
+        let (source, rest, movement) = read_axes(axes, gamepads, raw)
+            .into_iter()
+            .map(|(source, value)| {
+                let rest = self.rest(&source);
+                (source, rest, value - rest)
+            })
+            .filter(|&(_, _, movement)| movement.abs() > MIN_LISTENABLE_AXIS_MAG as f32)
+            .max_by(|a, b| a.2.abs().partial_cmp(&b.2.abs()).unwrap_or(Ordering::Equal))?;
+
+        let sign = if movement > 0.0 {
+            AxisSign::Plus
+        } else {
+            AxisSign::Minus
+        };
+
+        // An axis resting near one end of its range moves through all of it towards the other.
+        let range = if rest.abs() >= FULL_RANGE_MIN_REST && rest.signum() != movement.signum() {
+            AxisRange::Full { rest }
+        } else {
+            AxisRange::Half
+        };
+
+        Some((with_axis_sign(&source, sign), range))
+    }
+
+    // Returns `true` if the axis of `source` is still moved away from its rest in the
+    // direction of its sign.
+    pub fn is_moved(
+        &self,
+        source: &InputSource,
+        axes: &Axis<GamepadAxis>,
+        raw: &mut RawInputRes,
+    ) -> bool {
+        let movement = match poll_raw_axis(source, axes, raw) {
+            Some(value) => value - self.rest(source),
+            None => return false,
+        };
+        let min_mag = MIN_LISTENABLE_AXIS_MAG as f32;
+
+        match source {
+            InputSource::Axis(_, AxisSign::Plus) | InputSource::HidAxis(_, _, AxisSign::Plus) => {
+                movement > min_mag
+            }
+            _ => movement < -min_mag,
+        }
+    }
+}
+
+// Returns `true` if `source` is still held down, so that a chord can keep growing.
+// Mouse motion and wheel ticks are momentary, so they end a chord as soon as they're heard.
+pub fn is_source_held(
+    source: &InputSource,
+    keyboard: &Input<KeyCode>,
+    global_keyboard: &GlobalKeyboardRes,
+    buttons: &Input<GamepadButton>,
+    axes: &Axis<GamepadAxis>,
+    raw: &mut RawInputRes,
+    mouse_buttons: &Input<MouseButton>,
+    baseline: &AxisBaseline,
+) -> bool {
+    match source {
+        InputSource::Key(k) => keyboard.pressed(*k) || global_keyboard.pressed(*k),
+        InputSource::Button(b) => buttons.pressed(*b),
+        InputSource::Axis(_, _) | InputSource::HidAxis(_, _, _) => {
+            baseline.is_moved(source, axes, raw)
+        }
+        InputSource::HidButton(id, button) => {
+            matches!(
+                raw.0.poll_hid_button(id, button),
+                Some(InputValue::Button(true))
+            )
+        }
+        InputSource::HidHatSwitch(id, hat) => {
+            matches!(
+                raw.0.poll_hid_hatswitch(id, hat),
+                Some(InputValue::Button(true))
+            )
+        }
+        InputSource::MouseButton(b) => mouse_buttons.pressed(*b),
+        InputSource::MouseMotion(_, _) | InputSource::MouseWheel(_) => false,
     }
 }
 
+// Listen for the next pressed button, key, mouse button, wheel tick or mouse motion.
+// Axes are heard separately, relative to an `AxisBaseline`.
 pub fn listen_for_input_source(
-    keyboard: Res<Input<KeyCode>>,
-    buttons: Res<Input<GamepadButton>>,
-    axes: Res<Axis<GamepadAxis>>,
-    mut raw: NonSendMut<RawInputRes>,
-    gamepads: Res<Gamepads>,
+    keyboard: &Input<KeyCode>,
+    global_keyboard: &GlobalKeyboardRes,
+    buttons: &Input<GamepadButton>,
+    raw: &mut RawInputRes,
+    mouse_buttons: &Input<MouseButton>,
+    mouse: &MouseInputRes,
+    is_ignored: impl Fn(&InputSource) -> bool,
 ) -> Option<InputSource> {
-    // Listen for rawinput
+    // Listen for rawinput, besides axes
     if let Some(rawinput_source) = raw.0.listen() {
-        return Some(rawinput_source);
+        if !matches!(rawinput_source, InputSource::HidAxis(_, _, _))
+            && !is_ignored(&rawinput_source)
+        {
+            return Some(rawinput_source);
+        }
     }
 
-    // Listen for keyboard input
-    for keycode in keyboard.get_just_pressed() {
-        return Some(InputSource::Key(*keycode));
+    // Listen for keyboard input, including keyboard input captured in the background
+    let keycodes = keyboard
+        .get_just_pressed()
+        .chain(global_keyboard.get_just_pressed());
+    for keycode in keycodes {
+        let source = InputSource::Key(*keycode);
+        if !is_ignored(&source) {
+            return Some(source);
+        }
     }
 
     // Listen for xinput buttons
     for b in buttons.get_just_pressed() {
-        return Some(InputSource::Button(*b));
+        let source = InputSource::Button(*b);
+        if !is_ignored(&source) {
+            return Some(source);
+        }
     }
 
-    // Listen for xinput axes
-    for gamepad in gamepads.iter() {
-        for axis in GAMEPAD_AXES {
-            let gamepad_axis = GamepadAxis(*gamepad, axis);
-            let axis_input = axes.get(gamepad_axis);
+    // Listen for mouse buttons
+    for b in mouse_buttons.get_just_pressed() {
+        let source = InputSource::MouseButton(*b);
+        if !is_ignored(&source) {
+            return Some(source);
+        }
+    }
 
-            match axis_input {
-                Some(f) if f > (MIN_LISTENABLE_AXIS_MAG as f32) => {
-                    return Some(InputSource::Axis(gamepad_axis, AxisSign::Plus))
-                }
-                Some(f) if f < (-MIN_LISTENABLE_AXIS_MAG as f32) => {
-                    return Some(InputSource::Axis(gamepad_axis, AxisSign::Minus))
-                }
-                _ => (),
+    // Listen for mouse wheel ticks
+    let wheel = mouse.wheel();
+    if wheel.abs() >= MIN_LISTENABLE_MOUSE_WHEEL {
+        let sign = if wheel > 0.0 {
+            AxisSign::Plus
+        } else {
+            AxisSign::Minus
+        };
+        return Some(InputSource::MouseWheel(sign));
+    }
+
+    // Listen for mouse motion
+    for axis in [MouseAxis::X, MouseAxis::Y] {
+        let value = mouse.axis(axis);
+        if value.abs() >= MIN_LISTENABLE_MOUSE_MOTION {
+            let sign = if value > 0.0 {
+                AxisSign::Plus
+            } else {
+                AxisSign::Minus
             };
+            return Some(InputSource::MouseMotion(axis, sign));
         }
     }
 
@@ -141,22 +440,135 @@ pub fn listen_for_input_source(
 }
 
 pub fn input_listener_system(
+    time: Res<Time>,
+    settings: Res<InputSettings>,
     mut input_listener: ResMut<InputListener>,
     layouts: ResMut<ControllerLayoutsRes>,
+    device_map: Res<DeviceMapRes>,
     keyboard: Res<Input<KeyCode>>,
+    global_keyboard: NonSend<GlobalKeyboardRes>,
     buttons: Res<Input<GamepadButton>>,
     axes: Res<Axis<GamepadAxis>>,
-    raw: NonSendMut<RawInputRes>,
+    mut raw: NonSendMut<RawInputRes>,
     gamepads: Res<Gamepads>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mouse: Res<MouseInputRes>,
     mut event_writer: EventWriter<ListenerResult>,
 ) {
+    if input_listener.state == ListenerState::Inactive {
+        return;
+    }
+
+    // Ignore whatever was held when the listener started, and start the timeout.
+    if !input_listener.started {
+        input_listener.started = true;
+        input_listener.ignored = held_buttons(
+            &keyboard,
+            &global_keyboard,
+            &buttons,
+            &mut raw,
+            &mouse_buttons,
+        );
+        input_listener.baseline = AxisBaseline::capture(&axes, &gamepads, &mut raw);
+        input_listener.time_left = if settings.listen_timeout > 0.0 {
+            Some(settings.listen_timeout)
+        } else {
+            None
+        };
+    }
+
+    if cancel_requested(&keyboard, &global_keyboard) {
+        input_listener.stop_listening();
+        return;
+    }
+
+    if let Some(time_left) = input_listener.time_left.as_mut() {
+        *time_left -= time.delta_seconds();
+        if *time_left <= 0.0 {
+            input_listener.stop_listening();
+            return;
+        }
+    }
+
+    // Stop ignoring the held sources once they're released.
+    let mut ignored = std::mem::take(&mut input_listener.ignored);
+    ignored.retain(|source| {
+        is_source_held(
+            source,
+            &keyboard,
+            &global_keyboard,
+            &buttons,
+            &axes,
+            &mut raw,
+            &mouse_buttons,
+            &input_listener.baseline,
+        )
+    });
+    input_listener.ignored = ignored;
+
+    let heard_source = listen_for_input_source(
+        &keyboard,
+        &global_keyboard,
+        &buttons,
+        &mut raw,
+        &mouse_buttons,
+        &mouse,
+        |source| input_listener.is_ignored(source),
+    );
+
+    // Listen for axes moving away from where they were resting when the listener started.
+    let (heard_source, heard_range) = match heard_source {
+        Some(source) => (Some(source), None),
+        None => match input_listener
+            .baseline
+            .moved_axis(&axes, &gamepads, &mut raw)
+        {
+            Some((source, range)) => (Some(source), Some(range)),
+            None => (None, None),
+        },
+    };
+
     match input_listener.state {
         ListenerState::ListenInputSource => {
-            // Stop listening on any heard input source.
-            if let Some(source) = listen_for_input_source(keyboard, buttons, axes, raw, gamepads) {
+            // Add each newly heard source to the chord.
+            if let Some(source) = heard_source {
+                if !input_listener.chord.contains(&source) {
+                    if input_listener.chord.is_empty() {
+                        input_listener.range = heard_range;
+                    }
+                    input_listener.chord.push(source);
+                }
+            }
+
+            // Stop listening once any source of the chord is released.
+            if input_listener.chord.is_empty() {
+                return;
+            }
+            let all_held = input_listener.chord.iter().all(|source| {
+                is_source_held(
+                    source,
+                    &keyboard,
+                    &global_keyboard,
+                    &buttons,
+                    &axes,
+                    &mut raw,
+                    &mouse_buttons,
+                    &input_listener.baseline,
+                )
+            });
+
+            if !all_held {
                 if let Some(ListenerConsumer::Key(key)) = input_listener.consumer {
-                    // TODO: change this to an event
-                    event_writer.send(ListenerResult::SourceToKey(source, key));
+                    let chord = SourceChord {
+                        sources: input_listener.chord.drain(..).collect(),
+                    };
+                    let range = input_listener.range.take();
+                    event_writer.send(ListenerResult::SourceToKey(
+                        chord,
+                        key,
+                        input_listener.slot,
+                        range,
+                    ));
                 } else {
                     panic!("weird input listener state");
                 }
@@ -164,11 +576,13 @@ pub fn input_listener_system(
         }
         ListenerState::ListenControllerKey => {
             // Stop listening when a bound controller key is heard.
-            if let Some(source) = listen_for_input_source(keyboard, buttons, axes, raw, gamepads) {
+            // Bindings refer to joysticks by their saved id rather than the heard runtime id.
+            let saved_source = heard_source.and_then(|s| device_map.saved_source(&s));
+            if let Some(source) = saved_source {
                 if let Some(key) = layouts.is_source_bound(&source) {
                     if let Some(ListenerConsumer::Sink(sink)) = input_listener.consumer {
                         let result = ListenerResult::KeyToSink(key, sink);
-                        event_writer.send(result);
+                        event_writer.send(result.clone());
                         input_listener.result = Some(result);
                     }
                 }
@@ -1,5 +1,23 @@
+pub mod calibration;
+#[cfg(test)]
+mod calibration_tests;
+pub mod device;
+pub mod events;
+pub mod global_keyboard;
 pub mod input;
 pub mod listener;
+pub mod mouse;
+pub mod playback;
+#[cfg(test)]
+mod playback_tests;
+pub mod recording;
+#[cfg(test)]
+mod recording_tests;
+pub mod response;
+#[cfg(test)]
+mod response_tests;
+pub mod settings;
+pub mod stats;
 
 pub mod raw_input;
 pub use raw_input::RawInputReader;
@@ -7,8 +25,14 @@ pub use raw_input::RawInputReader;
 #[cfg(target_family = "windows")]
 pub use raw_input::windows as raw_input_reader;
 
-#[cfg(target_family = "unix")]
+#[cfg(target_os = "linux")]
+pub use raw_input::linux as raw_input_reader;
+
+#[cfg(target_os = "macos")]
 pub use raw_input::macos as raw_input_reader;
 
 #[cfg(never)]
 pub mod raw_input_test;
+
+#[cfg(test)]
+mod tests;
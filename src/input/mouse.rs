@@ -0,0 +1,82 @@
+use bevy::{
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+// The pixels of mouse motion in a single frame that produce a full axis value.
+const MOUSE_MOTION_FULL_SCALE: f32 = 20.0;
+// The pixels of a pixel-based scroll event that count as a single wheel tick.
+const MOUSE_WHEEL_PIXELS_PER_TICK: f32 = 50.0;
+// The fraction of a mouse axis value that remains after a second without movement.
+const MOUSE_AXIS_DECAY_PER_SECOND: f32 = 0.0001;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MouseAxis {
+    X,
+    Y,
+}
+
+impl MouseAxis {
+    pub fn partner(self) -> MouseAxis {
+        match self {
+            MouseAxis::X => MouseAxis::Y,
+            MouseAxis::Y => MouseAxis::X,
+        }
+    }
+}
+
+// The global resource exposing mouse motion and wheel ticks as axes.
+// Each axis jumps towards the latest movement and decays back to 0.0 once the mouse stops,
+// so that short movements stay visible on a display for a few frames.
+#[derive(Debug, Default)]
+pub struct MouseInputRes {
+    // Rightwards and upwards motion are positive.
+    motion: Vec2,
+    // Scrolling up is positive.
+    wheel: f32,
+}
+
+impl MouseInputRes {
+    pub fn axis(&self, axis: MouseAxis) -> f32 {
+        match axis {
+            MouseAxis::X => self.motion.x,
+            MouseAxis::Y => self.motion.y,
+        }
+    }
+
+    pub fn wheel(&self) -> f32 {
+        self.wheel
+    }
+
+    pub fn update(&mut self, delta_seconds: f32, motion: Vec2, wheel_ticks: f32) {
+        let decay = MOUSE_AXIS_DECAY_PER_SECOND.powf(delta_seconds);
+
+        let motion = self.motion * decay + motion / MOUSE_MOTION_FULL_SCALE;
+        self.motion = motion.clamp(Vec2::splat(-1.0), Vec2::splat(1.0));
+        self.wheel = (self.wheel * decay + wheel_ticks).clamp(-1.0, 1.0);
+    }
+}
+
+pub fn update_mouse_input_system(
+    time: Res<Time>,
+    mut mouse_input: ResMut<MouseInputRes>,
+    mut motion_events: EventReader<MouseMotion>,
+    mut wheel_events: EventReader<MouseWheel>,
+) {
+    let mut motion = Vec2::ZERO;
+    for ev in motion_events.iter() {
+        // Screen coordinates grow downwards, but upwards motion should be positive.
+        motion += Vec2::new(ev.delta.x, -ev.delta.y);
+    }
+
+    let mut wheel_ticks = 0.0;
+    for ev in wheel_events.iter() {
+        wheel_ticks += match ev.unit {
+            MouseScrollUnit::Line => ev.y,
+            MouseScrollUnit::Pixel => ev.y / MOUSE_WHEEL_PIXELS_PER_TICK,
+        };
+    }
+
+    mouse_input.update(time.delta_seconds(), motion, wheel_ticks);
+}
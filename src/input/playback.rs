@@ -0,0 +1,197 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+
+use crate::{controller::layout::PlayerKey, state::AppState};
+
+use super::{
+    input::{InputSink, InputValue},
+    recording::{read_saved_sessions_dir, ExpandedSessionTick, InputSession},
+};
+
+const PLAYBACK_WINDOW_TITLE: &'static str = "Playback";
+const MIN_PLAYBACK_SPEED: f32 = 0.1;
+const MAX_PLAYBACK_SPEED: f32 = 4.0;
+
+// The global resource driving `InputSink` values from a recorded input session.
+pub struct InputPlayback {
+    // If `true`, `InputSink` values come from the loaded session instead of live input.
+    active: bool,
+    path: Option<String>,
+    session: Option<InputSession>,
+    // The session's stored ticks, expanded to the value of every key.
+    ticks: Vec<ExpandedSessionTick>,
+    // The current playback position, in seconds since the start of the session.
+    pub position: f64,
+    pub playing: bool,
+    pub speed: f32,
+    pub looping: bool,
+}
+
+impl Default for InputPlayback {
+    fn default() -> Self {
+        InputPlayback {
+            active: false,
+            path: None,
+            session: None,
+            ticks: vec![],
+            position: 0.0,
+            playing: false,
+            speed: 1.0,
+            looping: false,
+        }
+    }
+}
+
+impl InputPlayback {
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn load(&mut self, path: &str, session: InputSession) {
+        self.ticks = session.expanded_ticks();
+        self.session = Some(session);
+        self.path = Some(path.to_string());
+        self.position = 0.0;
+        self.playing = false;
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.session.as_ref().map_or(0.0, |s| s.duration)
+    }
+
+    pub fn toggle_playing(&mut self) {
+        // Restart from the beginning if playback already reached the end.
+        if !self.playing && self.position >= self.duration() {
+            self.position = 0.0;
+        }
+        self.playing = !self.playing;
+    }
+
+    // Advance the playback position by `delta` seconds of real time.
+    pub fn advance(&mut self, delta: f64) {
+        if !self.playing || self.session.is_none() {
+            return;
+        }
+
+        let duration = self.duration();
+        self.position += delta * self.speed as f64;
+
+        if self.position >= duration {
+            if self.looping && duration > 0.0 {
+                self.position %= duration;
+            } else {
+                self.position = duration;
+                self.playing = false;
+            }
+        }
+    }
+
+    // Returns the recorded value of `key` at the current playback position.
+    pub fn value_at_position(&self, key: PlayerKey) -> Option<InputValue> {
+        let session = self.session.as_ref()?;
+        let key_idx = session.keys.iter().position(|k| *k == key)?;
+
+        // Find the last stored tick at or before the playback position. Several ticks can be
+        // stored at the same time, such as a tap recorded between polls and the next poll.
+        match self
+            .ticks
+            .partition_point(|tick| tick.time <= self.position)
+        {
+            0 => None,
+            idx => self.ticks[idx - 1].values[key_idx],
+        }
+    }
+}
+
+// Write the recorded values at the current playback position to every `InputSink`.
+fn playback_update_system(
+    time: Res<Time>,
+    mut playback: ResMut<InputPlayback>,
+    mut query: Query<&mut InputSink>,
+) {
+    playback.advance(time.delta_seconds_f64());
+
+    for mut sink in query.iter_mut() {
+        for i in 0..sink.keys.len() {
+            sink.values[i] = sink.keys[i].and_then(|key| playback.value_at_position(key));
+            // Live presses would otherwise keep feeding displays that read timed presses.
+            sink.timed_presses[i].clear();
+        }
+        sink.latch_pressed();
+    }
+}
+
+fn playback_ui_system(mut egui_ctx: ResMut<EguiContext>, mut playback: ResMut<InputPlayback>) {
+    egui::Window::new(PLAYBACK_WINDOW_TITLE).show(egui_ctx.ctx_mut(), |ui| {
+        // Session selection
+        let selected_text = playback.path.clone().unwrap_or("-".to_string());
+        egui::ComboBox::new("playback_session", "Recording")
+            .selected_text(selected_text)
+            .show_ui(ui, |ui| {
+                for path in read_saved_sessions_dir().paths {
+                    if ui.selectable_label(false, path.as_str()).clicked() {
+                        match InputSession::read(&path) {
+                            Ok(session) => playback.load(&path, session),
+                            Err(e) => {
+                                println!("Error reading input session from '{}': {:?}", path, e)
+                            }
+                        }
+                    }
+                }
+            });
+
+        if playback.session.is_none() {
+            ui.label("No recording loaded.");
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            let play_text = if playback.playing { "Pause" } else { "Play" };
+            if ui.button(play_text).clicked() {
+                playback.toggle_playing();
+            }
+
+            ui.checkbox(&mut playback.looping, "Loop");
+        });
+
+        // Seek bar
+        let duration = playback.duration();
+        ui.add(
+            egui::Slider::new(&mut playback.position, 0.0..=duration)
+                .text("Position")
+                .suffix("s"),
+        );
+
+        ui.add(
+            egui::Slider::new(&mut playback.speed, MIN_PLAYBACK_SPEED..=MAX_PLAYBACK_SPEED)
+                .text("Speed")
+                .suffix("x"),
+        );
+    });
+}
+
+fn enter_playback_system(mut playback: ResMut<InputPlayback>) {
+    playback.active = true;
+}
+
+fn exit_playback_system(mut playback: ResMut<InputPlayback>) {
+    playback.active = false;
+    playback.playing = false;
+}
+
+pub fn add_playback_systems(app: &mut App, playback_state: AppState) {
+    app.insert_resource(InputPlayback::default());
+
+    // Enter playback state
+    app.add_system_set(SystemSet::on_enter(playback_state).with_system(enter_playback_system));
+
+    // Exit playback state
+    app.add_system_set(SystemSet::on_exit(playback_state).with_system(exit_playback_system));
+
+    // Update
+    app.add_system_set(
+        SystemSet::on_update(playback_state)
+            .with_system(playback_ui_system)
+            .with_system(playback_update_system),
+    );
+}
@@ -0,0 +1,122 @@
+// Tests of seeking through, looping and speeding up the playback of a recorded session.
+
+use crate::controller::layout::{ControllerKey, PlayerKey, Ps2Key};
+
+use super::{
+    input::InputValue,
+    playback::InputPlayback,
+    recording::{InputSession, SessionTick},
+};
+
+fn ps2_key(key: Ps2Key) -> PlayerKey {
+    PlayerKey::new(0, ControllerKey::Ps2(key))
+}
+
+fn cross_tick(tick: u64, time: f64, pressed: bool) -> SessionTick {
+    SessionTick {
+        tick,
+        time,
+        changes: vec![(0, Some(InputValue::Button(pressed)))],
+    }
+}
+
+// Load a 2 second session of Cross, pressed at 0.5s and released at 1.5s.
+// A tap recorded between polls is stored at 1.0s, at the same time as the tick after it.
+fn loaded_playback() -> InputPlayback {
+    let mut session = InputSession::new(0);
+    session.keys = vec![ps2_key(Ps2Key::Cross)];
+    session.num_ticks = 5;
+    session.duration = 2.0;
+    session.ticks = vec![
+        cross_tick(0, 0.0, false),
+        cross_tick(1, 0.5, true),
+        cross_tick(2, 1.0, false),
+        cross_tick(2, 1.0, true),
+        cross_tick(3, 1.5, false),
+    ];
+
+    let mut playback = InputPlayback::default();
+    playback.load("session.vrec", session);
+    playback
+}
+
+fn cross_at(playback: &InputPlayback) -> Option<InputValue> {
+    playback.value_at_position(ps2_key(Ps2Key::Cross))
+}
+
+#[test]
+fn seeking_reads_the_last_tick_at_or_before_the_position() {
+    let mut playback = loaded_playback();
+
+    for (position, pressed) in [
+        (0.0, false),
+        (0.25, false),
+        (0.5, true),
+        (0.75, true),
+        (1.0, true),
+        (1.25, true),
+        (1.5, false),
+        (2.0, false),
+    ] {
+        playback.position = position;
+        assert_eq!(
+            cross_at(&playback),
+            Some(InputValue::Button(pressed)),
+            "at {}s",
+            position
+        );
+    }
+
+    // Keys that weren't recorded have no value.
+    assert_eq!(playback.value_at_position(ps2_key(Ps2Key::Circle)), None);
+}
+
+#[test]
+fn seeking_before_the_first_tick_reads_nothing() {
+    let mut playback = loaded_playback();
+    playback.position = -1.0;
+    assert_eq!(cross_at(&playback), None);
+
+    playback.position = f64::NAN;
+    assert_eq!(cross_at(&playback), None);
+}
+
+#[test]
+fn playback_stops_at_the_end_unless_looping() {
+    let mut playback = loaded_playback();
+
+    // Nothing moves until playback starts.
+    playback.advance(1.0);
+    assert_eq!(playback.position, 0.0);
+
+    playback.toggle_playing();
+    playback.advance(1.5);
+    assert_eq!(playback.position, 1.5);
+    playback.advance(1.5);
+    assert_eq!(playback.position, 2.0);
+    assert!(!playback.playing);
+
+    // Playing again from the end restarts the session.
+    playback.toggle_playing();
+    assert_eq!(playback.position, 0.0);
+
+    playback.looping = true;
+    playback.advance(1.5);
+    playback.advance(1.0);
+    assert_eq!(playback.position, 0.5);
+    assert!(playback.playing);
+}
+
+#[test]
+fn playback_speed_scales_time() {
+    let mut playback = loaded_playback();
+    playback.toggle_playing();
+
+    playback.speed = 2.0;
+    playback.advance(0.25);
+    assert_eq!(playback.position, 0.5);
+
+    playback.speed = 0.5;
+    playback.advance(1.0);
+    assert_eq!(playback.position, 1.0);
+}
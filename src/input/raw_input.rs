@@ -1,14 +1,76 @@
-use super::input::{
-    AxisSign, HidAxisId, HidButtonId, HidHatSwitchId, HidId, InputSource, InputValue,
+use std::time::SystemTime;
+
+use super::{
+    device::DeviceIdentity,
+    input::{
+        AxisSign, HidAxisId, HidButtonId, HidHatSwitchId, HidId, InputSource, InputValue, HID_AXES,
+        HID_HAT_SWITCHES,
+    },
 };
 
+// The non-send bevy resource holding the reader of HID/DirectInput joysticks, which bevy's
+// gamepad support doesn't cover. Each platform provides its own default reader.
+pub struct RawInputRes(pub Box<dyn RawInputReader>);
+
+impl RawInputRes {
+    pub fn with_reader(reader: impl RawInputReader + 'static) -> RawInputRes {
+        RawInputRes(Box::new(reader))
+    }
+}
+
 pub trait RawInputReader {
     fn update(&mut self, id: HidId);
 
+    // Look for joysticks which were connected or disconnected since the last call.
+    // Returns `true` if the set of joysticks changed, which may also change their `HidId`s.
+    fn refresh_devices(&mut self) -> bool;
+
+    // The identities of the connected joysticks, indexed by `HidId`.
+    fn device_identities(&self) -> Vec<DeviceIdentity>;
+
+    // The axis of joystick `id` numbered by SDL with each index, or `None` for axes that can't
+    // be read as a `HidAxisId`. This is used to import SDL controller mappings.
+    fn sdl_axis_order(&self, _id: HidId) -> Vec<Option<HidAxisId>> {
+        HID_AXES.iter().copied().map(Some).collect()
+    }
+
     // Returns the `InputSource` of the first joystick event captured by the update, if such an event exists.
     fn listen(&mut self) -> Option<InputSource>;
 
+    // The presses (`true`) and releases (`false`) of joystick buttons and hat directions read
+    // since the last call, in order, with the time of each event if the backend reports one.
+    fn take_button_events(&mut self) -> Vec<(InputSource, bool, Option<SystemTime>)> {
+        vec![]
+    }
+
     fn num_joysticks(&self) -> usize;
+
+    // The joystick buttons and hat directions held down, besides the centers of resting hats.
+    fn held_buttons(&mut self) -> Vec<InputSource> {
+        let mut held = vec![];
+        let is_down = |value: Option<InputValue>| value == Some(InputValue::Button(true));
+
+        for id in 0..self.num_joysticks() {
+            let mut button_id = 0;
+            while let Some(value) = self.poll_hid_button(&id, &button_id) {
+                if is_down(Some(value)) {
+                    held.push(InputSource::HidButton(id, button_id));
+                }
+                button_id += 1;
+            }
+
+            for &hat_id in HID_HAT_SWITCHES.iter() {
+                if hat_id != HidHatSwitchId::Center
+                    && is_down(self.poll_hid_hatswitch(&id, &hat_id))
+                {
+                    held.push(InputSource::HidHatSwitch(id, hat_id));
+                }
+            }
+        }
+
+        held
+    }
+
     fn poll_hid_button(&mut self, id: &HidId, button_id: &HidButtonId) -> Option<InputValue>;
     fn poll_hid_axis(
         &mut self,
@@ -19,41 +81,67 @@ pub trait RawInputReader {
     fn poll_hid_hatswitch(&mut self, id: &HidId, hat_id: &HidHatSwitchId) -> Option<InputValue>;
 }
 
+// The presses and releases of the hat directions of joystick `id` which moved from `was_down`
+// to `is_down`, which tell whether each direction is held.
+pub fn hat_events(
+    id: HidId,
+    was_down: impl Fn(HidHatSwitchId) -> bool,
+    is_down: impl Fn(HidHatSwitchId) -> bool,
+) -> Vec<(InputSource, bool)> {
+    HID_HAT_SWITCHES
+        .iter()
+        .filter(|&&hat_id| was_down(hat_id) != is_down(hat_id))
+        .map(|&hat_id| (InputSource::HidHatSwitch(id, hat_id), is_down(hat_id)))
+        .collect()
+}
+
 #[cfg(target_os = "windows")]
 pub mod windows {
-    use std::time::Instant;
+    use std::{collections::HashMap, time::SystemTime};
 
-    use super::RawInputReader;
-    use crate::{controller::layout::ControllerLayoutsRes, input::input::*};
+    pub use super::RawInputRes;
+    use super::{hat_events, RawInputReader};
+    use crate::{
+        controller::layout::ControllerLayoutsRes,
+        input::{
+            device::{DeviceIdentity, DeviceMapRes},
+            input::*,
+        },
+    };
     use bevy::prelude::{NonSendMut, Res};
-    use multiinput::JoystickState;
+    use multiinput::{HatSwitch, JoystickState, RawEvent};
 
     // A bevy system to poll the `RawInputManager`.
     // Polling is what sync the internal state of `RawInputManager` to the current rawinput.
     pub fn poll_rawinput_system(
         mut raw_input: NonSendMut<RawInputRes>,
         controller: Res<ControllerLayoutsRes>,
+        device_map: Res<DeviceMapRes>,
     ) {
-        for (_, source) in controller.ps2.sources.iter() {
-            if let Some(id) = source.get_hid_id() {
-                raw_input.0.update(id);
-                break;
-            }
+        let hid_id = controller
+            .bindings()
+            .into_iter()
+            .flat_map(|binding| binding.sources())
+            .find_map(|source| device_map.resolve_source(source).get_hid_id());
+
+        if let Some(id) = hid_id {
+            raw_input.0.update(id);
         }
     }
 
-    // The non-send bevy resource for HID/DirectInput gamepads.
-    pub struct RawInputRes(pub RawInput);
-
     impl Default for RawInputRes {
         fn default() -> Self {
-            RawInputRes(RawInput::default())
+            RawInputRes::with_reader(RawInput::default())
         }
     }
 
     pub struct RawInput {
         manager: multiinput::RawInputManager,
         joystick_state: Option<JoystickState>,
+        // The button and hat events read since the last `take_button_events`.
+        button_events: Vec<(InputSource, bool, Option<SystemTime>)>,
+        // The hat directions held on each joystick, as of the last hat event read from it.
+        hats: HashMap<HidId, Vec<HidHatSwitchId>>,
     }
 
     impl Default for RawInput {
@@ -68,6 +156,8 @@ pub mod windows {
             RawInput {
                 manager,
                 joystick_state: None,
+                button_events: vec![],
+                hats: HashMap::new(),
             }
         }
     }
@@ -76,28 +166,91 @@ pub mod windows {
         fn get_cached_joystick_state(&self, _id: HidId) -> Option<&JoystickState> {
             self.joystick_state.as_ref()
         }
+
+        // Read the next rawinput event, keeping track of the button and hat events.
+        // Rawinput events aren't timestamped, so those happen on the frame they're logged.
+        fn read_event(&mut self) -> Option<RawEvent> {
+            let event = self.manager.get_event()?;
+
+            match &event {
+                &RawEvent::JoystickButtonEvent(id, button_id, ref state) => {
+                    let pressed = matches!(state, multiinput::State::Pressed);
+                    self.button_events
+                        .push((InputSource::HidButton(id, button_id), pressed, None));
+                }
+                &RawEvent::JoystickHatSwitchEvent(id, ref hatswitch) => {
+                    let held: Vec<HidHatSwitchId> = HID_HAT_SWITCHES
+                        .iter()
+                        .copied()
+                        .filter(|&hat_id| is_hat_down(hatswitch, hat_id))
+                        .collect();
+                    let was_held = self.hats.insert(id, held.clone()).unwrap_or_default();
+
+                    for (source, pressed) in hat_events(
+                        id,
+                        |hat_id| was_held.contains(&hat_id),
+                        |hat_id| held.contains(&hat_id),
+                    ) {
+                        self.button_events.push((source, pressed, None));
+                    }
+                }
+                _ => {}
+            }
+
+            Some(event)
+        }
+    }
+
+    // Whether the hat direction `hat_id` is held when the hat is at `hatswitch`.
+    fn is_hat_down(hatswitch: &HatSwitch, hat_id: HidHatSwitchId) -> bool {
+        use self::HidHatSwitchId::*;
+
+        match hatswitch {
+            HatSwitch::Center => hat_id == Center,
+            HatSwitch::Right => hat_id == Right,
+            HatSwitch::Left => hat_id == Left,
+            HatSwitch::Up => hat_id == Up,
+            HatSwitch::Down => hat_id == Down,
+            HatSwitch::UpRight => hat_id == Up || hat_id == Right,
+            HatSwitch::DownRight => hat_id == Down || hat_id == Right,
+            HatSwitch::DownLeft => hat_id == Down || hat_id == Left,
+            HatSwitch::UpLeft => hat_id == Up || hat_id == Left,
+        }
     }
 
     impl super::RawInputReader for RawInput {
         // Syncs the state of the `RawInputManager` based on pending rawinput events.
         fn update(&mut self, id: HidId) {
-            loop {
-                // Reading the events one by one like this has the effect of also updating
-                // the internal joystick state of `RawInputManager`.
-                if let Some(_) = self.manager.get_event() {
-                } else {
-                    break;
-                }
-            }
+            // Reading the events one by one like this has the effect of also updating
+            // the internal joystick state of `RawInputManager`.
+            while let Some(_) = self.read_event() {}
 
             self.joystick_state = self.manager.get_joystick_state(id);
         }
 
+        // `multiinput` only enumerates devices when they're registered, so joysticks
+        // connected after startup aren't seen.
+        fn refresh_devices(&mut self) -> bool {
+            false
+        }
+
+        fn device_identities(&self) -> Vec<DeviceIdentity> {
+            self.manager
+                .get_device_list()
+                .joysticks
+                .into_iter()
+                .map(|info| DeviceIdentity {
+                    serial: info.serial,
+                    ..DeviceIdentity::named(&info.name)
+                })
+                .collect()
+        }
+
         fn listen(&mut self) -> Option<InputSource> {
             use multiinput::RawEvent::*;
             use multiinput::State;
             loop {
-                if let Some(e) = self.manager.get_event() {
+                if let Some(e) = self.read_event() {
                     match e {
                         JoystickButtonEvent(id, button_id, State::Pressed) => {
                             return Some(InputSource::HidButton(id, button_id));
@@ -138,6 +291,10 @@ pub mod windows {
             None
         }
 
+        fn take_button_events(&mut self) -> Vec<(InputSource, bool, Option<SystemTime>)> {
+            std::mem::take(&mut self.button_events)
+        }
+
         // Returns the number of joysticks in the device list.
         fn num_joysticks(&self) -> usize {
             self.manager.get_device_list().joysticks.len()
@@ -145,8 +302,9 @@ pub mod windows {
 
         fn poll_hid_button(&mut self, id: &HidId, button_id: &HidButtonId) -> Option<InputValue> {
             let js = self.get_cached_joystick_state(*id)?;
+            let pressed = js.button_states.get(*button_id)?;
 
-            Some(InputValue::Button(js.button_states[*button_id]))
+            Some(InputValue::Button(*pressed))
         }
 
         // Reads the value of a `HidAxis` input source from the rawinput state.
@@ -177,44 +335,45 @@ pub mod windows {
             id: &HidId,
             hat_id: &self::HidHatSwitchId,
         ) -> Option<InputValue> {
-            use self::HidHatSwitchId::*;
-            use multiinput::HatSwitch;
-
             let js = self.get_cached_joystick_state(*id)?;
+            let hatswitch = js.hatswitch.as_ref()?;
 
-            let is_hat_id_down = match js.hatswitch.as_ref()? {
-                HatSwitch::Center => *hat_id == Center,
-                HatSwitch::Right => *hat_id == Right,
-                HatSwitch::Left => *hat_id == Left,
-                HatSwitch::Up => *hat_id == Up,
-                HatSwitch::Down => *hat_id == Down,
-                HatSwitch::UpRight => *hat_id == Up || *hat_id == Right,
-                HatSwitch::DownRight => *hat_id == Down || *hat_id == Right,
-                HatSwitch::DownLeft => *hat_id == Down || *hat_id == Left,
-                HatSwitch::UpLeft => *hat_id == Up || *hat_id == Left,
-            };
-
-            Some(InputValue::Button(is_hat_id_down))
+            Some(InputValue::Button(is_hat_down(hatswitch, *hat_id)))
         }
     }
 }
 
+#[cfg(target_os = "linux")]
+pub mod linux;
+
+#[cfg(all(test, target_os = "linux"))]
+mod linux_tests;
+
+#[cfg(test)]
+pub mod scripted;
+
 #[cfg(target_os = "macos")]
 pub mod macos {
     use super::RawInputReader;
-    use crate::input::input::*;
-    pub struct RawInputRes(pub NoopRawInput);
+    pub use super::RawInputRes;
+    use crate::input::{device::DeviceIdentity, input::*};
 
     impl Default for RawInputRes {
         fn default() -> Self {
-            RawInputRes(NoopRawInput)
+            RawInputRes::with_reader(NoopRawInput)
         }
     }
 
     pub struct NoopRawInput;
 
     impl RawInputReader for NoopRawInput {
-        fn update(&mut self) {}
+        fn update(&mut self, _id: HidId) {}
+        fn refresh_devices(&mut self) -> bool {
+            false
+        }
+        fn device_identities(&self) -> Vec<DeviceIdentity> {
+            vec![]
+        }
         fn listen(&mut self) -> Option<InputSource> {
             None
         }
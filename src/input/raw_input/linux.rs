@@ -0,0 +1,689 @@
+#[cfg(test)]
+use std::{cell::RefCell, rc::Rc};
+use std::{
+    collections::HashMap,
+    fs, io,
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+pub use super::RawInputRes;
+use super::{hat_events, RawInputReader};
+use crate::{
+    controller::layout::ControllerLayoutsRes,
+    input::{
+        device::{sdl_guid, DeviceIdentity, DeviceMapRes},
+        input::*,
+    },
+};
+use bevy::prelude::{NonSendMut, Res};
+use evdev::{AbsoluteAxisType, InputEventKind, Key};
+
+// A bevy system to poll the evdev joysticks.
+// Polling is what syncs the cached joystick state of `RawInput` to the current device state.
+pub fn poll_rawinput_system(
+    mut raw_input: NonSendMut<RawInputRes>,
+    controller: Res<ControllerLayoutsRes>,
+    device_map: Res<DeviceMapRes>,
+) {
+    let hid_id = controller
+        .bindings()
+        .into_iter()
+        .flat_map(|binding| binding.sources())
+        .find_map(|source| device_map.resolve_source(source).get_hid_id());
+
+    if let Some(id) = hid_id {
+        raw_input.0.update(id);
+    }
+}
+
+impl Default for RawInputRes {
+    fn default() -> Self {
+        RawInputRes::with_reader(RawInput::default())
+    }
+}
+
+// A single event read from an evdev device, reduced to the event types we care about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceEvent {
+    // A `EV_KEY` event with its key code and value (0 = released, 1 = pressed, 2 = repeat).
+    Key(u16, i32),
+    // A `EV_ABS` event with its axis code and raw (unnormalized) value.
+    Abs(u16, i32),
+}
+
+// The range and current value of an absolute axis, as reported by the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AxisInfo {
+    pub code: u16,
+    pub min: i32,
+    pub max: i32,
+    pub value: i32,
+}
+
+// The parts of an evdev device that `RawInput` reads from.
+// Abstracting over this lets `RawInput` be driven by a `MockEventDevice` instead of real hardware.
+pub trait EventDevice {
+    fn name(&self) -> String;
+
+    // The persistent identity of the device. Only the name is known by default.
+    fn identity(&self) -> DeviceIdentity {
+        DeviceIdentity::named(&self.name())
+    }
+
+    // The key codes supported by the device, in ascending order.
+    fn key_codes(&self) -> Vec<u16>;
+
+    // The absolute axes supported by the device.
+    fn axes(&self) -> Vec<AxisInfo>;
+
+    // Returns all events that have arrived since the last read, without blocking, along with
+    // the time the kernel reported for each.
+    fn read_events(&mut self) -> Vec<(DeviceEvent, SystemTime)>;
+}
+
+// An `EventDevice` backed by a `/dev/input/event*` node.
+pub struct EvdevDevice(evdev::Device);
+
+impl EvdevDevice {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<EvdevDevice> {
+        EvdevDevice::from_device(evdev::Device::open(path)?)
+    }
+
+    pub fn from_device(device: evdev::Device) -> io::Result<EvdevDevice> {
+        // `evdev::Device::fetch_events` blocks by default, which would stall the bevy schedule.
+        let fd = device.as_raw_fd();
+        let set_flags_result = unsafe {
+            let flags = libc::fcntl(fd, libc::F_GETFL);
+            libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK)
+        };
+
+        if set_flags_result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(EvdevDevice(device))
+    }
+}
+
+impl EventDevice for EvdevDevice {
+    fn name(&self) -> String {
+        self.0.name().unwrap_or("Unknown device").to_string()
+    }
+
+    fn identity(&self) -> DeviceIdentity {
+        let id = self.0.input_id();
+        let serial = self
+            .0
+            .unique_name()
+            .filter(|uniq| !uniq.is_empty())
+            .map(|uniq| uniq.to_string());
+
+        DeviceIdentity {
+            name: self.name(),
+            vendor: Some(id.vendor()),
+            product: Some(id.product()),
+            serial,
+            guid: Some(sdl_guid(
+                id.bus_type().0,
+                id.vendor(),
+                id.product(),
+                id.version(),
+            )),
+        }
+    }
+
+    fn key_codes(&self) -> Vec<u16> {
+        match self.0.supported_keys() {
+            Some(keys) => keys.iter().map(|key| key.code()).collect(),
+            None => vec![],
+        }
+    }
+
+    fn axes(&self) -> Vec<AxisInfo> {
+        let supported = match self.0.supported_absolute_axes() {
+            Some(supported) => supported,
+            None => return vec![],
+        };
+
+        let state = match self.0.get_abs_state() {
+            Ok(state) => state,
+            Err(_) => return vec![],
+        };
+
+        supported
+            .iter()
+            .map(|axis| {
+                let info = state[axis.0 as usize];
+                AxisInfo {
+                    code: axis.0,
+                    min: info.minimum,
+                    max: info.maximum,
+                    value: info.value,
+                }
+            })
+            .collect()
+    }
+
+    fn read_events(&mut self) -> Vec<(DeviceEvent, SystemTime)> {
+        let mut result = vec![];
+
+        // With the device in nonblocking mode, an empty event queue shows up as a `WouldBlock` error.
+        if let Ok(events) = self.0.fetch_events() {
+            for event in events {
+                let device_event = match event.kind() {
+                    InputEventKind::Key(key) => DeviceEvent::Key(key.code(), event.value()),
+                    InputEventKind::AbsAxis(axis) => DeviceEvent::Abs(axis.0, event.value()),
+                    _ => continue,
+                };
+                result.push((device_event, event.timestamp()));
+            }
+        }
+
+        result
+    }
+}
+
+// An in-memory `EventDevice` whose events are pushed by hand, for tests.
+// Clones share the same event queue, so a clone can keep feeding events to a device
+// after the original has been handed to `RawInput::from_devices`.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct MockEventDevice {
+    pub name: String,
+    pub key_codes: Vec<u16>,
+    pub axes: Vec<AxisInfo>,
+    queue: Rc<RefCell<Vec<(DeviceEvent, SystemTime)>>>,
+}
+
+#[cfg(test)]
+impl MockEventDevice {
+    // A mock gamepad with the standard face/shoulder buttons, two sticks and a d-pad hat.
+    pub fn gamepad(name: &str) -> MockEventDevice {
+        let stick_axes = [
+            AbsoluteAxisType::ABS_X,
+            AbsoluteAxisType::ABS_Y,
+            AbsoluteAxisType::ABS_RX,
+            AbsoluteAxisType::ABS_RY,
+        ];
+        let hat_axes = [AbsoluteAxisType::ABS_HAT0X, AbsoluteAxisType::ABS_HAT0Y];
+
+        let mut axes = vec![];
+        for axis in stick_axes {
+            axes.push(AxisInfo {
+                code: axis.0,
+                min: -32768,
+                max: 32767,
+                value: 0,
+            });
+        }
+        for axis in hat_axes {
+            axes.push(AxisInfo {
+                code: axis.0,
+                min: -1,
+                max: 1,
+                value: 0,
+            });
+        }
+
+        MockEventDevice {
+            name: name.to_string(),
+            key_codes: (Key::BTN_SOUTH.code()..=Key::BTN_THUMBR.code()).collect(),
+            axes,
+            queue: Default::default(),
+        }
+    }
+
+    // A mock keyboard with every key from `KEY_ESC` to `KEY_COMPOSE`.
+    pub fn keyboard(name: &str) -> MockEventDevice {
+        MockEventDevice {
+            name: name.to_string(),
+            key_codes: (Key::KEY_ESC.code()..=Key::KEY_COMPOSE.code()).collect(),
+            axes: vec![],
+            queue: Default::default(),
+        }
+    }
+
+    pub fn push(&self, event: DeviceEvent) {
+        self.push_at(event, SystemTime::now());
+    }
+
+    pub fn push_at(&self, event: DeviceEvent, time: SystemTime) {
+        self.queue.borrow_mut().push((event, time));
+    }
+}
+
+#[cfg(test)]
+impl EventDevice for MockEventDevice {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn key_codes(&self) -> Vec<u16> {
+        self.key_codes.clone()
+    }
+
+    fn axes(&self) -> Vec<AxisInfo> {
+        self.axes.clone()
+    }
+
+    fn read_events(&mut self) -> Vec<(DeviceEvent, SystemTime)> {
+        self.queue.borrow_mut().drain(..).collect()
+    }
+}
+
+// Where `RawInput` opens its devices from, which is checked for devices being added or removed.
+pub trait DeviceSource {
+    // The paths of the available devices, which change when a device is added or removed.
+    fn device_paths(&self) -> Vec<PathBuf>;
+
+    fn open_devices(&self) -> Vec<Box<dyn EventDevice>>;
+}
+
+// The `/dev/input/event*` nodes.
+pub struct EvdevDeviceSource;
+
+impl DeviceSource for EvdevDeviceSource {
+    fn device_paths(&self) -> Vec<PathBuf> {
+        list_event_paths()
+    }
+
+    // Devices we don't have permission to read are skipped by `evdev::enumerate`.
+    fn open_devices(&self) -> Vec<Box<dyn EventDevice>> {
+        let mut devices: Vec<Box<dyn EventDevice>> = vec![];
+
+        for (path, device) in evdev::enumerate() {
+            match EvdevDevice::from_device(device) {
+                Ok(device) => devices.push(Box::new(device)),
+                Err(e) => println!("Error opening evdev device {:?}: {:?}", path, e),
+            }
+        }
+
+        devices
+    }
+}
+
+// An in-memory `DeviceSource` whose devices are plugged in and unplugged by hand, for tests.
+// Clones share the same devices, like `MockEventDevice`.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct MockDeviceSource(Rc<RefCell<Vec<(PathBuf, MockEventDevice)>>>);
+
+#[cfg(test)]
+impl MockDeviceSource {
+    pub fn plug(&self, path: &str, device: MockEventDevice) {
+        let mut devices = self.0.borrow_mut();
+        devices.push((PathBuf::from(path), device));
+        devices.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+
+    pub fn unplug(&self, path: &str) {
+        self.0
+            .borrow_mut()
+            .retain(|(device_path, _)| *device_path != PathBuf::from(path));
+    }
+}
+
+#[cfg(test)]
+impl DeviceSource for MockDeviceSource {
+    fn device_paths(&self) -> Vec<PathBuf> {
+        self.0
+            .borrow()
+            .iter()
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    fn open_devices(&self) -> Vec<Box<dyn EventDevice>> {
+        self.0
+            .borrow()
+            .iter()
+            .map(|(_, device)| Box::new(device.clone()) as Box<dyn EventDevice>)
+            .collect()
+    }
+}
+
+// Returns `true` if `code` is a joystick or gamepad button (`BTN_JOYSTICK`, `BTN_GAMEPAD`,
+// or `BTN_TRIGGER_HAPPY` ranges), as opposed to a keyboard key or mouse button.
+fn is_joystick_button(code: u16) -> bool {
+    (Key::BTN_TRIGGER.code()..=Key::BTN_THUMBR.code()).contains(&code)
+        || (Key::BTN_TRIGGER_HAPPY1.code()..=Key::BTN_TRIGGER_HAPPY40.code()).contains(&code)
+}
+
+fn is_hat_axis(code: u16) -> bool {
+    code == AbsoluteAxisType::ABS_HAT0X.0 || code == AbsoluteAxisType::ABS_HAT0Y.0
+}
+
+// Whether the hat direction `hat_id` is held when the hat is at the x/y position `hat`.
+fn is_hat_down((x, y): (i32, i32), hat_id: HidHatSwitchId) -> bool {
+    use HidHatSwitchId::*;
+
+    match hat_id {
+        Center => x == 0 && y == 0,
+        Up => y < 0,
+        Down => y > 0,
+        Left => x < 0,
+        Right => x > 0,
+    }
+}
+
+// Map a raw axis value onto [-1, 1] using the axis range reported by the device.
+fn normalize_axis(value: i32, min: i32, max: i32) -> f64 {
+    if max <= min {
+        return 0.0;
+    }
+
+    let t = (value - min) as f64 / (max - min) as f64;
+    (t * 2.0 - 1.0).clamp(-1.0, 1.0)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AxisState {
+    min: i32,
+    max: i32,
+    value: f64,
+}
+
+// A joystick device together with the state accumulated from its events.
+struct Joystick {
+    device: Box<dyn EventDevice>,
+    // The key codes of the joystick's buttons, indexed by `HidButtonId`.
+    button_codes: Vec<u16>,
+    button_states: Vec<bool>,
+    // The joystick's non-hat axes, keyed by evdev axis code.
+    axes: HashMap<u16, AxisState>,
+    // The x/y position of the d-pad hat, or `None` if the joystick has no hat.
+    hat: Option<(i32, i32)>,
+}
+
+impl Joystick {
+    fn new(device: Box<dyn EventDevice>) -> Joystick {
+        let button_codes: Vec<u16> = device
+            .key_codes()
+            .into_iter()
+            .filter(|&code| is_joystick_button(code))
+            .collect();
+        let button_states = vec![false; button_codes.len()];
+
+        let mut axes = HashMap::new();
+        let mut hat = None;
+        for info in device.axes() {
+            if is_hat_axis(info.code) {
+                hat = Some((0, 0));
+            } else {
+                axes.insert(
+                    info.code,
+                    AxisState {
+                        min: info.min,
+                        max: info.max,
+                        value: normalize_axis(info.value, info.min, info.max),
+                    },
+                );
+            }
+        }
+
+        Joystick {
+            device,
+            button_codes,
+            button_states,
+            axes,
+            hat,
+        }
+    }
+
+    // Returns `true` if the device looks like a joystick or gamepad.
+    fn is_joystick(device: &dyn EventDevice) -> bool {
+        device
+            .key_codes()
+            .iter()
+            .any(|&code| is_joystick_button(code))
+    }
+
+    // Apply `event` to the joystick state, pushing the button and hat presses and releases
+    // it causes to `button_events`.
+    // Returns the `InputSource` that `event` would be heard as by the input listener, if any.
+    fn apply_event(
+        &mut self,
+        id: HidId,
+        event: DeviceEvent,
+        button_events: &mut Vec<(InputSource, bool)>,
+    ) -> Option<InputSource> {
+        match event {
+            DeviceEvent::Key(code, value) => {
+                let button_id = self.button_codes.iter().position(|&c| c == code)?;
+                let pressed = value != 0;
+                if self.button_states[button_id] != pressed {
+                    button_events.push((InputSource::HidButton(id, button_id), pressed));
+                }
+                self.button_states[button_id] = pressed;
+
+                // Ignore key repeat events, which have a value of 2.
+                if value == 1 {
+                    Some(InputSource::HidButton(id, button_id))
+                } else {
+                    None
+                }
+            }
+            DeviceEvent::Abs(code, value) if is_hat_axis(code) => {
+                let hat = self.hat.get_or_insert((0, 0));
+                let old_hat = *hat;
+                if code == AbsoluteAxisType::ABS_HAT0X.0 {
+                    hat.0 = value.signum();
+                } else {
+                    hat.1 = value.signum();
+                }
+                let new_hat = *hat;
+                button_events.extend(hat_events(
+                    id,
+                    |hat_id| is_hat_down(old_hat, hat_id),
+                    |hat_id| is_hat_down(new_hat, hat_id),
+                ));
+
+                let hatswitch = HidHatSwitchId::from_evdev_hat(code, value)?;
+                Some(InputSource::HidHatSwitch(id, hatswitch))
+            }
+            DeviceEvent::Abs(code, value) => {
+                let axis = self.axes.get_mut(&code)?;
+                axis.value = normalize_axis(value, axis.min, axis.max);
+
+                let axis_id = HidAxisId::from_evdev_axis(code)?;
+                if axis.value > MIN_LISTENABLE_AXIS_MAG {
+                    Some(InputSource::HidAxis(id, axis_id, AxisSign::Plus))
+                } else if axis.value < -MIN_LISTENABLE_AXIS_MAG {
+                    Some(InputSource::HidAxis(id, axis_id, AxisSign::Minus))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    fn axis_value(&self, axis_id: HidAxisId) -> Option<f64> {
+        self.axes
+            .iter()
+            .find(|(&code, _)| HidAxisId::from_evdev_axis(code) == Some(axis_id))
+            .map(|(_, axis)| axis.value)
+    }
+}
+
+pub struct RawInput {
+    // The connected joysticks, indexed by `HidId`.
+    joysticks: Vec<Joystick>,
+    // Where the joysticks were opened from, or `None` if they were given explicitly and
+    // shouldn't be refreshed.
+    source: Option<Box<dyn DeviceSource>>,
+    // The device paths of `source` when the joysticks were opened.
+    watched_paths: Vec<PathBuf>,
+    // The button and hat events read since the last `take_button_events`.
+    button_events: Vec<(InputSource, bool, Option<SystemTime>)>,
+}
+
+// Returns the paths of every `/dev/input/event*` node, in order.
+fn list_event_paths() -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = match fs::read_dir("/dev/input") {
+        Ok(readdir) => readdir
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .map_or(false, |name| name.starts_with("event"))
+            })
+            .collect(),
+        Err(_) => vec![],
+    };
+    paths.sort();
+    paths
+}
+
+impl Default for RawInput {
+    // Open every joystick-like device under `/dev/input`.
+    fn default() -> Self {
+        RawInput::from_source(Box::new(EvdevDeviceSource))
+    }
+}
+
+impl RawInput {
+    // Build a `RawInput` from an explicit list of devices.
+    // Devices that don't look like joysticks are ignored.
+    pub fn from_devices(devices: Vec<Box<dyn EventDevice>>) -> RawInput {
+        let joysticks = devices
+            .into_iter()
+            .filter(|device| Joystick::is_joystick(&**device))
+            .map(Joystick::new)
+            .collect();
+
+        RawInput {
+            joysticks,
+            source: None,
+            watched_paths: vec![],
+            button_events: vec![],
+        }
+    }
+
+    // Build a `RawInput` from the devices of `source`, which are reopened when it changes.
+    pub fn from_source(source: Box<dyn DeviceSource>) -> RawInput {
+        let watched_paths = source.device_paths();
+        let joysticks = RawInput::from_devices(source.open_devices()).joysticks;
+
+        RawInput {
+            joysticks,
+            source: Some(source),
+            watched_paths,
+            button_events: vec![],
+        }
+    }
+
+    // The names of the connected joysticks, indexed by `HidId`.
+    pub fn joystick_names(&self) -> Vec<String> {
+        self.joysticks.iter().map(|js| js.device.name()).collect()
+    }
+
+    // Read pending events from every joystick and apply them to the cached state.
+    // Returns the first event that the input listener would hear, if any.
+    fn read_all_events(&mut self) -> Option<InputSource> {
+        let mut heard = None;
+
+        for (id, joystick) in self.joysticks.iter_mut().enumerate() {
+            for (event, time) in joystick.device.read_events() {
+                let mut button_events = vec![];
+                let source = joystick.apply_event(id, event, &mut button_events);
+                if heard.is_none() {
+                    heard = source;
+                }
+
+                for (source, pressed) in button_events {
+                    self.button_events.push((source, pressed, Some(time)));
+                }
+            }
+        }
+
+        heard
+    }
+}
+
+impl RawInputReader for RawInput {
+    // Syncs the cached state of all joysticks based on pending evdev events.
+    // Unlike rawinput, evdev delivers events per device, so every joystick is synced
+    // regardless of `id`.
+    fn update(&mut self, _id: HidId) {
+        self.read_all_events();
+    }
+
+    // Reopen every joystick if a device was added to or removed from the device source.
+    fn refresh_devices(&mut self) -> bool {
+        let source = match self.source.as_ref() {
+            Some(source) => source,
+            None => return false,
+        };
+
+        let paths = source.device_paths();
+        if paths == self.watched_paths {
+            return false;
+        }
+
+        self.joysticks = RawInput::from_devices(source.open_devices()).joysticks;
+        self.watched_paths = paths;
+        true
+    }
+
+    fn device_identities(&self) -> Vec<DeviceIdentity> {
+        self.joysticks
+            .iter()
+            .map(|js| js.device.identity())
+            .collect()
+    }
+
+    // SDL numbers the non-hat axes of a joystick in order of their evdev axis codes.
+    fn sdl_axis_order(&self, id: HidId) -> Vec<Option<HidAxisId>> {
+        let js = match self.joysticks.get(id) {
+            Some(js) => js,
+            None => return vec![],
+        };
+
+        let mut codes: Vec<u16> = js.axes.keys().copied().collect();
+        codes.sort();
+        codes.into_iter().map(HidAxisId::from_evdev_axis).collect()
+    }
+
+    fn listen(&mut self) -> Option<InputSource> {
+        self.read_all_events()
+    }
+
+    fn take_button_events(&mut self) -> Vec<(InputSource, bool, Option<SystemTime>)> {
+        std::mem::take(&mut self.button_events)
+    }
+
+    fn num_joysticks(&self) -> usize {
+        self.joysticks.len()
+    }
+
+    fn poll_hid_button(&mut self, id: &HidId, button_id: &HidButtonId) -> Option<InputValue> {
+        let js = self.joysticks.get(*id)?;
+        let pressed = js.button_states.get(*button_id)?;
+
+        Some(InputValue::Button(*pressed))
+    }
+
+    // Reads the value of a `HidAxis` input source from the cached joystick state.
+    fn poll_hid_axis(
+        &mut self,
+        id: &HidId,
+        axis_id: &HidAxisId,
+        axis_sign: &AxisSign,
+    ) -> Option<InputValue> {
+        let js = self.joysticks.get(*id)?;
+        let value = js.axis_value(*axis_id)?;
+
+        Some(InputValue::Axis(axis_sign.clamp_f64(value)))
+    }
+
+    // Reads the value of a `HidHatSwitch` input source from the cached joystick state.
+    fn poll_hid_hatswitch(&mut self, id: &HidId, hat_id: &HidHatSwitchId) -> Option<InputValue> {
+        let js = self.joysticks.get(*id)?;
+        let hat = js.hat?;
+
+        Some(InputValue::Button(is_hat_down(hat, *hat_id)))
+    }
+}
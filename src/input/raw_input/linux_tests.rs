@@ -0,0 +1,243 @@
+// Tests of the evdev `RawInput`, driven by `MockEventDevice`s instead of real hardware.
+
+use std::time::{Duration, SystemTime};
+
+use evdev::{AbsoluteAxisType, Key};
+
+use super::{
+    linux::{AxisInfo, DeviceEvent, EventDevice, MockDeviceSource, MockEventDevice, RawInput},
+    RawInputReader,
+};
+use crate::input::input::{AxisSign, HidAxisId, HidHatSwitchId, InputSource, InputValue};
+
+fn raw_input_with(devices: &[&MockEventDevice]) -> RawInput {
+    RawInput::from_devices(
+        devices
+            .iter()
+            .map(|&device| Box::new(device.clone()) as Box<dyn EventDevice>)
+            .collect(),
+    )
+}
+
+fn axis(raw_input: &mut RawInput, axis: HidAxisId, sign: AxisSign) -> Option<InputValue> {
+    raw_input.poll_hid_axis(&0, &axis, &sign)
+}
+
+fn hat(raw_input: &mut RawInput, hat: HidHatSwitchId) -> Option<InputValue> {
+    raw_input.poll_hid_hatswitch(&0, &hat)
+}
+
+#[test]
+fn only_joysticks_are_opened() {
+    let mut keyboard = MockEventDevice::gamepad("Keyboard");
+    keyboard.key_codes = vec![Key::KEY_A.code(), Key::KEY_B.code()];
+    keyboard.axes.clear();
+    let gamepad = MockEventDevice::gamepad("Pad");
+    let raw_input = raw_input_with(&[&keyboard, &gamepad]);
+
+    assert_eq!(raw_input.num_joysticks(), 1);
+    assert_eq!(raw_input.joystick_names(), vec!["Pad".to_string()]);
+}
+
+#[test]
+fn buttons_are_numbered_in_code_order_and_repeats_are_not_heard() {
+    let gamepad = MockEventDevice::gamepad("Pad");
+    let mut raw_input = raw_input_with(&[&gamepad]);
+
+    gamepad.push(DeviceEvent::Key(Key::BTN_EAST.code(), 1));
+    assert_eq!(raw_input.listen(), Some(InputSource::HidButton(0, 1)));
+    assert_eq!(
+        raw_input.poll_hid_button(&0, &1),
+        Some(InputValue::Button(true))
+    );
+
+    gamepad.push(DeviceEvent::Key(Key::BTN_EAST.code(), 2));
+    assert_eq!(raw_input.listen(), None);
+
+    gamepad.push(DeviceEvent::Key(Key::BTN_EAST.code(), 0));
+    raw_input.update(0);
+    assert_eq!(
+        raw_input.poll_hid_button(&0, &1),
+        Some(InputValue::Button(false))
+    );
+}
+
+#[test]
+fn axes_are_normalized_to_their_range() {
+    let mut gamepad = MockEventDevice::gamepad("Pad");
+    gamepad.axes.push(AxisInfo {
+        code: AbsoluteAxisType::ABS_Z.0,
+        min: 0,
+        max: 255,
+        value: 0,
+    });
+    let mut raw_input = raw_input_with(&[&gamepad]);
+
+    // An axis resting at the bottom of its range reads as fully negative.
+    assert_eq!(
+        axis(&mut raw_input, HidAxisId::Z, AxisSign::Minus),
+        Some(InputValue::Axis(1.0))
+    );
+
+    gamepad.push(DeviceEvent::Abs(AbsoluteAxisType::ABS_X.0, 32767));
+    gamepad.push(DeviceEvent::Abs(AbsoluteAxisType::ABS_Y.0, -32768));
+    gamepad.push(DeviceEvent::Abs(AbsoluteAxisType::ABS_Z.0, 255));
+    assert_eq!(
+        raw_input.listen(),
+        Some(InputSource::HidAxis(0, HidAxisId::X, AxisSign::Plus))
+    );
+
+    assert_eq!(
+        axis(&mut raw_input, HidAxisId::X, AxisSign::Plus),
+        Some(InputValue::Axis(1.0))
+    );
+    assert_eq!(
+        axis(&mut raw_input, HidAxisId::X, AxisSign::Minus),
+        Some(InputValue::Axis(0.0))
+    );
+    assert_eq!(
+        axis(&mut raw_input, HidAxisId::Y, AxisSign::Minus),
+        Some(InputValue::Axis(1.0))
+    );
+    assert_eq!(
+        axis(&mut raw_input, HidAxisId::Z, AxisSign::Plus),
+        Some(InputValue::Axis(1.0))
+    );
+
+    // The centered value of an even-sized range is within half a step of 0.
+    gamepad.push(DeviceEvent::Abs(AbsoluteAxisType::ABS_X.0, 0));
+    raw_input.update(0);
+    match axis(&mut raw_input, HidAxisId::X, AxisSign::Plus) {
+        Some(InputValue::Axis(value)) => assert!(value < 1e-4),
+        value => panic!("unexpected axis value {:?}", value),
+    }
+
+    // Axes the device doesn't have can't be read.
+    assert_eq!(axis(&mut raw_input, HidAxisId::RZ, AxisSign::Plus), None);
+}
+
+#[test]
+fn hat_axes_map_to_hat_switches() {
+    let gamepad = MockEventDevice::gamepad("Pad");
+    let mut raw_input = raw_input_with(&[&gamepad]);
+
+    raw_input.update(0);
+    assert_eq!(
+        hat(&mut raw_input, HidHatSwitchId::Center),
+        Some(InputValue::Button(true))
+    );
+
+    gamepad.push(DeviceEvent::Abs(AbsoluteAxisType::ABS_HAT0X.0, -1));
+    assert_eq!(
+        raw_input.listen(),
+        Some(InputSource::HidHatSwitch(0, HidHatSwitchId::Left))
+    );
+
+    gamepad.push(DeviceEvent::Abs(AbsoluteAxisType::ABS_HAT0Y.0, 1));
+    assert_eq!(
+        raw_input.listen(),
+        Some(InputSource::HidHatSwitch(0, HidHatSwitchId::Down))
+    );
+
+    // Diagonals press both of their directions.
+    for (hat_id, pressed) in [
+        (HidHatSwitchId::Left, true),
+        (HidHatSwitchId::Down, true),
+        (HidHatSwitchId::Up, false),
+        (HidHatSwitchId::Right, false),
+        (HidHatSwitchId::Center, false),
+    ] {
+        assert_eq!(
+            hat(&mut raw_input, hat_id),
+            Some(InputValue::Button(pressed))
+        );
+    }
+
+    // Releasing the hat isn't heard by the listener.
+    gamepad.push(DeviceEvent::Abs(AbsoluteAxisType::ABS_HAT0X.0, 0));
+    gamepad.push(DeviceEvent::Abs(AbsoluteAxisType::ABS_HAT0Y.0, 0));
+    assert_eq!(raw_input.listen(), None);
+    assert_eq!(
+        hat(&mut raw_input, HidHatSwitchId::Center),
+        Some(InputValue::Button(true))
+    );
+}
+
+#[test]
+fn button_and_hat_events_keep_the_device_time() {
+    let gamepad = MockEventDevice::gamepad("Pad");
+    let mut raw_input = raw_input_with(&[&gamepad]);
+
+    let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(1);
+    let t1 = t0 + Duration::from_millis(10);
+    let t2 = t0 + Duration::from_millis(20);
+    gamepad.push_at(DeviceEvent::Key(Key::BTN_EAST.code(), 1), t0);
+    gamepad.push_at(DeviceEvent::Key(Key::BTN_EAST.code(), 2), t0);
+    gamepad.push_at(DeviceEvent::Abs(AbsoluteAxisType::ABS_HAT0X.0, 1), t1);
+    gamepad.push_at(DeviceEvent::Key(Key::BTN_EAST.code(), 0), t2);
+    gamepad.push_at(DeviceEvent::Abs(AbsoluteAxisType::ABS_HAT0X.0, 0), t2);
+    raw_input.update(0);
+
+    // Repeats aren't presses, and moving the hat releases the direction it leaves.
+    assert_eq!(
+        raw_input.take_button_events(),
+        vec![
+            (InputSource::HidButton(0, 1), true, Some(t0)),
+            (
+                InputSource::HidHatSwitch(0, HidHatSwitchId::Center),
+                false,
+                Some(t1)
+            ),
+            (
+                InputSource::HidHatSwitch(0, HidHatSwitchId::Right),
+                true,
+                Some(t1)
+            ),
+            (InputSource::HidButton(0, 1), false, Some(t2)),
+            (
+                InputSource::HidHatSwitch(0, HidHatSwitchId::Center),
+                true,
+                Some(t2)
+            ),
+            (
+                InputSource::HidHatSwitch(0, HidHatSwitchId::Right),
+                false,
+                Some(t2)
+            ),
+        ]
+    );
+    assert!(raw_input.take_button_events().is_empty());
+}
+
+#[test]
+fn hotplugged_joysticks_are_reopened() {
+    let source = MockDeviceSource::default();
+    source.plug("/dev/input/event3", MockEventDevice::gamepad("Pad A"));
+    let mut raw_input = RawInput::from_source(Box::new(source.clone()));
+
+    assert!(!raw_input.refresh_devices());
+    assert_eq!(raw_input.joystick_names(), vec!["Pad A".to_string()]);
+
+    // Devices are numbered in the order of their paths.
+    source.plug("/dev/input/event1", MockEventDevice::gamepad("Pad B"));
+    source.plug("/dev/input/event5", MockEventDevice::keyboard("Keyboard"));
+    assert!(raw_input.refresh_devices());
+    assert_eq!(
+        raw_input.joystick_names(),
+        vec!["Pad B".to_string(), "Pad A".to_string()]
+    );
+    assert!(!raw_input.refresh_devices());
+
+    source.unplug("/dev/input/event1");
+    assert!(raw_input.refresh_devices());
+    assert_eq!(raw_input.joystick_names(), vec!["Pad A".to_string()]);
+}
+
+#[test]
+fn explicit_devices_are_not_refreshed() {
+    let gamepad = MockEventDevice::gamepad("Pad");
+    let mut raw_input = raw_input_with(&[&gamepad]);
+
+    assert!(!raw_input.refresh_devices());
+    assert_eq!(raw_input.num_joysticks(), 1);
+}
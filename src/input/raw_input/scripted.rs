@@ -0,0 +1,193 @@
+use std::{cell::RefCell, rc::Rc, time::SystemTime};
+
+use bevy::utils::HashMap;
+
+use super::{hat_events, RawInputReader};
+use crate::input::{device::DeviceIdentity, input::*};
+
+// A change to the state of a `ScriptedRawInput` joystick.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScriptedEvent {
+    Button(HidId, HidButtonId, bool),
+    // The signed value of an axis, in [-1, 1].
+    Axis(HidId, HidAxisId, f32),
+    // The direction the hat is pushed in, or `None` if it's centered.
+    Hat(HidId, Option<HidHatSwitchId>),
+}
+
+#[derive(Debug, Default)]
+struct ScriptedJoystick {
+    identity: DeviceIdentity,
+    buttons: Vec<bool>,
+    axes: HashMap<HidAxisId, f32>,
+    hat: Option<HidHatSwitchId>,
+}
+
+#[derive(Debug, Default)]
+struct ScriptState {
+    joysticks: Vec<ScriptedJoystick>,
+    // The sources heard by `push` since the last `listen`, in order.
+    heard: Vec<InputSource>,
+    // The button and hat presses and releases pushed since the last `take_button_events`.
+    button_events: Vec<(InputSource, bool)>,
+    devices_changed: bool,
+}
+
+// A `RawInputReader` whose joysticks and their input are scripted by hand, for tests.
+// Events are applied as soon as they're pushed, so they don't depend on when the raw input
+// polling system runs. Clones share the same state, so a clone kept by a test can keep
+// scripting input after the original has been moved into a `RawInputRes`.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedRawInput(Rc<RefCell<ScriptState>>);
+
+impl ScriptedRawInput {
+    // Connect a joystick with `num_buttons` buttons and every HID axis resting at 0.0.
+    // Returns the `HidId` of the joystick.
+    pub fn connect(&self, name: &str, num_buttons: usize) -> HidId {
+        let mut state = self.0.borrow_mut();
+        state.joysticks.push(ScriptedJoystick {
+            identity: DeviceIdentity::named(name),
+            buttons: vec![false; num_buttons],
+            axes: HID_AXES.iter().map(|&axis| (axis, 0.0)).collect(),
+            hat: None,
+        });
+        state.devices_changed = true;
+        state.joysticks.len() - 1
+    }
+
+    // Disconnect joystick `id`. Like real backends, this shifts the ids of later joysticks.
+    pub fn disconnect(&self, id: HidId) {
+        let mut state = self.0.borrow_mut();
+        if id < state.joysticks.len() {
+            state.joysticks.remove(id);
+            state.devices_changed = true;
+        }
+    }
+
+    pub fn push(&self, event: ScriptedEvent) {
+        let mut state = self.0.borrow_mut();
+        let heard = match event {
+            ScriptedEvent::Button(id, button, pressed) => {
+                let source = InputSource::HidButton(id, button);
+                let button_state = match state
+                    .joysticks
+                    .get_mut(id)
+                    .and_then(|js| js.buttons.get_mut(button))
+                {
+                    Some(button_state) => button_state,
+                    None => return,
+                };
+                let changed = *button_state != pressed;
+                *button_state = pressed;
+
+                if changed {
+                    state.button_events.push((source, pressed));
+                }
+                Some(source).filter(|_| pressed)
+            }
+            ScriptedEvent::Axis(id, axis, value) => {
+                match state.joysticks.get_mut(id) {
+                    Some(js) => js.axes.insert(axis, value.clamp(-1.0, 1.0)),
+                    None => return,
+                };
+
+                if value > MIN_LISTENABLE_AXIS_MAG as f32 {
+                    Some(InputSource::HidAxis(id, axis, AxisSign::Plus))
+                } else if value < -MIN_LISTENABLE_AXIS_MAG as f32 {
+                    Some(InputSource::HidAxis(id, axis, AxisSign::Minus))
+                } else {
+                    None
+                }
+            }
+            ScriptedEvent::Hat(id, hat) => {
+                let old_hat = match state.joysticks.get_mut(id) {
+                    Some(js) => std::mem::replace(&mut js.hat, hat),
+                    None => return,
+                };
+                let is_down = |hat: Option<HidHatSwitchId>, hat_id: HidHatSwitchId| {
+                    hat.map_or(hat_id == HidHatSwitchId::Center, |hat| hat == hat_id)
+                };
+                state.button_events.extend(hat_events(
+                    id,
+                    |hat_id| is_down(old_hat, hat_id),
+                    |hat_id| is_down(hat, hat_id),
+                ));
+                hat.map(|hat| InputSource::HidHatSwitch(id, hat))
+            }
+        };
+
+        if let Some(source) = heard {
+            state.heard.push(source);
+        }
+    }
+}
+
+impl RawInputReader for ScriptedRawInput {
+    fn update(&mut self, _id: HidId) {}
+
+    fn refresh_devices(&mut self) -> bool {
+        std::mem::take(&mut self.0.borrow_mut().devices_changed)
+    }
+
+    fn device_identities(&self) -> Vec<DeviceIdentity> {
+        self.0
+            .borrow()
+            .joysticks
+            .iter()
+            .map(|js| js.identity.clone())
+            .collect()
+    }
+
+    // Returns the first source heard since the last call, like the evdev backend.
+    fn listen(&mut self) -> Option<InputSource> {
+        let mut state = self.0.borrow_mut();
+        let heard = state.heard.first().copied();
+        state.heard.clear();
+        heard
+    }
+
+    // Scripted events are untimed, so they happen whenever they're read.
+    fn take_button_events(&mut self) -> Vec<(InputSource, bool, Option<SystemTime>)> {
+        let mut state = self.0.borrow_mut();
+        state
+            .button_events
+            .drain(..)
+            .map(|(source, pressed)| (source, pressed, None))
+            .collect()
+    }
+
+    fn num_joysticks(&self) -> usize {
+        self.0.borrow().joysticks.len()
+    }
+
+    fn poll_hid_button(&mut self, id: &HidId, button_id: &HidButtonId) -> Option<InputValue> {
+        let state = self.0.borrow();
+        let pressed = state.joysticks.get(*id)?.buttons.get(*button_id)?;
+
+        Some(InputValue::Button(*pressed))
+    }
+
+    fn poll_hid_axis(
+        &mut self,
+        id: &HidId,
+        axis_id: &HidAxisId,
+        sign: &AxisSign,
+    ) -> Option<InputValue> {
+        let state = self.0.borrow();
+        let value = state.joysticks.get(*id)?.axes.get(axis_id)?;
+
+        Some(InputValue::Axis(sign.clamp_f32(*value)))
+    }
+
+    fn poll_hid_hatswitch(&mut self, id: &HidId, hat_id: &HidHatSwitchId) -> Option<InputValue> {
+        let state = self.0.borrow();
+        let hat = state.joysticks.get(*id)?.hat;
+
+        let is_hat_id_down = match hat {
+            Some(hat) => hat == *hat_id,
+            None => *hat_id == HidHatSwitchId::Center,
+        };
+
+        Some(InputValue::Button(is_hat_id_down))
+    }
+}
@@ -0,0 +1,416 @@
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{controller::layout::PlayerKey, util::write_to_file, VERSION};
+
+use super::{
+    input::{InputSink, InputValue},
+    settings::{input_time, ManualInputClock},
+};
+
+pub const RECORDINGS_DIR_PATH: &'static str = "recordings";
+pub const SESSION_FILE_EXTENSION: &'static str = "vrec";
+const RECORD_HOTKEY: KeyCode = KeyCode::F9;
+
+// The values of every recorded controller key which changed during a single tick.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionTick {
+    // The index of this tick, counting from the start of the session.
+    #[serde(rename = "n")]
+    pub tick: u64,
+    // Seconds since the start of the session.
+    #[serde(rename = "t")]
+    pub time: f64,
+    // Pairs of an index into the session's `keys` and the new value of that key.
+    #[serde(rename = "c")]
+    pub changes: Vec<(usize, Option<InputValue>)>,
+}
+
+// A recorded input session.
+// Only ticks where some key's value changed are stored, so that a long session of mostly
+// idle input stays small on disk.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputSession {
+    pub version: String,
+    // Unix time in seconds at which the session started.
+    pub started_at: u64,
+    // The total number of ticks recorded, including ticks where nothing changed.
+    pub num_ticks: u64,
+    // The length of the session in seconds.
+    pub duration: f64,
+    // The player keys recorded during the session.
+    pub keys: Vec<PlayerKey>,
+    pub ticks: Vec<SessionTick>,
+}
+
+// A single tick of an exported session, with the value of every key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpandedSessionTick {
+    pub tick: u64,
+    pub time: f64,
+    pub values: Vec<Option<InputValue>>,
+}
+
+// The human-readable JSON export format of a session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpandedInputSession {
+    pub version: String,
+    pub started_at: u64,
+    pub duration: f64,
+    pub keys: Vec<String>,
+    pub ticks: Vec<ExpandedSessionTick>,
+}
+
+impl InputSession {
+    pub fn new(started_at: u64) -> InputSession {
+        InputSession {
+            version: VERSION.to_string(),
+            started_at,
+            num_ticks: 0,
+            duration: 0.0,
+            keys: vec![],
+            ticks: vec![],
+        }
+    }
+
+    // Returns the value of every key after each stored tick.
+    pub fn expanded_ticks(&self) -> Vec<ExpandedSessionTick> {
+        let mut values = vec![None; self.keys.len()];
+        let mut result = vec![];
+
+        for tick in self.ticks.iter() {
+            for &(idx, value) in tick.changes.iter() {
+                values[idx] = value;
+            }
+
+            result.push(ExpandedSessionTick {
+                tick: tick.tick,
+                time: tick.time,
+                values: values.clone(),
+            });
+        }
+
+        result
+    }
+
+    pub fn read(path: &str) -> Result<InputSession, Box<dyn Error>> {
+        let reader = BufReader::new(File::open(path)?);
+        let session = serde_json::from_reader(reader)?;
+        Ok(session)
+    }
+
+    // Write the session in its compact form.
+    pub fn write(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, self)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn export_json(&self, path: &str) {
+        let expanded = ExpandedInputSession {
+            version: self.version.clone(),
+            started_at: self.started_at,
+            duration: self.duration,
+            keys: self.keys.iter().map(|key| key.to_string()).collect(),
+            ticks: self.expanded_ticks(),
+        };
+
+        write_to_file(&expanded, path);
+    }
+
+    // Write one row per stored tick, with one column per key.
+    // Buttons are written as 0/1, axes as their value, and unbound keys as an empty cell.
+    pub fn export_csv(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        let mut header = vec!["tick".to_string(), "time".to_string()];
+        for key in self.keys.iter() {
+            header.push(csv_escape(&key.to_string()));
+        }
+        writeln!(writer, "{}", header.join(","))?;
+
+        for tick in self.expanded_ticks() {
+            let mut row = vec![tick.tick.to_string(), format!("{:.4}", tick.time)];
+            for value in tick.values {
+                row.push(match value {
+                    Some(InputValue::Button(pressed)) => (pressed as u8).to_string(),
+                    Some(InputValue::Axis(v)) => format!("{:.4}", v),
+                    None => "".to_string(),
+                });
+            }
+            writeln!(writer, "{}", row.join(","))?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+pub fn csv_escape(s: &str) -> String {
+    if s.contains(',') || s.contains('"') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+// The global resource recording the input values resolved by `resolve_input_sinks_system`.
+#[derive(Debug, Default)]
+pub struct InputRecorder {
+    session: Option<InputSession>,
+    // The value of `Time::seconds_since_startup` when the session started.
+    start_time: f64,
+    // The most recently recorded value of each key in the session.
+    last_values: Vec<Option<InputValue>>,
+}
+
+impl InputRecorder {
+    pub fn is_recording(&self) -> bool {
+        self.session.is_some()
+    }
+
+    pub fn start(&mut self, now: f64) {
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        self.session = Some(InputSession::new(started_at));
+        self.start_time = now;
+        self.last_values = vec![];
+    }
+
+    pub fn stop(&mut self) -> Option<InputSession> {
+        self.last_values = vec![];
+        self.session.take()
+    }
+
+    // Returns the index of `key` in the session, giving keys that weren't seen before
+    // their own column.
+    fn key_index(
+        session: &mut InputSession,
+        last_values: &mut Vec<Option<InputValue>>,
+        key: PlayerKey,
+    ) -> usize {
+        match session.keys.iter().position(|k| *k == key) {
+            Some(idx) => idx,
+            None => {
+                session.keys.push(key);
+                last_values.push(None);
+                session.keys.len() - 1
+            }
+        }
+    }
+
+    // Record button presses and releases which happened since the last tick, each at its
+    // own time, so that taps shorter than a tick are kept. `presses` must be sorted by time.
+    pub fn record_presses(&mut self, presses: &[(PlayerKey, f64, Option<InputValue>)]) {
+        let session = match self.session.as_mut() {
+            Some(session) if session.num_ticks > 0 => session,
+            _ => return,
+        };
+
+        for &(key, now, value) in presses {
+            let idx = Self::key_index(session, &mut self.last_values, key);
+            if self.last_values[idx] != value {
+                session.ticks.push(SessionTick {
+                    tick: session.num_ticks,
+                    time: now - self.start_time,
+                    changes: vec![(idx, value)],
+                });
+                self.last_values[idx] = value;
+            }
+        }
+    }
+
+    // Record the values of `keys` for the current tick.
+    pub fn record_tick(&mut self, now: f64, keys: &[(PlayerKey, Option<InputValue>)]) {
+        let session = match self.session.as_mut() {
+            Some(session) => session,
+            None => return,
+        };
+
+        let time = now - self.start_time;
+        let mut changes = vec![];
+
+        for &(key, value) in keys {
+            let idx = Self::key_index(session, &mut self.last_values, key);
+
+            // Always store the first tick so that playback starts from the recorded state.
+            if self.last_values[idx] != value || session.num_ticks == 0 {
+                changes.push((idx, value));
+                self.last_values[idx] = value;
+            }
+        }
+
+        if !changes.is_empty() {
+            session.ticks.push(SessionTick {
+                tick: session.num_ticks,
+                time,
+                changes,
+            });
+        }
+
+        session.num_ticks += 1;
+        session.duration = time;
+    }
+}
+
+// Record the value of every player key resolved by `resolve_input_sinks_system`.
+pub fn record_input_system(
+    time: Res<Time>,
+    clock: Option<Res<ManualInputClock>>,
+    mut recorder: ResMut<InputRecorder>,
+    query: Query<&InputSink>,
+) {
+    if !recorder.is_recording() {
+        return;
+    }
+
+    // Collect each key once, even if several sinks listen to it.
+    let mut keys: Vec<(PlayerKey, Option<InputValue>)> = vec![];
+    let mut presses: Vec<(PlayerKey, f64, Option<InputValue>)> = vec![];
+    for sink in query.iter() {
+        for i in 0..sink.keys.len() {
+            let key = match sink.keys[i] {
+                Some(key) if !keys.iter().any(|(k, _)| *k == key) => key,
+                _ => continue,
+            };
+
+            // A button tapped between polls is held over as pressed for one poll, but its
+            // recorded presses already cover the tap, so record its state after them instead.
+            let mut value = sink.values[i];
+            if let (Some(InputValue::Button(_)), Some(last)) = (value, sink.timed_presses[i].last())
+            {
+                value = Some(InputValue::Button(last.pressed));
+            }
+
+            keys.push((key, value));
+            for press in sink.timed_presses[i].iter() {
+                presses.push((key, press.time, Some(InputValue::Button(press.pressed))));
+            }
+        }
+    }
+    presses.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+    recorder.record_presses(&presses);
+    recorder.record_tick(input_time(&time, clock.as_deref()), &keys);
+}
+
+pub struct RequestToggleRecording;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionExportFormat {
+    Json,
+    Csv,
+}
+
+pub struct RequestExportSession(pub String, pub SessionExportFormat);
+
+#[derive(Debug)]
+pub struct SavedInputSessions {
+    pub paths: Vec<String>,
+}
+
+pub fn read_saved_sessions_dir() -> SavedInputSessions {
+    let sessions_dir = Path::new(RECORDINGS_DIR_PATH);
+    if !sessions_dir.is_dir() {
+        fs::create_dir(RECORDINGS_DIR_PATH);
+    }
+
+    let mut paths: Vec<String> = vec![];
+    if let Ok(readdir) = fs::read_dir(RECORDINGS_DIR_PATH) {
+        for entry in readdir {
+            if let Ok(de) = entry {
+                let path = de.path();
+                if path
+                    .extension()
+                    .map_or(false, |ext| ext == SESSION_FILE_EXTENSION)
+                {
+                    paths.push(path.to_str().unwrap().to_string());
+                }
+            }
+        }
+    }
+
+    SavedInputSessions { paths }
+}
+
+fn handle_request_toggle_recording(
+    time: Res<Time>,
+    clock: Option<Res<ManualInputClock>>,
+    mut recorder: ResMut<InputRecorder>,
+    mut event_reader: EventReader<RequestToggleRecording>,
+) {
+    for _ in event_reader.iter() {
+        if !recorder.is_recording() {
+            recorder.start(input_time(&time, clock.as_deref()));
+            continue;
+        }
+
+        if let Some(session) = recorder.stop() {
+            read_saved_sessions_dir();
+            let path = format!(
+                "{}/session-{}.{}",
+                RECORDINGS_DIR_PATH, session.started_at, SESSION_FILE_EXTENSION
+            );
+
+            if let Err(e) = session.write(&path) {
+                println!("Error writing input session to '{}': {:?}", path, e);
+            }
+        }
+    }
+}
+
+fn handle_request_export_session(mut event_reader: EventReader<RequestExportSession>) {
+    for RequestExportSession(path, format) in event_reader.iter() {
+        let session = match InputSession::read(path) {
+            Ok(session) => session,
+            Err(e) => {
+                println!("Error reading input session from '{}': {:?}", path, e);
+                continue;
+            }
+        };
+
+        match format {
+            SessionExportFormat::Json => {
+                let json_path = Path::new(path).with_extension("json");
+                session.export_json(json_path.to_str().unwrap());
+            }
+            SessionExportFormat::Csv => {
+                let csv_path = Path::new(path).with_extension("csv");
+                if let Err(e) = session.export_csv(csv_path.to_str().unwrap()) {
+                    println!("Error exporting input session to {:?}: {:?}", csv_path, e);
+                }
+            }
+        }
+    }
+}
+
+fn recording_hotkey_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut event_writer: EventWriter<RequestToggleRecording>,
+) {
+    if keyboard_input.just_pressed(RECORD_HOTKEY) {
+        event_writer.send(RequestToggleRecording);
+    }
+}
+
+pub fn add_recording_systems(app: &mut App) {
+    app.insert_resource(InputRecorder::default());
+
+    app.add_event::<RequestToggleRecording>();
+    app.add_system(handle_request_toggle_recording);
+    app.add_system(recording_hotkey_system);
+
+    app.add_event::<RequestExportSession>();
+    app.add_system(handle_request_export_session);
+}
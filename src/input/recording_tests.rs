@@ -0,0 +1,161 @@
+// Tests of encoding recorded sessions and exporting them as JSON and CSV.
+
+use std::{env, fs, process};
+
+use crate::{
+    controller::layout::{ControllerKey, PlayerKey, Ps2Key},
+    util::read_from_file,
+    VERSION,
+};
+
+use super::{
+    input::InputValue,
+    recording::{
+        csv_escape, ExpandedInputSession, ExpandedSessionTick, InputRecorder, InputSession,
+        SessionTick,
+    },
+};
+
+fn ps2_key(key: Ps2Key) -> PlayerKey {
+    PlayerKey::new(0, ControllerKey::Ps2(key))
+}
+
+// A path in the temporary directory which no other test process writes to.
+fn temp_path(name: &str) -> String {
+    let path = env::temp_dir().join(format!("vert-{}-{}", process::id(), name));
+    path.to_str().unwrap().to_string()
+}
+
+// Record a session where Cross is tapped between two ticks and L2 is pressed halfway.
+// The times are exact in binary, so that they survive the round trip unchanged.
+fn recorded_session() -> InputSession {
+    let cross = ps2_key(Ps2Key::Cross);
+    let l2 = ps2_key(Ps2Key::L2);
+    let released = Some(InputValue::Button(false));
+
+    let mut recorder = InputRecorder::default();
+    recorder.start(1.0);
+    recorder.record_tick(1.0, &[(cross, released), (l2, Some(InputValue::Axis(0.0)))]);
+    recorder.record_presses(&[
+        (cross, 1.0625, Some(InputValue::Button(true))),
+        (cross, 1.125, released),
+    ]);
+    recorder.record_tick(
+        1.25,
+        &[(cross, released), (l2, Some(InputValue::Axis(0.5)))],
+    );
+    recorder.record_tick(1.5, &[(cross, released), (l2, Some(InputValue::Axis(0.5)))]);
+
+    recorder.stop().unwrap()
+}
+
+#[test]
+fn only_changes_are_stored() {
+    let session = recorded_session();
+    let released = Some(InputValue::Button(false));
+
+    assert_eq!(
+        session.keys,
+        vec![ps2_key(Ps2Key::Cross), ps2_key(Ps2Key::L2)]
+    );
+    assert_eq!(session.num_ticks, 3);
+    assert_eq!(session.duration, 0.5);
+    assert_eq!(
+        session.ticks,
+        vec![
+            SessionTick {
+                tick: 0,
+                time: 0.0,
+                changes: vec![(0, released), (1, Some(InputValue::Axis(0.0)))],
+            },
+            SessionTick {
+                tick: 1,
+                time: 0.0625,
+                changes: vec![(0, Some(InputValue::Button(true)))],
+            },
+            SessionTick {
+                tick: 1,
+                time: 0.125,
+                changes: vec![(0, released)],
+            },
+            SessionTick {
+                tick: 1,
+                time: 0.25,
+                changes: vec![(1, Some(InputValue::Axis(0.5)))],
+            },
+        ]
+    );
+}
+
+#[test]
+fn sessions_round_trip_through_files() {
+    let session = recorded_session();
+    let path = temp_path("round-trip.vrec");
+
+    session.write(&path).unwrap();
+    let read = InputSession::read(&path);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(read.unwrap(), session);
+}
+
+#[test]
+fn sessions_export_every_key_at_every_tick() {
+    let session = recorded_session();
+    let button = |pressed| Some(InputValue::Button(pressed));
+    let axis = |value| Some(InputValue::Axis(value));
+    let expanded_tick = |tick, time, values| ExpandedSessionTick { tick, time, values };
+
+    let ticks = vec![
+        expanded_tick(0, 0.0, vec![button(false), axis(0.0)]),
+        expanded_tick(1, 0.0625, vec![button(true), axis(0.0)]),
+        expanded_tick(1, 0.125, vec![button(false), axis(0.0)]),
+        expanded_tick(1, 0.25, vec![button(false), axis(0.5)]),
+    ];
+    assert_eq!(session.expanded_ticks(), ticks);
+
+    let json_path = temp_path("export.json");
+    session.export_json(&json_path);
+    let exported = read_from_file::<ExpandedInputSession>(&json_path);
+    fs::remove_file(&json_path).unwrap();
+
+    assert_eq!(
+        exported.unwrap(),
+        ExpandedInputSession {
+            version: VERSION.to_string(),
+            started_at: session.started_at,
+            duration: 0.5,
+            keys: session.keys.iter().map(|key| key.to_string()).collect(),
+            ticks,
+        }
+    );
+}
+
+#[test]
+fn sessions_export_to_csv() {
+    let session = recorded_session();
+    let path = temp_path("export.csv");
+
+    session.export_csv(&path).unwrap();
+    let csv = fs::read_to_string(&path);
+    fs::remove_file(&path).unwrap();
+
+    let header = format!(
+        "tick,time,{},{}",
+        csv_escape(&session.keys[0].to_string()),
+        csv_escape(&session.keys[1].to_string())
+    );
+    assert_eq!(
+        csv.unwrap().lines().collect::<Vec<&str>>(),
+        vec![
+            header.as_str(),
+            "0,0.0000,0,0.0000",
+            "1,0.0625,1,0.0000",
+            "1,0.1250,0,0.0000",
+            "1,0.2500,0,0.5000",
+        ]
+    );
+
+    assert_eq!(csv_escape("P1 Cross"), "P1 Cross");
+    assert_eq!(csv_escape("a,\"b\""), "\"a,\"\"b\"\"\"");
+}
@@ -0,0 +1,198 @@
+use std::cmp::Ordering;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::input::{AxisSign, HidAxisId};
+
+// The default axis magnitudes at which an axis presses and releases a button-type key.
+pub const DEFAULT_PRESS_THRESHOLD: f32 = 0.5;
+pub const DEFAULT_RELEASE_THRESHOLD: f32 = 0.4;
+
+// How the inner and outer deadzones of an axis are measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DeadzoneMode {
+    // Each axis is measured on its own, which produces a cross-shaped deadzone on sticks.
+    Axial,
+    // The distance of the whole stick from center is measured, which produces a circular deadzone.
+    Radial,
+}
+
+impl DeadzoneMode {
+    pub const ALL: [DeadzoneMode; 2] = [DeadzoneMode::Axial, DeadzoneMode::Radial];
+
+    pub fn to_string(self) -> String {
+        match self {
+            DeadzoneMode::Axial => "Axial".to_string(),
+            DeadzoneMode::Radial => "Radial".to_string(),
+        }
+    }
+}
+
+// The mapping from an axis magnitude (after deadzones) to the displayed magnitude.
+// Every curve maps 0.0 to 0.0 and 1.0 to 1.0.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ResponseCurve {
+    Linear,
+    // Raise the magnitude to the given power.
+    Exponential(f32),
+    // Linearly interpolate between the given (input, output) points.
+    // The points (0, 0) and (1, 1) are always implicitly included.
+    Custom(Vec<(f32, f32)>),
+}
+
+impl Default for ResponseCurve {
+    fn default() -> Self {
+        ResponseCurve::Linear
+    }
+}
+
+impl ResponseCurve {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ResponseCurve::Linear => "Linear",
+            ResponseCurve::Exponential(_) => "Exponential",
+            ResponseCurve::Custom(_) => "Custom",
+        }
+    }
+
+    // Map a magnitude in [0, 1] through the curve.
+    pub fn apply(&self, value: f32) -> f32 {
+        match self {
+            ResponseCurve::Linear => value,
+            ResponseCurve::Exponential(exp) => value.powf(*exp),
+            ResponseCurve::Custom(points) => {
+                // Points written by hand in the layouts file may be out of order.
+                let mut points = points.clone();
+                points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+                let mut prev = (0.0, 0.0);
+                for &point in points.iter().chain(std::iter::once(&(1.0, 1.0))) {
+                    if value <= point.0 {
+                        let width = point.0 - prev.0;
+                        if width <= 0.0 {
+                            return point.1;
+                        }
+                        let t = (value - prev.0) / width;
+                        return prev.1 + (point.1 - prev.1) * t;
+                    }
+                    prev = point;
+                }
+                1.0
+            }
+        }
+    }
+}
+
+// Per-binding settings applied to a polled input value before it reaches an `InputSink`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputResponse {
+    // Axis magnitudes below this are treated as 0.0.
+    pub inner_deadzone: f32,
+    // Axis magnitudes within this distance of 1.0 are treated as 1.0.
+    pub outer_deadzone: f32,
+    pub deadzone_mode: DeadzoneMode,
+    // Flip the direction of an axis, or the pressed state of a button.
+    pub invert: bool,
+    pub curve: ResponseCurve,
+    // An axis drives a button-type key once its magnitude reaches `press_threshold`,
+    // and keeps it pressed until its magnitude falls below `release_threshold`.
+    pub press_threshold: f32,
+    pub release_threshold: f32,
+}
+
+impl Default for InputResponse {
+    fn default() -> Self {
+        InputResponse {
+            inner_deadzone: 0.0,
+            outer_deadzone: 0.0,
+            deadzone_mode: DeadzoneMode::Axial,
+            invert: false,
+            curve: ResponseCurve::Linear,
+            press_threshold: DEFAULT_PRESS_THRESHOLD,
+            release_threshold: DEFAULT_RELEASE_THRESHOLD,
+        }
+    }
+}
+
+impl InputResponse {
+    pub fn is_default(&self) -> bool {
+        *self == InputResponse::default()
+    }
+
+    // Rescale a magnitude so that the inner deadzone maps to 0.0 and the outer deadzone to 1.0.
+    fn apply_deadzones(&self, magnitude: f32) -> f32 {
+        let min = self.inner_deadzone;
+        let max = 1.0 - self.outer_deadzone;
+
+        if magnitude <= min {
+            0.0
+        } else if magnitude >= max || max <= min {
+            1.0
+        } else {
+            (magnitude - min) / (max - min)
+        }
+    }
+
+    // Compute the value of the half-axis given by `sign` from the raw values of an axis
+    // and the other axis on the same stick (which is only used by radial deadzones).
+    pub fn apply_axis(&self, value: f32, partner_value: f32, sign: AxisSign) -> f32 {
+        let value = if self.invert { -value } else { value };
+
+        let value = match self.deadzone_mode {
+            DeadzoneMode::Axial => value.signum() * self.apply_deadzones(value.abs()),
+            DeadzoneMode::Radial => {
+                let magnitude = Vec2::new(value, partner_value).length();
+                if magnitude > 0.0 {
+                    value * self.apply_deadzones(magnitude.min(1.0)) / magnitude
+                } else {
+                    0.0
+                }
+            }
+        };
+
+        let value = value.signum() * self.curve.apply(value.abs().min(1.0));
+        sign.clamp_f32(value)
+    }
+
+    pub fn apply_button(&self, pressed: bool) -> bool {
+        pressed != self.invert
+    }
+
+    // Decide whether an axis magnitude presses a button-type key, given whether it was
+    // pressed on the previous update. The gap between the two thresholds keeps an axis
+    // resting near a single threshold from flickering between pressed and released.
+    pub fn apply_threshold(&self, magnitude: f32, was_pressed: bool) -> bool {
+        if was_pressed {
+            magnitude >= self.release_threshold.min(self.press_threshold)
+        } else {
+            magnitude >= self.press_threshold
+        }
+    }
+}
+
+// Returns the other axis on the same stick as `axis`, if there is one.
+pub fn partner_gamepad_axis(axis: GamepadAxisType) -> Option<GamepadAxisType> {
+    match axis {
+        GamepadAxisType::LeftStickX => Some(GamepadAxisType::LeftStickY),
+        GamepadAxisType::LeftStickY => Some(GamepadAxisType::LeftStickX),
+        GamepadAxisType::RightStickX => Some(GamepadAxisType::RightStickY),
+        GamepadAxisType::RightStickY => Some(GamepadAxisType::RightStickX),
+        GamepadAxisType::DPadX => Some(GamepadAxisType::DPadY),
+        GamepadAxisType::DPadY => Some(GamepadAxisType::DPadX),
+        _ => None,
+    }
+}
+
+// Returns the other axis on the same stick as `axis`, if there is one.
+// `Z` and `RZ` are often separate triggers rather than a stick, so they have no partner.
+pub fn partner_hid_axis(axis: HidAxisId) -> Option<HidAxisId> {
+    match axis {
+        HidAxisId::X => Some(HidAxisId::Y),
+        HidAxisId::Y => Some(HidAxisId::X),
+        HidAxisId::RX => Some(HidAxisId::RY),
+        HidAxisId::RY => Some(HidAxisId::RX),
+        _ => None,
+    }
+}
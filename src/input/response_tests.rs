@@ -0,0 +1,159 @@
+// Tests of the deadzones, curves and thresholds applied to polled values by `InputResponse`.
+
+use super::{
+    input::AxisSign,
+    response::{DeadzoneMode, InputResponse, ResponseCurve},
+};
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() < 1e-5,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
+
+fn with_deadzones(inner_deadzone: f32, outer_deadzone: f32, mode: DeadzoneMode) -> InputResponse {
+    InputResponse {
+        inner_deadzone,
+        outer_deadzone,
+        deadzone_mode: mode,
+        ..Default::default()
+    }
+}
+
+fn with_curve(curve: ResponseCurve) -> InputResponse {
+    InputResponse {
+        curve,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn axial_deadzones_measure_each_axis_alone() {
+    let response = with_deadzones(0.5, 0.0, DeadzoneMode::Axial);
+
+    // The partner axis doesn't pull an axis out of its deadzone.
+    assert_close(response.apply_axis(0.25, 0.9, AxisSign::Plus), 0.0);
+    assert_close(response.apply_axis(0.6, 0.8, AxisSign::Plus), 0.2);
+    assert_close(response.apply_axis(-0.75, 0.0, AxisSign::Minus), 0.5);
+    assert_close(response.apply_axis(-0.75, 0.0, AxisSign::Plus), 0.0);
+}
+
+#[test]
+fn radial_deadzones_measure_the_whole_stick() {
+    let response = with_deadzones(0.5, 0.0, DeadzoneMode::Radial);
+
+    assert_close(response.apply_axis(0.3, 0.3, AxisSign::Plus), 0.0);
+    assert_close(response.apply_axis(0.0, 0.0, AxisSign::Plus), 0.0);
+
+    // An axis inside its own deadzone moves once the stick as a whole is out of it.
+    let magnitude = 0.25f32.hypot(0.9);
+    assert_close(
+        response.apply_axis(0.25, 0.9, AxisSign::Plus),
+        0.25 * (magnitude - 0.5) / 0.5 / magnitude,
+    );
+    assert_close(response.apply_axis(-0.6, 0.8, AxisSign::Minus), 0.6);
+
+    // A stick past the edge of its range is scaled back onto it.
+    let diagonal = response.apply_axis(0.8, 0.8, AxisSign::Plus);
+    assert_close(diagonal, 0.8 / 0.8f32.hypot(0.8));
+}
+
+#[test]
+fn outer_deadzones_reach_the_full_range_early() {
+    let response = with_deadzones(0.0, 0.25, DeadzoneMode::Axial);
+    assert_close(response.apply_axis(0.375, 0.0, AxisSign::Plus), 0.5);
+    assert_close(response.apply_axis(0.75, 0.0, AxisSign::Plus), 1.0);
+    assert_close(response.apply_axis(0.9, 0.0, AxisSign::Plus), 1.0);
+
+    // Both deadzones together rescale the range between them.
+    let response = with_deadzones(0.25, 0.25, DeadzoneMode::Axial);
+    assert_close(response.apply_axis(0.5, 0.0, AxisSign::Plus), 0.5);
+
+    // Deadzones which overlap leave nothing between fully released and fully pressed.
+    let response = with_deadzones(0.6, 0.6, DeadzoneMode::Axial);
+    assert_close(response.apply_axis(0.5, 0.0, AxisSign::Plus), 0.0);
+    assert_close(response.apply_axis(0.7, 0.0, AxisSign::Plus), 1.0);
+}
+
+#[test]
+fn exponential_curves_raise_the_magnitude() {
+    let response = with_curve(ResponseCurve::Exponential(2.0));
+    assert_close(response.apply_axis(0.5, 0.0, AxisSign::Plus), 0.25);
+    assert_close(response.apply_axis(-0.5, 0.0, AxisSign::Minus), 0.25);
+    assert_close(response.apply_axis(1.0, 0.0, AxisSign::Plus), 1.0);
+
+    assert_close(ResponseCurve::Exponential(0.5).apply(0.25), 0.5);
+    assert_close(ResponseCurve::Exponential(0.5).apply(0.0), 0.0);
+}
+
+#[test]
+fn custom_curves_interpolate_between_their_points() {
+    let curve = ResponseCurve::Custom(vec![(0.5, 0.25)]);
+    assert_close(curve.apply(0.0), 0.0);
+    assert_close(curve.apply(0.25), 0.125);
+    assert_close(curve.apply(0.5), 0.25);
+    assert_close(curve.apply(0.75), 0.625);
+    assert_close(curve.apply(1.0), 1.0);
+
+    let response = with_curve(curve);
+    assert_close(response.apply_axis(-0.25, 0.0, AxisSign::Minus), 0.125);
+}
+
+#[test]
+fn custom_curve_points_are_sorted() {
+    let curve = ResponseCurve::Custom(vec![(0.75, 0.5), (0.25, 0.25)]);
+    assert_close(curve.apply(0.125), 0.125);
+    assert_close(curve.apply(0.5), 0.375);
+    assert_close(curve.apply(0.875), 0.75);
+}
+
+#[test]
+fn inverted_axes_flip_direction() {
+    let response = InputResponse {
+        invert: true,
+        ..Default::default()
+    };
+    assert_close(response.apply_axis(0.5, 0.0, AxisSign::Minus), 0.5);
+    assert_close(response.apply_axis(0.5, 0.0, AxisSign::Plus), 0.0);
+}
+
+#[test]
+fn thresholds_press_and_release_with_hysteresis() {
+    let response = InputResponse {
+        press_threshold: 0.5,
+        release_threshold: 0.25,
+        ..Default::default()
+    };
+
+    // Feed a trigger that's pushed in, eased off between the thresholds, then let go.
+    let mut pressed = false;
+    for (magnitude, expected) in [
+        (0.0, false),
+        (0.4, false),
+        (0.5, true),
+        (0.4, true),
+        (0.25, true),
+        (0.2, false),
+        (0.4, false),
+        (0.75, true),
+    ] {
+        pressed = response.apply_threshold(magnitude, pressed);
+        assert_eq!(pressed, expected, "at magnitude {}", magnitude);
+    }
+}
+
+#[test]
+fn release_thresholds_above_the_press_threshold_release_at_it() {
+    let response = InputResponse {
+        press_threshold: 0.5,
+        release_threshold: 0.75,
+        ..Default::default()
+    };
+
+    assert!(response.apply_threshold(0.6, true));
+    assert!(response.apply_threshold(0.5, true));
+    assert!(!response.apply_threshold(0.45, true));
+}
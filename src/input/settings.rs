@@ -0,0 +1,108 @@
+use bevy::{ecs::schedule::ShouldRun, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::util::{read_from_file, write_to_file};
+
+pub const INPUT_SETTINGS_FILE_PATH: &'static str = "input.json";
+
+pub const MIN_POLL_RATE: f32 = 10.0;
+pub const MAX_POLL_RATE: f32 = 1000.0;
+pub const MAX_LISTEN_TIMEOUT: f32 = 30.0;
+
+// The most polling steps that will be run in a single frame, so that a long frame
+// (e.g. while the window is being dragged) doesn't stall the app catching up.
+const MAX_POLL_STEPS_PER_FRAME: f64 = 8.0;
+
+// How often and how precisely input is read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputSettings {
+    // The number of times per second that input sources are polled into `InputSink`s.
+    pub poll_rate: f32,
+    // Whether button presses and releases between polls are tracked with their own timestamps,
+    // so that taps shorter than a polling step still show up on displays and in recordings.
+    pub timestamped_presses: bool,
+    // How many seconds the input listener waits for a binding before giving up.
+    // A timeout of 0 waits forever.
+    pub listen_timeout: f32,
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        InputSettings {
+            poll_rate: 60.0,
+            timestamped_presses: true,
+            listen_timeout: 5.0,
+        }
+    }
+}
+
+impl InputSettings {
+    pub fn read() -> InputSettings {
+        read_from_file::<InputSettings>(INPUT_SETTINGS_FILE_PATH).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        write_to_file(self, INPUT_SETTINGS_FILE_PATH);
+    }
+
+    // The length of a polling step in seconds.
+    pub fn poll_step(&self) -> f64 {
+        1.0 / self.poll_rate.clamp(MIN_POLL_RATE, MAX_POLL_RATE) as f64
+    }
+}
+
+// A clock for the input systems which is advanced by hand rather than following `Time`, so
+// that when input is polled doesn't depend on how long frames take. This is used by tests.
+#[derive(Debug, Default)]
+pub struct ManualInputClock {
+    pub seconds_since_startup: f64,
+}
+
+impl ManualInputClock {
+    pub fn advance(&mut self, seconds: f64) {
+        self.seconds_since_startup += seconds;
+    }
+}
+
+// The time used to poll and timestamp input: the manual clock if one was inserted,
+// or else `Time::seconds_since_startup`.
+pub fn input_time(time: &Time, clock: Option<&ManualInputClock>) -> f64 {
+    clock.map_or(time.seconds_since_startup(), |clock| {
+        clock.seconds_since_startup
+    })
+}
+
+#[derive(Debug, Default)]
+pub struct PollTimestepState {
+    accumulator: f64,
+    looping: bool,
+    last_time: Option<f64>,
+}
+
+// A run criteria running its systems once per polling step in `InputSettings`.
+// This behaves like bevy's `FixedTimestep`, except that the step can change at runtime.
+pub fn poll_input_run_criteria(
+    time: Res<Time>,
+    clock: Option<Res<ManualInputClock>>,
+    settings: Res<InputSettings>,
+    mut state: Local<PollTimestepState>,
+) -> ShouldRun {
+    let step = settings.poll_step();
+
+    if !state.looping {
+        let now = input_time(&time, clock.as_deref());
+        state.accumulator += state.last_time.map_or(0.0, |last| now - last);
+        state.accumulator = state.accumulator.min(step * MAX_POLL_STEPS_PER_FRAME);
+        state.last_time = Some(now);
+    }
+
+    if state.accumulator >= step {
+        state.accumulator -= step;
+        state.looping = true;
+        ShouldRun::YesAndCheckAgain
+    } else {
+        state.looping = false;
+        ShouldRun::No
+    }
+}
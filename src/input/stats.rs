@@ -0,0 +1,395 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use bevy::{app::AppExit, prelude::*};
+use bevy_egui::{egui, EguiContext};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    controller::layout::PlayerKey,
+    util::{read_from_file, write_to_file},
+};
+
+use super::{
+    input::{InputSink, InputValue},
+    playback::InputPlayback,
+    recording::csv_escape,
+    settings::{input_time, ManualInputClock},
+};
+
+pub const STATS_FILE_PATH: &'static str = "stats.json";
+pub const STATS_CSV_FILE_PATH: &'static str = "stats.csv";
+const STATS_WINDOW_TITLE: &'static str = "Statistics";
+const STATS_HOTKEY: KeyCode = KeyCode::F11;
+// How often the lifetime statistics are saved, in seconds.
+const STATS_SAVE_INTERVAL: f64 = 60.0;
+
+// The statistics of a single controller key.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyStats {
+    pub presses: u64,
+    // Seconds spent held, over every completed press.
+    pub total_hold: f64,
+    pub longest_hold: f64,
+    // The total change in the key's axis value. For a stick, the distance it travelled is the
+    // sum of the distances of its directions.
+    pub distance: f64,
+}
+
+impl KeyStats {
+    fn press(&mut self) {
+        self.presses += 1;
+    }
+
+    fn release(&mut self, hold: f64) {
+        self.total_hold += hold;
+        self.longest_hold = self.longest_hold.max(hold);
+    }
+}
+
+// The statistics of every controller key over some span of time.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputStats {
+    // The seconds of live input that these statistics cover.
+    pub duration: f64,
+    pub keys: Vec<(PlayerKey, KeyStats)>,
+}
+
+impl InputStats {
+    pub fn read() -> InputStats {
+        read_from_file::<InputStats>(STATS_FILE_PATH).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        write_to_file(self, STATS_FILE_PATH);
+    }
+
+    pub fn get(&self, key: PlayerKey) -> Option<&KeyStats> {
+        self.keys.iter().find(|(k, _)| *k == key).map(|(_, s)| s)
+    }
+
+    fn key_mut(&mut self, key: PlayerKey) -> &mut KeyStats {
+        match self.keys.iter().position(|(k, _)| *k == key) {
+            Some(idx) => &mut self.keys[idx].1,
+            None => {
+                self.keys.push((key, KeyStats::default()));
+                &mut self.keys.last_mut().unwrap().1
+            }
+        }
+    }
+
+    // The average number of presses of `stats` per second of input.
+    pub fn presses_per_second(&self, stats: &KeyStats) -> f64 {
+        if self.duration > 0.0 {
+            stats.presses as f64 / self.duration
+        } else {
+            0.0
+        }
+    }
+}
+
+// The state of a key between updates, which isn't saved.
+#[derive(Debug, Clone, Copy, Default)]
+struct TrackedKey {
+    // The time at which the key was last pressed, if it's still held.
+    held_since: Option<f64>,
+    last_axis: Option<f32>,
+}
+
+// The global resource with the statistics of the current session and of every session.
+#[derive(Debug, Default)]
+pub struct InputStatsRes {
+    pub session: InputStats,
+    pub lifetime: InputStats,
+    tracked: Vec<(PlayerKey, TrackedKey)>,
+    // The value of `Time::seconds_since_startup` at the last update and the last save.
+    last_update: Option<f64>,
+    last_save: f64,
+}
+
+impl InputStatsRes {
+    pub fn new(lifetime: InputStats) -> InputStatsRes {
+        InputStatsRes {
+            lifetime,
+            ..Default::default()
+        }
+    }
+
+    fn tracked_mut(&mut self, key: PlayerKey) -> &mut TrackedKey {
+        match self.tracked.iter().position(|(k, _)| *k == key) {
+            Some(idx) => &mut self.tracked[idx].1,
+            None => {
+                self.tracked.push((key, TrackedKey::default()));
+                &mut self.tracked.last_mut().unwrap().1
+            }
+        }
+    }
+
+    // Apply `f` to the statistics of `key` in both the session and lifetime statistics.
+    fn update_key(&mut self, key: PlayerKey, f: impl Fn(&mut KeyStats)) {
+        f(self.session.key_mut(key));
+        f(self.lifetime.key_mut(key));
+    }
+
+    // Record that `key` was pressed or released at time `now`.
+    pub fn record_press(&mut self, key: PlayerKey, now: f64, pressed: bool) {
+        let tracked = self.tracked_mut(key);
+        match (tracked.held_since, pressed) {
+            (None, true) => {
+                tracked.held_since = Some(now);
+                self.update_key(key, KeyStats::press);
+            }
+            (Some(since), false) => {
+                tracked.held_since = None;
+                self.update_key(key, |stats| stats.release(now - since));
+            }
+            _ => {}
+        }
+    }
+
+    // Record the axis value of `key`, adding how far it moved since the last update.
+    pub fn record_axis(&mut self, key: PlayerKey, value: f32) {
+        let tracked = self.tracked_mut(key);
+        let moved = tracked
+            .last_axis
+            .map_or(0.0, |last| (value - last).abs() as f64);
+        tracked.last_axis = Some(value);
+
+        if moved > 0.0 {
+            self.update_key(key, |stats| stats.distance += moved);
+        }
+    }
+
+    // Advance the duration of the statistics to the time `now`.
+    pub fn record_time(&mut self, now: f64) {
+        if let Some(last) = self.last_update {
+            self.session.duration += now - last;
+            self.lifetime.duration += now - last;
+        }
+        self.last_update = Some(now);
+    }
+
+    // Stop tracking time and held keys, e.g. while live input isn't being read.
+    pub fn pause(&mut self) {
+        self.last_update = None;
+        self.tracked.clear();
+    }
+
+    pub fn reset_session(&mut self) {
+        self.session = InputStats::default();
+    }
+
+    // Write one row per key of both the session and lifetime statistics.
+    pub fn export_csv(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writeln!(
+            writer,
+            "scope,key,presses,presses_per_second,total_hold,longest_hold,distance"
+        )?;
+        for (scope, stats) in [("session", &self.session), ("lifetime", &self.lifetime)] {
+            for (key, key_stats) in stats.keys.iter() {
+                writeln!(
+                    writer,
+                    "{},{},{},{:.4},{:.4},{:.4},{:.4}",
+                    scope,
+                    csv_escape(&key.to_string()),
+                    key_stats.presses,
+                    stats.presses_per_second(key_stats),
+                    key_stats.total_hold,
+                    key_stats.longest_hold,
+                    key_stats.distance,
+                )?;
+            }
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+// Update the statistics of every player key resolved by `resolve_input_sinks_system`.
+// Recorded sessions being played back aren't counted.
+pub fn update_input_stats_system(
+    time: Res<Time>,
+    clock: Option<Res<ManualInputClock>>,
+    playback: Res<InputPlayback>,
+    mut stats: ResMut<InputStatsRes>,
+    query: Query<&InputSink>,
+) {
+    if playback.is_active() {
+        stats.pause();
+        return;
+    }
+
+    let now = input_time(&time, clock.as_deref());
+    stats.record_time(now);
+
+    // Count each key once, even if several sinks listen to it.
+    let mut seen: Vec<PlayerKey> = vec![];
+    for sink in query.iter() {
+        for i in 0..sink.keys.len() {
+            let key = match sink.keys[i] {
+                Some(key) if !seen.contains(&key) => key,
+                _ => continue,
+            };
+            seen.push(key);
+
+            // Count taps between polls from the key's recorded presses, then settle on the
+            // state after them, like `record_input_system`.
+            for press in sink.timed_presses[i].iter() {
+                stats.record_press(key, press.time, press.pressed);
+            }
+            let pressed = sink.timed_presses[i]
+                .last()
+                .map_or(sink.is_pressed(i), |last| last.pressed);
+            stats.record_press(key, now, pressed);
+
+            if let Some(InputValue::Axis(value)) = sink.values[i] {
+                stats.record_axis(key, value);
+            }
+        }
+    }
+}
+
+// Save the lifetime statistics every so often, and when the app exits.
+fn save_input_stats_system(
+    time: Res<Time>,
+    mut stats: ResMut<InputStatsRes>,
+    mut er_exit: EventReader<AppExit>,
+) {
+    let now = time.seconds_since_startup();
+    let exiting = er_exit.iter().count() > 0;
+
+    if exiting || now - stats.last_save >= STATS_SAVE_INTERVAL {
+        stats.lifetime.save();
+        stats.last_save = now;
+    }
+}
+
+pub struct RequestExportStats;
+
+fn handle_request_export_stats(
+    stats: Res<InputStatsRes>,
+    mut event_reader: EventReader<RequestExportStats>,
+) {
+    for _ in event_reader.iter() {
+        if let Err(e) = stats.export_csv(STATS_CSV_FILE_PATH) {
+            println!(
+                "Error exporting input statistics to '{}': {:?}",
+                STATS_CSV_FILE_PATH, e
+            );
+        }
+    }
+}
+
+// Whether the statistics window is open, and which statistics it shows.
+#[derive(Debug, Default)]
+pub struct StatsUiState {
+    pub open: bool,
+    pub show_lifetime: bool,
+}
+
+fn format_seconds(seconds: f64) -> String {
+    if seconds >= 3600.0 {
+        format!("{:.1}h", seconds / 3600.0)
+    } else if seconds >= 60.0 {
+        format!("{:.1}m", seconds / 60.0)
+    } else {
+        format!("{:.2}s", seconds)
+    }
+}
+
+fn stats_hotkey_system(keyboard_input: Res<Input<KeyCode>>, mut ui_state: ResMut<StatsUiState>) {
+    if keyboard_input.just_pressed(STATS_HOTKEY) {
+        ui_state.open = !ui_state.open;
+    }
+}
+
+fn stats_ui_system(
+    mut egui_ctx: ResMut<EguiContext>,
+    mut ui_state: ResMut<StatsUiState>,
+    mut stats: ResMut<InputStatsRes>,
+    mut ew_export: EventWriter<RequestExportStats>,
+) {
+    if !ui_state.open {
+        return;
+    }
+
+    let mut open = true;
+    egui::Window::new(STATS_WINDOW_TITLE)
+        .open(&mut open)
+        .show(egui_ctx.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut ui_state.show_lifetime, false, "Session");
+                ui.selectable_value(&mut ui_state.show_lifetime, true, "Lifetime");
+            });
+
+            let shown = if ui_state.show_lifetime {
+                &stats.lifetime
+            } else {
+                &stats.session
+            };
+            ui.label(format!("Time: {}", format_seconds(shown.duration)));
+
+            if shown.keys.is_empty() {
+                ui.label("No input yet.");
+            } else {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("input_stats").striped(true).show(ui, |ui| {
+                        ui.label("Key");
+                        ui.label("Presses");
+                        ui.label("Presses/s");
+                        ui.label("Total hold");
+                        ui.label("Longest hold");
+                        ui.label("Distance");
+                        ui.end_row();
+
+                        for (key, key_stats) in shown.keys.iter() {
+                            ui.label(key.to_string());
+                            ui.label(key_stats.presses.to_string());
+                            ui.label(format!("{:.2}", shown.presses_per_second(key_stats)));
+                            ui.label(format_seconds(key_stats.total_hold));
+                            ui.label(format_seconds(key_stats.longest_hold));
+                            ui.label(format!("{:.2}", key_stats.distance));
+                            ui.end_row();
+                        }
+                    });
+                });
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Export CSV").clicked() {
+                    ew_export.send(RequestExportStats);
+                }
+                if ui.button("Reset session").clicked() {
+                    stats.reset_session();
+                }
+            });
+        });
+
+    ui_state.open = open;
+}
+
+// Add the resource and system keeping the statistics up to date.
+// `update_input_stats_system` is run by `add_input_systems` after input is polled.
+pub fn add_stats_systems(app: &mut App) {
+    app.insert_resource(InputStatsRes::new(InputStats::read()));
+    app.add_system(save_input_stats_system);
+
+    app.add_event::<RequestExportStats>();
+    app.add_system(handle_request_export_stats);
+}
+
+// Add the statistics window, which is toggled from any state by its hotkey.
+pub fn add_stats_ui_systems(app: &mut App) {
+    app.init_resource::<StatsUiState>();
+    app.add_system(stats_hotkey_system);
+    app.add_system(stats_ui_system);
+}
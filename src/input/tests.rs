@@ -0,0 +1,713 @@
+// Tests of the input pipeline, which run the input systems under `MinimalPlugins` without a
+// window, with keyboard input sent as bevy events and joysticks scripted by `ScriptedRawInput`.
+// Input is polled and timestamped by a `ManualInputClock`, which only moves when `step` does.
+
+use std::time::{Duration, SystemTime};
+
+use bevy::{
+    input::{keyboard::KeyboardInput, mouse::MouseButtonInput, ElementState, InputPlugin},
+    prelude::*,
+};
+
+use crate::{
+    controller::layout::{ControllerKey, ControllerLayoutsRes, PlayerKey, Ps2Key, SourceChord},
+    editor::inspector::InputSinkId,
+};
+
+use super::{
+    calibration::AxisRange,
+    device::{DeviceIdentity, MISSING_HID_ID},
+    events::event_input_time,
+    global_keyboard::{GlobalKeyboardRes, NoopKeyboardBackend},
+    input::{
+        add_input_systems, AxisSign, HidAxisId, HidHatSwitchId, InputSink, InputSource, InputValue,
+    },
+    listener::{InputListener, ListenerResult},
+    playback::InputPlayback,
+    raw_input::{
+        scripted::{ScriptedEvent, ScriptedRawInput},
+        RawInputReader, RawInputRes,
+    },
+    settings::{InputSettings, ManualInputClock},
+    stats::InputStatsRes,
+};
+
+// A power of two, so that the clock advances by exact steps and polls once per `step`.
+const TEST_POLL_RATE: f32 = 128.0;
+
+// Build an app running the input systems without a window, reading joysticks from
+// `raw_input` and bindings from `layouts`.
+fn headless_input_app(raw_input: &ScriptedRawInput, mut layouts: ControllerLayoutsRes) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).add_plugin(InputPlugin);
+
+    // These are inserted before the input systems so that their defaults, which read the
+    // real devices, aren't.
+    app.insert_non_send_resource(RawInputRes::with_reader(raw_input.clone()));
+
+    // Bind the scripted joysticks by identity, like layouts saved by this version, so that
+    // migrating legacy devices doesn't overwrite the layouts file.
+    layouts.migrate_legacy_devices(&raw_input.device_identities());
+    app.insert_non_send_resource(GlobalKeyboardRes::with_backend(
+        Default::default(),
+        Box::new(NoopKeyboardBackend),
+    ));
+    app.insert_resource(layouts);
+    app.insert_resource(InputListener::default());
+    app.insert_resource(InputPlayback::default());
+    add_input_systems(&mut app);
+
+    // Don't depend on the settings or statistics files of whoever runs the tests.
+    app.insert_resource(InputSettings {
+        poll_rate: TEST_POLL_RATE,
+        ..Default::default()
+    });
+    app.insert_resource(InputStatsRes::default());
+    app.insert_resource(ManualInputClock::default());
+
+    // The first update runs startup systems and starts the clock.
+    app.update();
+    app
+}
+
+// Run one frame, after advancing the clock far enough for the input to be polled during it.
+fn step(app: &mut App) {
+    let poll_step = app
+        .world
+        .get_resource::<InputSettings>()
+        .unwrap()
+        .poll_step();
+    app.world
+        .get_resource_mut::<ManualInputClock>()
+        .unwrap()
+        .advance(poll_step);
+    app.update();
+}
+
+fn send_key(app: &mut App, key: KeyCode, state: ElementState) {
+    let mut events = app
+        .world
+        .get_resource_mut::<Events<KeyboardInput>>()
+        .unwrap();
+    events.send(KeyboardInput {
+        scan_code: 0,
+        key_code: Some(key),
+        state,
+    });
+}
+
+fn press_key(app: &mut App, key: KeyCode) {
+    send_key(app, key, ElementState::Pressed);
+    step(app);
+}
+
+fn release_key(app: &mut App, key: KeyCode) {
+    send_key(app, key, ElementState::Released);
+    step(app);
+}
+
+fn ps2_key(key: Ps2Key) -> PlayerKey {
+    PlayerKey::new(0, ControllerKey::Ps2(key))
+}
+
+fn layouts_with(bindings: &[(Ps2Key, InputSource)]) -> ControllerLayoutsRes {
+    let mut layouts = ControllerLayoutsRes::default();
+    for &(key, source) in bindings {
+        layouts.set_chord(ps2_key(key), None, source.into());
+    }
+    layouts
+}
+
+fn spawn_sink(app: &mut App, keys: &[Ps2Key]) -> Entity {
+    let keys = keys.iter().map(|&key| Some(ps2_key(key))).collect();
+    app.world.spawn().insert(InputSink::new(keys)).id()
+}
+
+fn sink(app: &App, entity: Entity) -> &InputSink {
+    app.world.get::<InputSink>(entity).unwrap()
+}
+
+fn listener(app: &mut App) -> Mut<InputListener> {
+    app.world.get_resource_mut::<InputListener>().unwrap()
+}
+
+// The listener results sent during the last two frames.
+fn listener_results(app: &App) -> Vec<ListenerResult> {
+    let events = app.world.get_resource::<Events<ListenerResult>>().unwrap();
+    events.get_reader().iter(events).cloned().collect()
+}
+
+#[test]
+fn key_binding_is_pressed_while_held() {
+    let raw_input = ScriptedRawInput::default();
+    let layouts = layouts_with(&[(Ps2Key::Cross, InputSource::Key(KeyCode::A))]);
+    let mut app = headless_input_app(&raw_input, layouts);
+    let entity = spawn_sink(&mut app, &[Ps2Key::Cross]);
+
+    step(&mut app);
+    assert!(!sink(&app, entity).is_pressed(0));
+
+    press_key(&mut app, KeyCode::A);
+    assert_eq!(sink(&app, entity).values[0], Some(InputValue::Button(true)));
+    assert!(sink(&app, entity).is_pressed(0));
+
+    release_key(&mut app, KeyCode::A);
+    assert!(!sink(&app, entity).is_pressed(0));
+}
+
+#[test]
+fn chords_need_every_source_and_any_chord() {
+    let raw_input = ScriptedRawInput::default();
+    let mut layouts = ControllerLayoutsRes::default();
+    let chord = SourceChord {
+        sources: vec![InputSource::Key(KeyCode::A), InputSource::Key(KeyCode::B)],
+    };
+    layouts.set_chord(ps2_key(Ps2Key::Start), None, chord);
+    layouts.set_chord(
+        ps2_key(Ps2Key::Start),
+        None,
+        InputSource::Key(KeyCode::C).into(),
+    );
+
+    let mut app = headless_input_app(&raw_input, layouts);
+    let entity = spawn_sink(&mut app, &[Ps2Key::Start]);
+
+    press_key(&mut app, KeyCode::A);
+    assert!(!sink(&app, entity).is_pressed(0));
+
+    press_key(&mut app, KeyCode::B);
+    assert!(sink(&app, entity).is_pressed(0));
+
+    release_key(&mut app, KeyCode::A);
+    release_key(&mut app, KeyCode::B);
+    assert!(!sink(&app, entity).is_pressed(0));
+
+    press_key(&mut app, KeyCode::C);
+    assert!(sink(&app, entity).is_pressed(0));
+}
+
+#[test]
+fn hid_sources_read_the_scripted_joystick() {
+    let raw_input = ScriptedRawInput::default();
+    let id = raw_input.connect("Pad", 4);
+    let layouts = layouts_with(&[
+        (Ps2Key::Circle, InputSource::HidButton(id, 2)),
+        (
+            Ps2Key::LeftPosX,
+            InputSource::HidAxis(id, HidAxisId::X, AxisSign::Plus),
+        ),
+        (
+            Ps2Key::LeftNegX,
+            InputSource::HidAxis(id, HidAxisId::X, AxisSign::Minus),
+        ),
+    ]);
+    let mut app = headless_input_app(&raw_input, layouts);
+    let entity = spawn_sink(
+        &mut app,
+        &[Ps2Key::Circle, Ps2Key::LeftPosX, Ps2Key::LeftNegX],
+    );
+
+    raw_input.push(ScriptedEvent::Button(id, 2, true));
+    raw_input.push(ScriptedEvent::Axis(id, HidAxisId::X, -0.75));
+    step(&mut app);
+
+    let sink = sink(&app, entity);
+    assert!(sink.is_pressed(0));
+    assert_eq!(sink.axis_value(1), 0.0);
+    assert!((sink.axis_value(2) - 0.75).abs() < 1e-6);
+}
+
+#[test]
+fn calibration_only_applies_to_the_primary_source() {
+    let raw_input = ScriptedRawInput::default();
+    let id = raw_input.connect("Pad", 0);
+    let primary = InputSource::HidAxis(id, HidAxisId::X, AxisSign::Plus);
+    let secondary = InputSource::HidAxis(id, HidAxisId::RX, AxisSign::Plus);
+
+    let mut layouts = layouts_with(&[(Ps2Key::L2, primary), (Ps2Key::R2, secondary)]);
+    layouts.set_chord(ps2_key(Ps2Key::L2), None, secondary.into());
+    layouts.set_axis_range(
+        ps2_key(Ps2Key::L2),
+        &primary.into(),
+        AxisRange::Full { rest: -1.0 },
+    );
+    let mut app = headless_input_app(&raw_input, layouts);
+    let entity = spawn_sink(&mut app, &[Ps2Key::L2, Ps2Key::R2]);
+
+    raw_input.push(ScriptedEvent::Axis(id, HidAxisId::X, -1.0));
+    raw_input.push(ScriptedEvent::Axis(id, HidAxisId::RX, 0.5));
+    step(&mut app);
+
+    // The primary source rests at 0.0 once calibrated, so `L2` reads the uncalibrated `RX`.
+    let sink = sink(&app, entity);
+    assert!(sink.axis_value(1) > 0.0);
+    assert_eq!(sink.axis_value(0), sink.axis_value(1));
+}
+
+#[test]
+fn saved_devices_are_resolved_to_runtime_ids() {
+    let raw_input = ScriptedRawInput::default();
+    raw_input.connect("Pad A", 1);
+    let pad_b = raw_input.connect("Pad B", 1);
+
+    // Saved HID ids index into the layout's devices, so id 0 refers to "Pad B".
+    let mut layouts = layouts_with(&[(Ps2Key::Cross, InputSource::HidButton(0, 0))]);
+    layouts.devices = vec![DeviceIdentity::named("Pad B")];
+
+    let mut app = headless_input_app(&raw_input, layouts);
+    let entity = spawn_sink(&mut app, &[Ps2Key::Cross]);
+    step(&mut app);
+
+    let binding = sink(&app, entity).bindings[0].as_ref().unwrap();
+    assert_eq!(
+        binding.primary_source(),
+        Some(&InputSource::HidButton(pad_b, 0))
+    );
+
+    raw_input.push(ScriptedEvent::Button(0, 0, true));
+    step(&mut app);
+    assert!(!sink(&app, entity).is_pressed(0));
+
+    raw_input.push(ScriptedEvent::Button(pad_b, 0, true));
+    step(&mut app);
+    assert!(sink(&app, entity).is_pressed(0));
+}
+
+#[test]
+fn disconnected_devices_are_never_pressed() {
+    let raw_input = ScriptedRawInput::default();
+    let pad_a = raw_input.connect("Pad A", 1);
+
+    let mut layouts = layouts_with(&[(Ps2Key::Cross, InputSource::HidButton(0, 0))]);
+    layouts.devices = vec![DeviceIdentity::named("Pad B")];
+
+    let mut app = headless_input_app(&raw_input, layouts);
+    let entity = spawn_sink(&mut app, &[Ps2Key::Cross]);
+
+    raw_input.push(ScriptedEvent::Button(pad_a, 0, true));
+    step(&mut app);
+
+    let sink = sink(&app, entity);
+    let binding = sink.bindings[0].as_ref().unwrap();
+    assert_eq!(
+        binding.primary_source(),
+        Some(&InputSource::HidButton(MISSING_HID_ID, 0))
+    );
+    assert!(!sink.is_pressed(0));
+}
+
+// Send `events` within a single frame, then return the key presses of the sink's first key
+// at the next poll.
+fn presses_between_polls(
+    app: &mut App,
+    entity: Entity,
+    events: &[(KeyCode, ElementState)],
+) -> Vec<bool> {
+    step(app);
+
+    // The clock doesn't move during this frame, so the events land between two polls.
+    for &(key, state) in events {
+        send_key(app, key, state);
+    }
+    app.update();
+
+    step(app);
+    sink(app, entity).timed_presses[0]
+        .iter()
+        .map(|p| p.pressed)
+        .collect()
+}
+
+#[test]
+fn taps_between_polls_are_kept() {
+    let raw_input = ScriptedRawInput::default();
+    let layouts = layouts_with(&[(Ps2Key::Cross, InputSource::Key(KeyCode::A))]);
+    let mut app = headless_input_app(&raw_input, layouts);
+    let entity = spawn_sink(&mut app, &[Ps2Key::Cross]);
+
+    // Tap the key within a single frame, well before the next poll.
+    let presses = presses_between_polls(
+        &mut app,
+        entity,
+        &[
+            (KeyCode::A, ElementState::Pressed),
+            (KeyCode::A, ElementState::Released),
+        ],
+    );
+    assert_eq!(sink(&app, entity).values[0], Some(InputValue::Button(true)));
+    assert_eq!(presses, vec![true, false]);
+}
+
+#[test]
+fn joystick_taps_between_polls_are_kept() {
+    let raw_input = ScriptedRawInput::default();
+    let id = raw_input.connect("Pad", 4);
+    let layouts = layouts_with(&[
+        (Ps2Key::Cross, InputSource::HidButton(id, 0)),
+        (
+            Ps2Key::PadU,
+            InputSource::HidHatSwitch(id, HidHatSwitchId::Up),
+        ),
+    ]);
+    let mut app = headless_input_app(&raw_input, layouts);
+    let entity = spawn_sink(&mut app, &[Ps2Key::Cross, Ps2Key::PadU]);
+    step(&mut app);
+
+    // Tap the button and the hat within a single frame, well before the next poll.
+    raw_input.push(ScriptedEvent::Button(id, 0, true));
+    raw_input.push(ScriptedEvent::Hat(id, Some(HidHatSwitchId::Up)));
+    raw_input.push(ScriptedEvent::Button(id, 0, false));
+    raw_input.push(ScriptedEvent::Hat(id, None));
+    app.update();
+    step(&mut app);
+
+    let sink = sink(&app, entity);
+    for i in 0..2 {
+        assert_eq!(sink.values[i], Some(InputValue::Button(true)));
+        let presses: Vec<bool> = sink.timed_presses[i].iter().map(|p| p.pressed).collect();
+        assert_eq!(presses, vec![true, false]);
+    }
+}
+
+#[test]
+fn device_event_times_are_converted_to_input_time() {
+    let wall_now = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+
+    let earlier = wall_now - Duration::from_millis(250);
+    assert_eq!(event_input_time(10.0, wall_now, earlier), 9.75);
+
+    // Events from the future, e.g. after the wall clock was set back, happen now.
+    let later = wall_now + Duration::from_secs(1);
+    assert_eq!(event_input_time(10.0, wall_now, later), 10.0);
+}
+
+#[test]
+fn chord_sources_alone_dont_press_between_polls() {
+    let raw_input = ScriptedRawInput::default();
+    let mut layouts = ControllerLayoutsRes::default();
+    let chord = SourceChord {
+        sources: vec![
+            InputSource::Key(KeyCode::LShift),
+            InputSource::Key(KeyCode::Z),
+        ],
+    };
+    layouts.set_chord(ps2_key(Ps2Key::Cross), None, chord);
+    let mut app = headless_input_app(&raw_input, layouts);
+    let entity = spawn_sink(&mut app, &[Ps2Key::Cross]);
+
+    let presses = presses_between_polls(
+        &mut app,
+        entity,
+        &[
+            (KeyCode::LShift, ElementState::Pressed),
+            (KeyCode::LShift, ElementState::Released),
+        ],
+    );
+    assert!(presses.is_empty());
+    assert!(!sink(&app, entity).is_pressed(0));
+}
+
+#[test]
+fn tapping_a_second_chord_keeps_a_held_key_pressed() {
+    let raw_input = ScriptedRawInput::default();
+    let mut layouts = layouts_with(&[(Ps2Key::Cross, InputSource::Key(KeyCode::Z))]);
+    layouts.set_chord(
+        ps2_key(Ps2Key::Cross),
+        None,
+        InputSource::Key(KeyCode::X).into(),
+    );
+    let mut app = headless_input_app(&raw_input, layouts);
+    let entity = spawn_sink(&mut app, &[Ps2Key::Cross]);
+
+    press_key(&mut app, KeyCode::Z);
+    let presses = presses_between_polls(
+        &mut app,
+        entity,
+        &[
+            (KeyCode::X, ElementState::Pressed),
+            (KeyCode::X, ElementState::Released),
+        ],
+    );
+    assert!(presses.is_empty());
+    assert!(sink(&app, entity).is_pressed(0));
+}
+
+#[test]
+fn inverted_taps_release_the_key() {
+    let raw_input = ScriptedRawInput::default();
+    let mut layouts = layouts_with(&[(Ps2Key::Cross, InputSource::Key(KeyCode::A))]);
+    layouts
+        .get_binding_mut(ps2_key(Ps2Key::Cross))
+        .unwrap()
+        .response
+        .invert = true;
+    let mut app = headless_input_app(&raw_input, layouts);
+    let entity = spawn_sink(&mut app, &[Ps2Key::Cross]);
+
+    let presses = presses_between_polls(
+        &mut app,
+        entity,
+        &[
+            (KeyCode::A, ElementState::Pressed),
+            (KeyCode::A, ElementState::Released),
+        ],
+    );
+    assert_eq!(presses, vec![false, true]);
+}
+
+#[test]
+fn stats_count_presses_holds_and_axis_distance() {
+    let raw_input = ScriptedRawInput::default();
+    let id = raw_input.connect("Pad", 4);
+    let layouts = layouts_with(&[
+        (Ps2Key::Cross, InputSource::Key(KeyCode::A)),
+        (
+            Ps2Key::LeftPosX,
+            InputSource::HidAxis(id, HidAxisId::X, AxisSign::Plus),
+        ),
+    ]);
+    let mut app = headless_input_app(&raw_input, layouts);
+    spawn_sink(&mut app, &[Ps2Key::Cross, Ps2Key::LeftPosX]);
+    step(&mut app);
+
+    press_key(&mut app, KeyCode::A);
+    step(&mut app);
+    release_key(&mut app, KeyCode::A);
+    press_key(&mut app, KeyCode::A);
+    release_key(&mut app, KeyCode::A);
+
+    raw_input.push(ScriptedEvent::Axis(id, HidAxisId::X, 1.0));
+    step(&mut app);
+    raw_input.push(ScriptedEvent::Axis(id, HidAxisId::X, 0.0));
+    step(&mut app);
+
+    let stats = app.world.get_resource::<InputStatsRes>().unwrap();
+    let cross = stats.session.get(ps2_key(Ps2Key::Cross)).unwrap();
+    assert_eq!(cross.presses, 2);
+    assert!(cross.longest_hold > 0.0);
+    assert!(cross.total_hold >= cross.longest_hold);
+
+    let stick = stats.session.get(ps2_key(Ps2Key::LeftPosX)).unwrap();
+    assert_eq!(stick.presses, 1);
+    assert!(stick.distance > 1.0);
+    assert!(stats.session.duration > 0.0);
+}
+
+#[test]
+fn stats_count_key_presses_rather_than_source_presses() {
+    let raw_input = ScriptedRawInput::default();
+    let mut layouts = layouts_with(&[(Ps2Key::Cross, InputSource::Key(KeyCode::Z))]);
+    layouts.set_chord(
+        ps2_key(Ps2Key::Cross),
+        None,
+        InputSource::Key(KeyCode::X).into(),
+    );
+    let mut app = headless_input_app(&raw_input, layouts);
+    let entity = spawn_sink(&mut app, &[Ps2Key::Cross]);
+
+    press_key(&mut app, KeyCode::Z);
+    presses_between_polls(
+        &mut app,
+        entity,
+        &[
+            (KeyCode::X, ElementState::Pressed),
+            (KeyCode::X, ElementState::Released),
+        ],
+    );
+    step(&mut app);
+
+    let stats = app.world.get_resource::<InputStatsRes>().unwrap();
+    let cross = stats.session.get(ps2_key(Ps2Key::Cross)).unwrap();
+    assert_eq!(cross.presses, 1);
+    assert_eq!(cross.total_hold, 0.0);
+}
+
+#[test]
+fn listener_hears_a_chord_once_a_source_is_released() {
+    let raw_input = ScriptedRawInput::default();
+    let mut app = headless_input_app(&raw_input, ControllerLayoutsRes::default());
+
+    listener(&mut app).listen_input_source(ps2_key(Ps2Key::Start), None);
+    step(&mut app);
+
+    press_key(&mut app, KeyCode::LShift);
+    press_key(&mut app, KeyCode::S);
+    assert_eq!(
+        listener(&mut app).partial_chord(),
+        &[
+            InputSource::Key(KeyCode::LShift),
+            InputSource::Key(KeyCode::S)
+        ]
+    );
+    assert!(listener_results(&app).is_empty());
+
+    release_key(&mut app, KeyCode::S);
+    let chord = SourceChord {
+        sources: vec![
+            InputSource::Key(KeyCode::LShift),
+            InputSource::Key(KeyCode::S),
+        ],
+    };
+    assert_eq!(
+        listener_results(&app),
+        vec![ListenerResult::SourceToKey(
+            chord,
+            ps2_key(Ps2Key::Start),
+            None,
+            None
+        )]
+    );
+}
+
+#[test]
+fn listener_is_cancelled_by_escape() {
+    let raw_input = ScriptedRawInput::default();
+    let mut app = headless_input_app(&raw_input, ControllerLayoutsRes::default());
+
+    listener(&mut app).listen_input_source(ps2_key(Ps2Key::Start), None);
+    step(&mut app);
+
+    press_key(&mut app, KeyCode::Escape);
+    assert!(!listener(&mut app).listening_for_input_source());
+
+    release_key(&mut app, KeyCode::Escape);
+    assert!(listener_results(&app).is_empty());
+}
+
+#[test]
+fn right_clicks_are_bound_rather_than_cancelling() {
+    let raw_input = ScriptedRawInput::default();
+    let mut app = headless_input_app(&raw_input, ControllerLayoutsRes::default());
+
+    listener(&mut app).listen_input_source(ps2_key(Ps2Key::Start), None);
+    step(&mut app);
+
+    for state in [ElementState::Pressed, ElementState::Released] {
+        let mut events = app
+            .world
+            .get_resource_mut::<Events<MouseButtonInput>>()
+            .unwrap();
+        events.send(MouseButtonInput {
+            button: MouseButton::Right,
+            state,
+        });
+        step(&mut app);
+    }
+
+    assert_eq!(
+        listener_results(&app),
+        vec![ListenerResult::SourceToKey(
+            InputSource::MouseButton(MouseButton::Right).into(),
+            ps2_key(Ps2Key::Start),
+            None,
+            None
+        )]
+    );
+}
+
+#[test]
+fn listener_ignores_joystick_buttons_held_when_it_starts() {
+    let raw_input = ScriptedRawInput::default();
+    let id = raw_input.connect("Pad", 2);
+    let mut app = headless_input_app(&raw_input, ControllerLayoutsRes::default());
+
+    raw_input.push(ScriptedEvent::Button(id, 0, true));
+    raw_input.push(ScriptedEvent::Hat(id, Some(HidHatSwitchId::Up)));
+    step(&mut app);
+
+    listener(&mut app).listen_input_source(ps2_key(Ps2Key::Start), None);
+    step(&mut app);
+    step(&mut app);
+    assert!(listener(&mut app).partial_chord().is_empty());
+
+    // Once released, the held sources can be heard again.
+    raw_input.push(ScriptedEvent::Button(id, 0, false));
+    raw_input.push(ScriptedEvent::Hat(id, None));
+    step(&mut app);
+    raw_input.push(ScriptedEvent::Button(id, 0, true));
+    step(&mut app);
+    assert_eq!(
+        listener(&mut app).partial_chord(),
+        &[InputSource::HidButton(id, 0)]
+    );
+}
+
+#[test]
+fn listener_ignores_state_hotkeys() {
+    let raw_input = ScriptedRawInput::default();
+    let mut app = headless_input_app(&raw_input, ControllerLayoutsRes::default());
+
+    listener(&mut app).listen_input_source(ps2_key(Ps2Key::Start), Some(0));
+    step(&mut app);
+
+    press_key(&mut app, KeyCode::F3);
+    release_key(&mut app, KeyCode::F3);
+    assert!(listener(&mut app).partial_chord().is_empty());
+
+    press_key(&mut app, KeyCode::Return);
+    release_key(&mut app, KeyCode::Return);
+    assert_eq!(
+        listener_results(&app),
+        vec![ListenerResult::SourceToKey(
+            InputSource::Key(KeyCode::Return).into(),
+            ps2_key(Ps2Key::Start),
+            Some(0),
+            None
+        )]
+    );
+}
+
+#[test]
+fn listener_hears_triggers_from_their_rest() {
+    let raw_input = ScriptedRawInput::default();
+    let id = raw_input.connect("Pad", 0);
+    let mut app = headless_input_app(&raw_input, ControllerLayoutsRes::default());
+
+    // The trigger rests at one end of its range, which shouldn't be heard.
+    raw_input.push(ScriptedEvent::Axis(id, HidAxisId::Z, -1.0));
+    step(&mut app);
+
+    listener(&mut app).listen_input_source(ps2_key(Ps2Key::R2), None);
+    step(&mut app);
+    step(&mut app);
+    assert!(listener(&mut app).partial_chord().is_empty());
+
+    raw_input.push(ScriptedEvent::Axis(id, HidAxisId::Z, 1.0));
+    step(&mut app);
+    raw_input.push(ScriptedEvent::Axis(id, HidAxisId::Z, -1.0));
+    step(&mut app);
+
+    assert_eq!(
+        listener_results(&app),
+        vec![ListenerResult::SourceToKey(
+            InputSource::HidAxis(id, HidAxisId::Z, AxisSign::Plus).into(),
+            ps2_key(Ps2Key::R2),
+            None,
+            Some(AxisRange::Full { rest: -1.0 })
+        )]
+    );
+}
+
+#[test]
+fn listener_hears_bound_keys_for_sinks() {
+    let raw_input = ScriptedRawInput::default();
+    let layouts = layouts_with(&[(Ps2Key::Triangle, InputSource::Key(KeyCode::W))]);
+    let mut app = headless_input_app(&raw_input, layouts);
+    let entity = spawn_sink(&mut app, &[Ps2Key::Cross]);
+    let sink_id = InputSinkId {
+        entity: Some(entity),
+        idx: 0,
+    };
+
+    listener(&mut app).listen_for_controller_key(sink_id);
+    step(&mut app);
+
+    // Unbound keys aren't heard.
+    press_key(&mut app, KeyCode::Q);
+    assert!(listener(&mut app).result.is_none());
+
+    press_key(&mut app, KeyCode::W);
+    let result = ListenerResult::KeyToSink(ps2_key(Ps2Key::Triangle), sink_id);
+    assert_eq!(listener(&mut app).consume_result(), Some(result.clone()));
+    assert_eq!(listener_results(&app), vec![result]);
+}
@@ -17,15 +17,24 @@ use display::{
     button::RootButtonMarker,
     display::{InputDisplay, RootAtomicDisplayMarker},
     frame::RootFrameMarker,
+    input_history::{InputHistory, RootInputHistoryMarker},
+    label::RootLabelMarker,
+    mouse::RootMouseMarker,
     present::add_present_systems,
+    socd::SocdState,
     system::add_display_systems,
     test::{
         clear_display_hotkey, inject_debug_display, inject_debug_display_hotkey,
         save_display_hotkey,
     },
+    trigger::RootTriggerMarker,
 };
 use editor::system::add_editor_systems;
-use input::input::{add_input_systems, InputSink};
+use input::{
+    input::{add_input_systems, InputSink},
+    playback::add_playback_systems,
+    stats::add_stats_ui_systems,
+};
 
 mod controller;
 mod display;
@@ -75,6 +84,7 @@ fn main() {
         ignore_components: [
             TypeId::of::<GlobalTransform>(),
             TypeId::of::<Children>(),
+            TypeId::of::<InputHistory>(),
             TypeId::of::<InputSink>(),
             TypeId::of::<Name>(),
             TypeId::of::<RootAnalogStickMarker>(),
@@ -86,6 +96,11 @@ fn main() {
             TypeId::of::<DrawMode>(),
             TypeId::of::<RootButtonMarker>(),
             TypeId::of::<RootFrameMarker>(),
+            TypeId::of::<RootInputHistoryMarker>(),
+            TypeId::of::<RootLabelMarker>(),
+            TypeId::of::<RootMouseMarker>(),
+            TypeId::of::<SocdState>(),
+            TypeId::of::<RootTriggerMarker>(),
         ]
         .iter()
         .copied()
@@ -102,8 +117,10 @@ fn main() {
     add_input_systems(&mut app);
     add_display_systems(&mut app);
     add_present_systems(&mut app, AppState::Present);
+    add_playback_systems(&mut app, AppState::Playback);
     add_controller_systems(&mut app, AppState::ConfigureController);
     add_editor_systems(&mut app, AppState::Editor);
+    add_stats_ui_systems(&mut app);
 
     // automatically inject the debug display for release, since that's the main use case
     app.add_startup_system(inject_debug_display);
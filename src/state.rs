@@ -11,6 +11,8 @@ pub enum AppState {
     ConfigureController,
     // Present an input display
     Present,
+    // Drive an input display from a recorded input session
+    Playback,
 }
 
 const INITIAL_STATE: AppState = AppState::Editor;
@@ -23,7 +25,7 @@ struct AppStateParams {
 
 pub struct RequestStateEvent(AppState);
 
-const STATE_PARAMS: [AppStateParams; 3] = [
+const STATE_PARAMS: [AppStateParams; 4] = [
     AppStateParams {
         hotkey: KeyCode::F2,
         state: AppState::Editor,
@@ -39,6 +41,11 @@ const STATE_PARAMS: [AppStateParams; 3] = [
         state: AppState::Present,
         title: "present",
     },
+    AppStateParams {
+        hotkey: KeyCode::F5,
+        state: AppState::Playback,
+        title: "playback",
+    },
 ];
 
 fn find_params(state: AppState) -> &'static AppStateParams {
@@ -48,6 +55,11 @@ fn find_params(state: AppState) -> &'static AppStateParams {
     }
 }
 
+// Returns `true` if `key` switches between app states.
+pub fn is_state_hotkey(key: KeyCode) -> bool {
+    STATE_PARAMS.iter().any(|p| p.hotkey == key)
+}
+
 // Contains behavior that should occur every time a state is entered.
 fn generic_state_transition(
     state: AppState,
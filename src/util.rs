@@ -26,6 +26,20 @@ pub fn invert_color(color: Color) -> Color {
     }
 }
 
+// Linearly interpolate between the colors `a` and `b`, where `t` = 0.0 is `a` and 1.0 is `b`.
+pub fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    let [ar, ag, ab, aa] = a.as_rgba_f32();
+    let [br, bg, bb, ba] = b.as_rgba_f32();
+    let t = t.clamp(0.0, 1.0);
+
+    Color::rgba(
+        ar + (br - ar) * t,
+        ag + (bg - ag) * t,
+        ab + (bb - ab) * t,
+        aa + (ba - aa) * t,
+    )
+}
+
 // Write a struct of type `T` to the file at `path`.
 pub fn write_to_file<T>(data: &T, path: &str)
 where